@@ -0,0 +1,125 @@
+use crate::artifact::internal_artifact::{ArtifactStatKey, InternalArtifact};
+use crate::artifact::rolls::{max_level, max_rolls, roll_combinations};
+use crate::artifact::rules::initial_substats;
+
+// Roll tiers as percentages of the highest roll, indexed like `roll_combinations`
+pub const TIER_PERCENTAGES: [u32; 4] = [70, 80, 90, 100];
+
+#[derive(Debug, Clone)]
+pub struct SubstatAnalysis {
+    pub key: ArtifactStatKey,
+    pub value: f64,
+    // roll counts that fit the rest of the artifact, ascending
+    pub possible_rolls: Vec<u32>,
+    // None when more than one count fits
+    pub rolls: Option<u32>,
+    // tier combinations reaching the value with one of the possible counts
+    pub tiers: Vec<Vec<u32>>,
+}
+
+// How the substats of an artifact came to be. Every upgrade adds exactly one roll, either to
+// an existing substat or as a new one, so the rolls add up to the initial substat count
+// plus the upgrades done.
+#[derive(Debug, Clone)]
+pub struct ArtifactAnalysis {
+    // None when both 3 and 4 (for 5 star) initial substats fit
+    pub initial_substats: Option<u32>,
+    pub upgrades_done: u32,
+    pub upgrades_remaining: u32,
+    // false when no split of the rolls explains every value, e.g. a misread substat or an
+    // artifact below 3 star which has no roll tables
+    pub consistent: bool,
+    pub substats: Vec<SubstatAnalysis>,
+}
+
+impl ArtifactAnalysis {
+    pub fn from_artifact(art: &InternalArtifact) -> ArtifactAnalysis {
+        let cap = max_level(art.rarity);
+        let level = art.level.min(cap);
+        let upgrades_done = level / 4;
+        let upgrades_remaining = cap / 4 - upgrades_done;
+
+        let subs = art.substats();
+        let combinations: Vec<Vec<Vec<usize>>> = subs
+            .iter()
+            .map(|s| roll_combinations(&s.key, art.rarity, max_rolls(art.rarity, level), s.value))
+            .collect();
+        let counts: Vec<Vec<u32>> = combinations
+            .iter()
+            .map(|c| {
+                let mut v: Vec<u32> = c.iter().map(|x| x.len() as u32).collect();
+                v.dedup();
+                v
+            })
+            .collect();
+
+        // every split of the rolls among the substats that some initial substat count explains
+        let (init_min, init_max) = initial_substats(art.rarity);
+        let mut assignments: Vec<(u32, Vec<u32>)> = Vec::new();
+        let mut stack: Vec<Vec<u32>> = vec![Vec::new()];
+        while let Some(a) = stack.pop() {
+            if a.len() < counts.len() {
+                for &c in counts[a.len()].iter() {
+                    let mut next = a.clone();
+                    next.push(c);
+                    stack.push(next);
+                }
+                continue;
+            }
+            let total: u32 = a.iter().sum();
+            if total < upgrades_done {
+                continue;
+            }
+            let initial = total - upgrades_done;
+            if initial >= init_min
+                && initial <= init_max
+                && (initial + upgrades_done).min(4) == subs.len() as u32
+            {
+                assignments.push((initial, a));
+            }
+        }
+
+        let mut initials: Vec<u32> = assignments.iter().map(|x| x.0).collect();
+        initials.sort();
+        initials.dedup();
+
+        let substats = subs
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let mut possible_rolls: Vec<u32> = assignments.iter().map(|x| x.1[i]).collect();
+                possible_rolls.sort();
+                possible_rolls.dedup();
+                let tiers = combinations[i]
+                    .iter()
+                    .filter(|c| possible_rolls.contains(&(c.len() as u32)))
+                    .map(|c| c.iter().map(|&t| TIER_PERCENTAGES[t]).collect())
+                    .collect();
+
+                SubstatAnalysis {
+                    key: s.key.clone(),
+                    value: s.value,
+                    rolls: if possible_rolls.len() == 1 {
+                        Some(possible_rolls[0])
+                    } else {
+                        None
+                    },
+                    possible_rolls,
+                    tiers,
+                }
+            })
+            .collect();
+
+        ArtifactAnalysis {
+            initial_substats: if initials.len() == 1 {
+                Some(initials[0])
+            } else {
+                None
+            },
+            upgrades_done,
+            upgrades_remaining,
+            consistent: !assignments.is_empty(),
+            substats,
+        }
+    }
+}
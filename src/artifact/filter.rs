@@ -0,0 +1,604 @@
+use crate::artifact::fuzzy::weighted_distance;
+use crate::artifact::game_data::game_data;
+use crate::artifact::internal_artifact::{
+    ArtifactSetKey, ArtifactSlotKey, ArtifactStat, ArtifactStatKey, CharacterKey, InternalArtifact,
+};
+use crate::artifact::score::{crit_value, roll_value};
+
+const STATS: [ArtifactStatKey; 18] = [
+    ArtifactStatKey::HealingBonus,
+    ArtifactStatKey::CriticalDamage,
+    ArtifactStatKey::Critical,
+    ArtifactStatKey::Atk,
+    ArtifactStatKey::AtkPercentage,
+    ArtifactStatKey::ElementalMastery,
+    ArtifactStatKey::Recharge,
+    ArtifactStatKey::HpPercentage,
+    ArtifactStatKey::Hp,
+    ArtifactStatKey::DefPercentage,
+    ArtifactStatKey::Def,
+    ArtifactStatKey::ElectroBonus,
+    ArtifactStatKey::PyroBonus,
+    ArtifactStatKey::HydroBonus,
+    ArtifactStatKey::CryoBonus,
+    ArtifactStatKey::AnemoBonus,
+    ArtifactStatKey::GeoBonus,
+    ArtifactStatKey::PhysicalBonus,
+];
+
+const SLOTS: [ArtifactSlotKey; 5] = [
+    ArtifactSlotKey::Flower,
+    ArtifactSlotKey::Plume,
+    ArtifactSlotKey::Sands,
+    ArtifactSlotKey::Goblet,
+    ArtifactSlotKey::Circlet,
+];
+
+const FIELDS: [&str; 12] = [
+    "set", "slot", "main", "location", "rarity", "level", "lock", "equipped", "sub", "has", "cv",
+    "rv",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Set,
+    Slot,
+    Stat,
+    Character,
+    Number,
+    Bool,
+}
+
+impl Type {
+    fn to_str(self) -> &'static str {
+        match self {
+            Type::Set => "a set",
+            Type::Slot => "a slot",
+            Type::Stat => "a stat",
+            Type::Character => "a character",
+            Type::Number => "a number",
+            Type::Bool => "true or false",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Set,
+    Slot,
+    Main,
+    Location,
+    Rarity,
+    Level,
+    Lock,
+    Equipped,
+    // substat value, 0 when the artifact does not have it
+    Sub(ArtifactStatKey),
+    Has(ArtifactStatKey),
+    CritValue,
+    RollValue,
+}
+
+impl Field {
+    fn value_type(&self) -> Type {
+        match self {
+            Field::Set => Type::Set,
+            Field::Slot => Type::Slot,
+            Field::Main => Type::Stat,
+            Field::Location => Type::Character,
+            Field::Rarity | Field::Level | Field::Sub(_) | Field::CritValue | Field::RollValue => {
+                Type::Number
+            }
+            Field::Lock | Field::Equipped | Field::Has(_) => Type::Bool,
+        }
+    }
+
+    fn get(&self, art: &InternalArtifact) -> Value {
+        match self {
+            Field::Set => Value::Set(art.set_key),
+            Field::Slot => Value::Slot(art.slot_key.clone()),
+            Field::Main => Value::Stat(art.main_stat.key.clone()),
+            Field::Location => Value::Character(art.location),
+            Field::Rarity => Value::Number(art.rarity as f64),
+            Field::Level => Value::Number(art.level as f64),
+            Field::Lock => Value::Bool(art.lock),
+            Field::Equipped => Value::Bool(art.location.is_some()),
+            Field::Sub(key) => Value::Number(substat(art, key).map_or(0.0, |s| s.value)),
+            Field::Has(key) => Value::Bool(substat(art, key).is_some()),
+            Field::CritValue => Value::Number(crit_value(art)),
+            Field::RollValue => Value::Number(roll_value(art)),
+        }
+    }
+}
+
+fn substat<'a>(art: &'a InternalArtifact, key: &ArtifactStatKey) -> Option<&'a ArtifactStat> {
+    art.substats().into_iter().find(|s| s.key == *key)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Set(ArtifactSetKey),
+    Slot(ArtifactSlotKey),
+    Stat(ArtifactStatKey),
+    // None for `location == none`
+    Character(Option<CharacterKey>),
+    Number(f64),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn from_str(s: &str) -> Option<CompareOp> {
+        let op = match s {
+            "==" => CompareOp::Eq,
+            "!=" => CompareOp::Ne,
+            "<" => CompareOp::Lt,
+            "<=" => CompareOp::Le,
+            ">" => CompareOp::Gt,
+            ">=" => CompareOp::Ge,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn test(&self, a: &Value, b: &Value) -> bool {
+        match (self, a, b) {
+            (CompareOp::Eq, _, _) => a == b,
+            (CompareOp::Ne, _, _) => a != b,
+            (CompareOp::Lt, Value::Number(x), Value::Number(y)) => x < y,
+            (CompareOp::Le, Value::Number(x), Value::Number(y)) => x <= y,
+            (CompareOp::Gt, Value::Number(x), Value::Number(y)) => x > y,
+            (CompareOp::Ge, Value::Number(x), Value::Number(y)) => x >= y,
+            _ => false,
+        }
+    }
+}
+
+// A parsed filter expression, e.g.
+// `set == EmblemOfSeveredFate && slot == Sands && main == Recharge && sub(critRate) >= 6.6`
+#[derive(Debug, Clone)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Compare(Field, CompareOp, Value),
+    In(Field, Vec<Value>),
+    // a true or false field on its own, e.g. `lock`
+    Test(Field),
+}
+
+impl Filter {
+    // The error names the column and what was expected, with the offending part underlined
+    pub fn parse(expr: &str) -> Result<Filter, String> {
+        let mut parser = Parser {
+            tokens: tokenize(expr).map_err(|e| e.render(expr))?,
+            pos: 0,
+            len: expr.chars().count(),
+        };
+        let filter = parser.parse_or().map_err(|e| e.render(expr))?;
+        if let Some(t) = parser.peek() {
+            let e = FilterError::new(t, format!("unexpected `{}`", t.text));
+            return Err(e.render(expr));
+        }
+
+        Ok(filter)
+    }
+
+    pub fn matches(&self, art: &InternalArtifact) -> bool {
+        match self {
+            Filter::And(a, b) => a.matches(art) && b.matches(art),
+            Filter::Or(a, b) => a.matches(art) || b.matches(art),
+            Filter::Not(a) => !a.matches(art),
+            Filter::Compare(field, op, value) => op.test(&field.get(art), value),
+            Filter::In(field, values) => values.contains(&field.get(art)),
+            Filter::Test(field) => field.get(art) == Value::Bool(true),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident,
+    Number(f64),
+    Symbol,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    text: String,
+    // columns, in characters
+    start: usize,
+    end: usize,
+}
+
+struct FilterError {
+    start: usize,
+    end: usize,
+    message: String,
+}
+
+impl FilterError {
+    fn new(token: &Token, message: String) -> FilterError {
+        FilterError {
+            start: token.start,
+            end: token.end,
+            message,
+        }
+    }
+
+    fn render(&self, expr: &str) -> String {
+        format!(
+            "column {}: {}\n  {}\n  {}{}",
+            self.start + 1,
+            self.message,
+            expr,
+            " ".repeat(self.start),
+            "^".repeat((self.end - self.start).max(1))
+        )
+    }
+}
+
+const SYMBOLS: [&str; 14] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "(", ")", "[", "]", ",",
+];
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, FilterError> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Ident,
+                text: chars[start..i].iter().collect(),
+                start,
+                end: i,
+            });
+        } else if c.is_ascii_digit() || c == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            match text.parse::<f64>() {
+                Ok(v) => tokens.push(Token {
+                    kind: TokenKind::Number(v),
+                    text,
+                    start,
+                    end: i,
+                }),
+                Err(_) => {
+                    return Err(FilterError {
+                        start,
+                        end: i,
+                        message: format!("malformed number `{}`", text),
+                    })
+                }
+            }
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let symbol = match SYMBOLS.iter().find(|s| rest.starts_with(*s)) {
+                Some(s) => *s,
+                None => {
+                    return Err(FilterError {
+                        start,
+                        end: i + 1,
+                        message: format!("unexpected character `{}`", c),
+                    })
+                }
+            };
+            i += symbol.len();
+            tokens.push(Token {
+                kind: TokenKind::Symbol,
+                text: String::from(symbol),
+                start,
+                end: i,
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Closest name for "did you mean" hints, a name the input is the start of counts as close
+fn suggest<'a>(raw: &str, names: &[&'a str]) -> Option<&'a str> {
+    let raw = raw.to_lowercase();
+    if let Some(name) = names.iter().find(|n| n.to_lowercase().starts_with(&raw)) {
+        return Some(name);
+    }
+    let (distance, name) = names
+        .iter()
+        .map(|n| (weighted_distance(&raw, &n.to_lowercase()), *n))
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())?;
+    if distance <= (raw.chars().count() as f64 / 3.0).max(2.0) {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+fn unknown(token: &Token, what: &str, names: &[&str]) -> FilterError {
+    let message = match suggest(&token.text, names) {
+        Some(s) => format!("unknown {} `{}`, did you mean `{}`?", what, token.text, s),
+        None => format!("unknown {} `{}`", what, token.text),
+    };
+    FilterError::new(token, message)
+}
+
+// GOOD key, with or without the trailing "_", or the variant name, e.g. "critRate_",
+// "critRate" or "Critical"
+fn stat_from_name(s: &str) -> Option<ArtifactStatKey> {
+    if let Some(key) = ArtifactStatKey::from_good(s) {
+        return Some(key);
+    }
+    let s = s.to_lowercase();
+    STATS
+        .iter()
+        .find(|k| {
+            let good = k.to_good().to_lowercase();
+            good == format!("{}_", s) || format!("{:?}", k).to_lowercase() == s
+        })
+        .cloned()
+}
+
+fn resolve_value(token: &Token, t: Type) -> Result<Value, FilterError> {
+    let mismatch = || {
+        FilterError::new(
+            token,
+            format!("expected {}, found `{}`", t.to_str(), token.text),
+        )
+    };
+
+    match t {
+        Type::Number => match token.kind {
+            TokenKind::Number(v) => return Ok(Value::Number(v)),
+            _ => return Err(mismatch()),
+        },
+        Type::Bool => match token.text.as_str() {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            _ => return Err(mismatch()),
+        },
+        _ => {}
+    }
+    if token.kind != TokenKind::Ident {
+        return Err(mismatch());
+    }
+
+    let s = token.text.as_str();
+    let lower = s.to_lowercase();
+    match t {
+        Type::Set => {
+            let sets = &game_data().sets;
+            let found = sets
+                .iter()
+                .find(|set| set.export_key("good").unwrap_or(set.key.0) == s || set.key.0 == s)
+                .or_else(|| sets.iter().find(|set| set.key.0.to_lowercase() == lower));
+            match found {
+                Some(set) => Ok(Value::Set(set.key)),
+                None => {
+                    let names: Vec<&str> = sets.iter().map(|set| set.key.0).collect();
+                    Err(unknown(token, "set", &names))
+                }
+            }
+        }
+        Type::Slot => match ArtifactSlotKey::from_good(&lower) {
+            Some(v) => Ok(Value::Slot(v)),
+            None => {
+                let names: Vec<String> = SLOTS.iter().map(|k| format!("{:?}", k)).collect();
+                let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+                Err(unknown(token, "slot", &names))
+            }
+        },
+        Type::Stat => match stat_from_name(s) {
+            Some(v) => Ok(Value::Stat(v)),
+            None => {
+                let names: Vec<String> = STATS.iter().map(|k| k.to_good()).collect();
+                let names: Vec<&str> = names.iter().map(|n| n.trim_end_matches('_')).collect();
+                Err(unknown(token, "stat", &names))
+            }
+        },
+        Type::Character => {
+            if lower == "none" {
+                return Ok(Value::Character(None));
+            }
+            let characters = &game_data().characters;
+            let found = characters
+                .iter()
+                .find(|c| c.export_key("good").unwrap_or(c.key.0) == s || c.key.0 == s)
+                .or_else(|| characters.iter().find(|c| c.key.0.to_lowercase() == lower));
+            match found {
+                Some(c) => Ok(Value::Character(Some(c.key))),
+                None => {
+                    let names: Vec<&str> = characters.iter().map(|c| c.key.0).collect();
+                    Err(unknown(token, "character", &names))
+                }
+            }
+        }
+        Type::Number | Type::Bool => Err(mismatch()),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    // length of the expression, where "end of expression" errors point to
+    len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_is(&self, text: &str) -> bool {
+        self.peek().is_some_and(|t| t.text == text)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token, FilterError> {
+        match self.tokens.get(self.pos) {
+            Some(t) => {
+                self.pos += 1;
+                Ok(t.clone())
+            }
+            None => Err(FilterError {
+                start: self.len,
+                end: self.len + 1,
+                message: format!("expected {}, found end of expression", expected),
+            }),
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<Token, FilterError> {
+        let t = self.next(&format!("`{}`", text))?;
+        if t.text != text {
+            return Err(FilterError::new(
+                &t,
+                format!("expected `{}`, found `{}`", text, t.text),
+            ));
+        }
+        Ok(t)
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, FilterError> {
+        let mut left = self.parse_and()?;
+        while self.peek_is("||") {
+            self.pos += 1;
+            left = Filter::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, FilterError> {
+        let mut left = self.parse_unary()?;
+        while self.peek_is("&&") {
+            self.pos += 1;
+            left = Filter::And(Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, FilterError> {
+        if self.peek_is("!") {
+            self.pos += 1;
+            return Ok(Filter::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.peek_is("(") {
+            self.pos += 1;
+            let inner = self.parse_or()?;
+            self.expect(")")?;
+            return Ok(inner);
+        }
+        self.parse_condition()
+    }
+
+    fn parse_field(&mut self) -> Result<(Field, Token), FilterError> {
+        let t = self.next("a field")?;
+        if t.kind != TokenKind::Ident {
+            return Err(FilterError::new(
+                &t,
+                format!("expected a field, found `{}`", t.text),
+            ));
+        }
+
+        let field = match t.text.as_str() {
+            "set" => Field::Set,
+            "slot" => Field::Slot,
+            "main" => Field::Main,
+            "location" => Field::Location,
+            "rarity" => Field::Rarity,
+            "level" => Field::Level,
+            "lock" => Field::Lock,
+            "equipped" => Field::Equipped,
+            "cv" => Field::CritValue,
+            "rv" => Field::RollValue,
+            "sub" | "has" => {
+                self.expect("(")?;
+                let stat = self.next("a stat")?;
+                let key = match resolve_value(&stat, Type::Stat)? {
+                    Value::Stat(k) => k,
+                    _ => unreachable!(),
+                };
+                self.expect(")")?;
+                if t.text == "sub" {
+                    Field::Sub(key)
+                } else {
+                    Field::Has(key)
+                }
+            }
+            _ => return Err(unknown(&t, "field", &FIELDS)),
+        };
+
+        Ok((field, t))
+    }
+
+    fn parse_condition(&mut self) -> Result<Filter, FilterError> {
+        let (field, name) = self.parse_field()?;
+        let t = field.value_type();
+
+        if self.peek_is("in") {
+            self.pos += 1;
+            self.expect("[")?;
+            let mut values: Vec<Value> = Vec::new();
+            loop {
+                let v = self.next(t.to_str())?;
+                values.push(resolve_value(&v, t)?);
+                let sep = self.next("`,` or `]`")?;
+                match sep.text.as_str() {
+                    "," => continue,
+                    "]" => break,
+                    _ => {
+                        return Err(FilterError::new(
+                            &sep,
+                            format!("expected `,` or `]`, found `{}`", sep.text),
+                        ))
+                    }
+                }
+            }
+            return Ok(Filter::In(field, values));
+        }
+
+        let op = match self.peek().and_then(|t| CompareOp::from_str(&t.text)) {
+            Some(op) => op,
+            None if t == Type::Bool => return Ok(Filter::Test(field)),
+            None => {
+                let message = format!("`{}` needs a comparison such as `==`", name.text);
+                return Err(FilterError::new(&name, message));
+            }
+        };
+        let op_token = self.next("a comparison")?;
+        if t != Type::Number && op != CompareOp::Eq && op != CompareOp::Ne {
+            return Err(FilterError::new(
+                &op_token,
+                format!(
+                    "`{}` compares numbers, but `{}` is {}",
+                    op_token.text,
+                    name.text,
+                    t.to_str()
+                ),
+            ));
+        }
+        let value = self.next(t.to_str())?;
+
+        Ok(Filter::Compare(field, op, resolve_value(&value, t)?))
+    }
+}
@@ -0,0 +1,161 @@
+use log::warn;
+use once_cell::sync::OnceCell;
+
+// Characters the OCR model tends to mix up, substituting one for another within a group is
+// cheaper than an arbitrary substitution
+const CONFUSABLE_GROUPS: [&str; 26] = [
+    "己已巳",
+    "土士",
+    "未末",
+    "日曰",
+    "人入八",
+    "王玉",
+    "大太犬",
+    "天夫",
+    "贝见",
+    "壁璧",
+    "侯候",
+    "戊戌戍",
+    "折析",
+    "拨拔",
+    "微徽",
+    "晷暑",
+    "力カ",
+    "口ロ",
+    "二ニ",
+    "工エ",
+    "一ー",
+    "夕タ",
+    "卜ト",
+    "O0",
+    "Il1",
+    "S5",
+];
+const CONFUSABLE_COST: f64 = 0.4;
+
+static FUZZY_RESOLVER: OnceCell<FuzzyResolver> = OnceCell::new();
+
+pub fn substitution_cost(a: char, b: char) -> f64 {
+    if a == b {
+        0.0
+    } else if CONFUSABLE_GROUPS
+        .iter()
+        .any(|g| g.contains(a) && g.contains(b))
+    {
+        CONFUSABLE_COST
+    } else {
+        1.0
+    }
+}
+
+// Edit distance over characters, insertions and deletions cost 1, substitutions cost 1 or
+// less for confusable characters
+pub fn weighted_distance(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut prev: Vec<f64> = (0..=b.len()).map(|j| j as f64).collect();
+    for i in 1..=a.len() {
+        let mut cur = vec![i as f64; b.len() + 1];
+        for j in 1..=b.len() {
+            cur[j] = (prev[j] + 1.0)
+                .min(cur[j - 1] + 1.0)
+                .min(prev[j - 1] + substitution_cost(a[i - 1], b[j - 1]));
+        }
+        prev = cur;
+    }
+
+    prev[b.len()]
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzyMatch<'a> {
+    pub name: &'a str,
+    pub distance: f64,
+    // closest candidate that means something else, and its distance
+    pub runner_up: Option<(&'a str, f64)>,
+    // the runner-up is too close to tell which one was meant
+    pub ambiguous: bool,
+}
+
+impl<'a> FuzzyMatch<'a> {
+    pub fn margin(&self) -> Option<f64> {
+        self.runner_up.map(|(_, d)| d - self.distance)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzyResolver {
+    // largest distance accepted, a match never differs in more than half of the name
+    pub max_distance: f64,
+    // how much further away the runner-up has to be for an inexact match to be trusted
+    pub min_margin: f64,
+}
+
+impl Default for FuzzyResolver {
+    fn default() -> FuzzyResolver {
+        FuzzyResolver::new(2.0, 1.0)
+    }
+}
+
+impl FuzzyResolver {
+    pub fn new(max_distance: f64, min_margin: f64) -> FuzzyResolver {
+        FuzzyResolver {
+            max_distance,
+            min_margin,
+        }
+    }
+
+    // Closest candidate to `raw`, None if none is close enough. Candidates carrying equal
+    // values are spelling variants of each other and never count as runner-up.
+    pub fn resolve<'a, T: PartialEq>(
+        &self,
+        raw: &str,
+        candidates: &[(&'a str, T)],
+    ) -> Option<FuzzyMatch<'a>> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return None;
+        }
+
+        let mut scored: Vec<(f64, usize)> = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (weighted_distance(raw, name), i))
+            .collect();
+        scored.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let &(distance, best) = scored.first()?;
+        let (name, value) = &candidates[best];
+        let limit = self.max_distance.min(name.chars().count() as f64 / 2.0);
+        if distance > limit + 1e-6 {
+            return None;
+        }
+
+        let runner_up = scored
+            .iter()
+            .skip(1)
+            .find(|(_, i)| candidates[*i].1 != *value)
+            .map(|&(d, i)| (candidates[i].0, d));
+        let ambiguous =
+            distance > 0.0 && runner_up.is_some_and(|(_, d)| d - distance < self.min_margin - 1e-6);
+
+        Some(FuzzyMatch {
+            name,
+            distance,
+            runner_up,
+            ambiguous,
+        })
+    }
+}
+
+// Sets the resolver used for the rest of the run
+pub fn init_fuzzy_resolver(resolver: FuzzyResolver) {
+    if FUZZY_RESOLVER.set(resolver).is_err() {
+        warn!("fuzzy resolver already initialized");
+    }
+}
+
+pub fn fuzzy_resolver() -> &'static FuzzyResolver {
+    FUZZY_RESOLVER.get_or_init(FuzzyResolver::default)
+}
@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use log::{info, warn};
+use once_cell::sync::OnceCell;
+use serde_json::Value;
+
+use crate::artifact::internal_artifact::{ArtifactSetKey, ArtifactSlotKey, CharacterKey};
+use crate::artifact::lang::Language;
+use crate::character::internal_character::Talent;
+use crate::weapon::internal_weapon::WeaponKey;
+
+// Highest data file format this build understands
+pub const GAME_DATA_VERSION: u64 = 1;
+
+const BUILTIN_GAME_DATA: &str = include_str!("../../data/game_data.json");
+
+const LANGUAGES: [Language; 3] = [Language::ZhCn, Language::En, Language::Ja];
+
+static GAME_DATA: OnceCell<GameData> = OnceCell::new();
+
+pub struct PieceInfo {
+    pub slot: ArtifactSlotKey,
+    names: HashMap<Language, Vec<String>>,
+}
+
+pub struct SetInfo {
+    pub key: ArtifactSetKey,
+    pub min_rarity: u32,
+    pub max_rarity: u32,
+    pub pieces: Vec<PieceInfo>,
+    export: HashMap<String, String>,
+}
+
+pub struct CharacterInfo {
+    pub key: CharacterKey,
+    // raised by the 3rd constellation, the 5th raises the other of skill and burst
+    pub c3_talent: Option<Talent>,
+    // always included in the levels the talent screen shows, e.g. the +1 normal attack of
+    // Tartaglia's passive, in the order auto attack, skill, burst
+    pub talent_bonus: [u32; 3],
+    names: HashMap<Language, Vec<String>>,
+    export: HashMap<String, String>,
+}
+
+pub struct WeaponInfo {
+    pub key: WeaponKey,
+    pub rarity: u32,
+    names: HashMap<Language, Vec<String>>,
+    export: HashMap<String, String>,
+}
+
+// Everything that changes with game patches: artifact sets and their pieces, characters,
+// weapons, their names in every supported language and the keys used by the export formats.
+pub struct GameData {
+    pub version: u64,
+    pub game_version: String,
+    pub sets: Vec<SetInfo>,
+    pub characters: Vec<CharacterInfo>,
+    pub weapons: Vec<WeaponInfo>,
+    pieces_by_name: HashMap<(Language, String), (usize, usize)>,
+    characters_by_name: HashMap<(Language, String), usize>,
+    weapons_by_name: HashMap<(Language, String), usize>,
+}
+
+// Keys are compared and copied everywhere, so they are interned once for the whole run
+fn intern(s: &str) -> &'static str {
+    Box::leak(String::from(s).into_boxed_str())
+}
+
+fn slot_from_str(s: &str) -> Option<ArtifactSlotKey> {
+    match s {
+        "flower" => Some(ArtifactSlotKey::Flower),
+        "plume" => Some(ArtifactSlotKey::Plume),
+        "sands" => Some(ArtifactSlotKey::Sands),
+        "goblet" => Some(ArtifactSlotKey::Goblet),
+        "circlet" => Some(ArtifactSlotKey::Circlet),
+        _ => None,
+    }
+}
+
+// A name entry is either a string or a list of strings (spelling variants)
+fn parse_names(value: &Value, what: &str) -> Result<HashMap<Language, Vec<String>>, String> {
+    let mut names: HashMap<Language, Vec<String>> = HashMap::new();
+    let obj = match value.as_object() {
+        Some(v) => v,
+        None => return Err(format!("{}: names must be an object", what)),
+    };
+    for (lang, v) in obj.iter() {
+        let lang = match lang.parse::<Language>() {
+            Ok(l) => l,
+            Err(e) => {
+                warn!("{}: {}", what, e);
+                continue;
+            }
+        };
+        let list: Vec<String> = match v {
+            Value::String(s) => vec![s.clone()],
+            Value::Array(arr) => arr
+                .iter()
+                .filter_map(|x| x.as_str().map(String::from))
+                .collect(),
+            _ => return Err(format!("{}: bad names for {}", what, lang.name())),
+        };
+        names.insert(lang, list);
+    }
+
+    Ok(names)
+}
+
+fn parse_export(value: &Value) -> HashMap<String, String> {
+    let mut export: HashMap<String, String> = HashMap::new();
+    if let Some(obj) = value.as_object() {
+        for (format, v) in obj.iter() {
+            if let Some(s) = v.as_str() {
+                export.insert(format.clone(), String::from(s));
+            }
+        }
+    }
+
+    export
+}
+
+fn parse_key(value: &Value, what: &str) -> Result<&'static str, String> {
+    match value["key"].as_str() {
+        Some(s) if !s.is_empty() => Ok(intern(s)),
+        _ => Err(format!("{}: missing key", what)),
+    }
+}
+
+impl FromStr for GameData {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GameData, String> {
+        let json: Value = match serde_json::from_str(s) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("cannot parse game data: {}", e)),
+        };
+
+        let version = match json["version"].as_u64() {
+            Some(v) => v,
+            None => return Err(String::from("missing version")),
+        };
+        if version > GAME_DATA_VERSION {
+            return Err(format!(
+                "data version {} is newer than supported {}",
+                version, GAME_DATA_VERSION
+            ));
+        }
+        let game_version = String::from(json["game_version"].as_str().unwrap_or("unknown"));
+
+        let mut sets: Vec<SetInfo> = Vec::new();
+        for (i, item) in json["sets"]
+            .as_array()
+            .unwrap_or(&Vec::new())
+            .iter()
+            .enumerate()
+        {
+            let key = parse_key(item, &format!("sets[{}]", i))?;
+            let mut pieces: Vec<PieceInfo> = Vec::new();
+            if let Some(obj) = item["pieces"].as_object() {
+                for (slot, names) in obj.iter() {
+                    let slot = match slot_from_str(slot) {
+                        Some(v) => v,
+                        None => return Err(format!("{}: unknown slot {}", key, slot)),
+                    };
+                    let names = parse_names(names, key)?;
+                    pieces.push(PieceInfo { slot, names });
+                }
+            }
+            if pieces.is_empty() {
+                return Err(format!("{}: no pieces", key));
+            }
+
+            sets.push(SetInfo {
+                key: ArtifactSetKey(key),
+                min_rarity: item["min_rarity"].as_u64().unwrap_or(1) as u32,
+                max_rarity: item["max_rarity"].as_u64().unwrap_or(5) as u32,
+                pieces,
+                export: parse_export(&item["export"]),
+            });
+        }
+
+        let mut characters: Vec<CharacterInfo> = Vec::new();
+        for (i, item) in json["characters"]
+            .as_array()
+            .unwrap_or(&Vec::new())
+            .iter()
+            .enumerate()
+        {
+            let key = parse_key(item, &format!("characters[{}]", i))?;
+            let c3_talent = match item["c3"].as_str() {
+                Some(s) => match s.parse::<Talent>() {
+                    Ok(t) if t != Talent::Auto => Some(t),
+                    _ => return Err(format!("{}: bad c3 talent {}", key, s)),
+                },
+                None => None,
+            };
+            let mut talent_bonus = [0_u32; 3];
+            if let Some(items) = item["talent_bonus"].as_array() {
+                if items.len() != 3 {
+                    return Err(format!("{}: talent_bonus needs 3 levels", key));
+                }
+                for (bonus, v) in talent_bonus.iter_mut().zip(items.iter()) {
+                    *bonus = match v.as_u64() {
+                        Some(v) => v as u32,
+                        None => return Err(format!("{}: bad talent_bonus {}", key, v)),
+                    };
+                }
+            }
+            characters.push(CharacterInfo {
+                key: CharacterKey(key),
+                c3_talent,
+                talent_bonus,
+                names: parse_names(&item["names"], key)?,
+                export: parse_export(&item["export"]),
+            });
+        }
+
+        let mut weapons: Vec<WeaponInfo> = Vec::new();
+        for (i, item) in json["weapons"]
+            .as_array()
+            .unwrap_or(&Vec::new())
+            .iter()
+            .enumerate()
+        {
+            let key = parse_key(item, &format!("weapons[{}]", i))?;
+            weapons.push(WeaponInfo {
+                key: WeaponKey(key),
+                rarity: item["rarity"].as_u64().unwrap_or(1) as u32,
+                names: parse_names(&item["names"], key)?,
+                export: parse_export(&item["export"]),
+            });
+        }
+
+        if sets.is_empty() {
+            return Err(String::from("no artifact sets"));
+        }
+
+        let mut pieces_by_name = HashMap::new();
+        for (i, set) in sets.iter().enumerate() {
+            for (j, piece) in set.pieces.iter().enumerate() {
+                for (lang, names) in piece.names.iter() {
+                    for name in names.iter() {
+                        if pieces_by_name
+                            .insert((*lang, name.clone()), (i, j))
+                            .is_some()
+                        {
+                            warn!("duplicate artifact name {}", name);
+                        }
+                    }
+                }
+            }
+        }
+        let mut characters_by_name = HashMap::new();
+        for (i, character) in characters.iter().enumerate() {
+            for (lang, names) in character.names.iter() {
+                for name in names.iter() {
+                    if characters_by_name
+                        .insert((*lang, name.clone()), i)
+                        .is_some()
+                    {
+                        warn!("duplicate character name {}", name);
+                    }
+                }
+            }
+        }
+
+        let mut weapons_by_name = HashMap::new();
+        for (i, weapon) in weapons.iter().enumerate() {
+            for (lang, names) in weapon.names.iter() {
+                for name in names.iter() {
+                    if weapons_by_name.insert((*lang, name.clone()), i).is_some() {
+                        warn!("duplicate weapon name {}", name);
+                    }
+                }
+            }
+        }
+
+        Ok(GameData {
+            version,
+            game_version,
+            sets,
+            characters,
+            weapons,
+            pieces_by_name,
+            characters_by_name,
+            weapons_by_name,
+        })
+    }
+}
+
+impl GameData {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<GameData, String> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(s) => GameData::from_str(&s),
+            Err(e) => Err(format!("cannot read {}: {}", path.display(), e)),
+        }
+    }
+
+    // The copy compiled into the binary
+    pub fn builtin() -> GameData {
+        GameData::from_str(BUILTIN_GAME_DATA).expect("builtin game data is broken")
+    }
+
+    pub fn find_piece(
+        &self,
+        lang: Language,
+        name: &str,
+    ) -> Option<(ArtifactSetKey, ArtifactSlotKey)> {
+        let &(i, j) = self.pieces_by_name.get(&(lang, String::from(name)))?;
+        let set = &self.sets[i];
+        Some((set.key, set.pieces[j].slot.clone()))
+    }
+
+    pub fn find_character(&self, lang: Language, name: &str) -> Option<CharacterKey> {
+        let &i = self.characters_by_name.get(&(lang, String::from(name)))?;
+        Some(self.characters[i].key)
+    }
+
+    pub fn find_weapon(&self, lang: Language, name: &str) -> Option<WeaponKey> {
+        let &i = self.weapons_by_name.get(&(lang, String::from(name)))?;
+        Some(self.weapons[i].key)
+    }
+
+    pub fn set(&self, key: &ArtifactSetKey) -> Option<&SetInfo> {
+        self.sets.iter().find(|s| s.key == *key)
+    }
+
+    pub fn character(&self, key: &CharacterKey) -> Option<&CharacterInfo> {
+        self.characters.iter().find(|c| c.key == *key)
+    }
+
+    pub fn weapon(&self, key: &WeaponKey) -> Option<&WeaponInfo> {
+        self.weapons.iter().find(|w| w.key == *key)
+    }
+
+    // All known piece names of a language, including spelling variants
+    pub fn piece_names(&self, lang: Language) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for set in self.sets.iter() {
+            for piece in set.pieces.iter() {
+                if let Some(list) = piece.names.get(&lang) {
+                    names.extend(list.iter().map(|s| s.as_str()));
+                }
+            }
+        }
+
+        names
+    }
+
+    pub fn character_names(&self, lang: Language) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for character in self.characters.iter() {
+            if let Some(list) = character.names.get(&lang) {
+                names.extend(list.iter().map(|s| s.as_str()));
+            }
+        }
+
+        names
+    }
+
+    pub fn weapon_names(&self, lang: Language) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for weapon in self.weapons.iter() {
+            if let Some(list) = weapon.names.get(&lang) {
+                names.extend(list.iter().map(|s| s.as_str()));
+            }
+        }
+
+        names
+    }
+}
+
+impl PieceInfo {
+    pub fn names(&self, lang: Language) -> &[String] {
+        self.names.get(&lang).map_or(&[], |v| v.as_slice())
+    }
+}
+
+impl SetInfo {
+    pub fn piece(&self, slot: &ArtifactSlotKey) -> Option<&PieceInfo> {
+        self.pieces.iter().find(|p| p.slot == *slot)
+    }
+
+    // Key used by an export format ("good", "mona", "genmo"), None if not supported there
+    pub fn export_key(&self, format: &str) -> Option<&str> {
+        self.export.get(format).map(|s| s.as_str())
+    }
+}
+
+impl CharacterInfo {
+    pub fn names(&self, lang: Language) -> &[String] {
+        self.names.get(&lang).map_or(&[], |v| v.as_slice())
+    }
+
+    pub fn export_key(&self, format: &str) -> Option<&str> {
+        self.export.get(format).map(|s| s.as_str())
+    }
+}
+
+impl WeaponInfo {
+    pub fn names(&self, lang: Language) -> &[String] {
+        self.names.get(&lang).map_or(&[], |v| v.as_slice())
+    }
+
+    pub fn export_key(&self, format: &str) -> Option<&str> {
+        self.export.get(format).map(|s| s.as_str())
+    }
+}
+
+// Loads the game data used for the rest of the run. `path` overrides the default
+// `game_data.json` next to the working directory. Falls back to the compiled-in copy when
+// no file is found or it cannot be used.
+pub fn init_game_data(path: Option<&str>) {
+    let path = match path {
+        Some(p) => Some(String::from(p)),
+        None if Path::new("game_data.json").exists() => Some(String::from("game_data.json")),
+        None => None,
+    };
+
+    let data = match path {
+        Some(p) => match GameData::from_file(&p) {
+            Ok(v) => {
+                info!("game data: {} ({})", p, v.game_version);
+                v
+            }
+            Err(e) => {
+                warn!("{}, using builtin game data", e);
+                GameData::builtin()
+            }
+        },
+        None => GameData::builtin(),
+    };
+    for problem in check_game_data(&data) {
+        warn!("game data: {}", problem);
+    }
+
+    if GAME_DATA.set(data).is_err() {
+        warn!("game data already initialized");
+    }
+}
+
+pub fn game_data() -> &'static GameData {
+    GAME_DATA.get_or_init(GameData::builtin)
+}
+
+// Problems that do not prevent using the data, e.g. a piece without a Japanese name can
+// still be parsed in the other languages
+pub fn check_game_data(data: &GameData) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::new();
+    for set in data.sets.iter() {
+        for piece in set.pieces.iter() {
+            for lang in LANGUAGES.iter() {
+                if piece.names(*lang).is_empty() {
+                    problems.push(format!(
+                        "{} {:?}: no {} name",
+                        set.key.0,
+                        piece.slot,
+                        lang.name()
+                    ));
+                }
+            }
+        }
+        if set.min_rarity > set.max_rarity {
+            problems.push(format!("{}: bad rarity range", set.key.0));
+        }
+    }
+    // weapons are only scanned from the Chinese client
+    for weapon in data.weapons.iter() {
+        if weapon.names(Language::ZhCn).is_empty() {
+            problems.push(format!("{}: no zh-cn name", weapon.key.0));
+        }
+        if weapon.rarity < 1 || weapon.rarity > 5 {
+            problems.push(format!("{}: bad rarity", weapon.key.0));
+        }
+    }
+
+    problems
+}
@@ -0,0 +1,73 @@
+use crate::artifact::internal_artifact::ArtifactStatKey;
+
+pub const STAT_NAMES: [&str; 15] = [
+    "Healing Bonus",
+    "CRIT DMG",
+    "CRIT Rate",
+    "ATK",
+    "Elemental Mastery",
+    "Energy Recharge",
+    "HP",
+    "DEF",
+    "Electro DMG Bonus",
+    "Pyro DMG Bonus",
+    "Hydro DMG Bonus",
+    "Cryo DMG Bonus",
+    "Anemo DMG Bonus",
+    "Geo DMG Bonus",
+    "Physical DMG Bonus",
+];
+
+// e.g. "CRIT Rate", "Pyro DMG Bonus"
+pub fn stat_key(name: &str, is_percentage: bool) -> Option<ArtifactStatKey> {
+    match name {
+        "Healing Bonus" => Some(ArtifactStatKey::HealingBonus),
+        "CRIT DMG" => Some(ArtifactStatKey::CriticalDamage),
+        "CRIT Rate" => Some(ArtifactStatKey::Critical),
+        "ATK" => {
+            if is_percentage {
+                Some(ArtifactStatKey::AtkPercentage)
+            } else {
+                Some(ArtifactStatKey::Atk)
+            }
+        }
+        "Elemental Mastery" => Some(ArtifactStatKey::ElementalMastery),
+        "Energy Recharge" => Some(ArtifactStatKey::Recharge),
+        "HP" => {
+            if is_percentage {
+                Some(ArtifactStatKey::HpPercentage)
+            } else {
+                Some(ArtifactStatKey::Hp)
+            }
+        }
+        "DEF" => {
+            if is_percentage {
+                Some(ArtifactStatKey::DefPercentage)
+            } else {
+                Some(ArtifactStatKey::Def)
+            }
+        }
+        "Electro DMG Bonus" => Some(ArtifactStatKey::ElectroBonus),
+        "Pyro DMG Bonus" => Some(ArtifactStatKey::PyroBonus),
+        "Hydro DMG Bonus" => Some(ArtifactStatKey::HydroBonus),
+        "Cryo DMG Bonus" => Some(ArtifactStatKey::CryoBonus),
+        "Anemo DMG Bonus" => Some(ArtifactStatKey::AnemoBonus),
+        "Geo DMG Bonus" => Some(ArtifactStatKey::GeoBonus),
+        "Physical DMG Bonus" => Some(ArtifactStatKey::PhysicalBonus),
+        _ => None,
+    }
+}
+
+// "Equipped: Zhongli" -> "Zhongli"
+pub fn equipped_name(s: &str) -> Option<&str> {
+    let s = s.trim();
+    if !s.starts_with("Equipped") {
+        return None;
+    }
+    let name = s["Equipped".len()..].trim_start_matches(|c: char| c == ':' || c.is_whitespace());
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
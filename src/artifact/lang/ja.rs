@@ -0,0 +1,78 @@
+use crate::artifact::internal_artifact::ArtifactStatKey;
+
+pub const STAT_NAMES: [&str; 15] = [
+    "与える治癒効果",
+    "会心ダメージ",
+    "会心率",
+    "攻撃力",
+    "元素熟知",
+    "元素チャージ効率",
+    "HP",
+    "防御力",
+    "雷元素ダメージ",
+    "炎元素ダメージ",
+    "水元素ダメージ",
+    "氷元素ダメージ",
+    "風元素ダメージ",
+    "岩元素ダメージ",
+    "物理ダメージ",
+];
+
+// e.g. "会心率", "炎元素ダメージ"
+pub fn stat_key(name: &str, is_percentage: bool) -> Option<ArtifactStatKey> {
+    match name {
+        "与える治癒効果" => Some(ArtifactStatKey::HealingBonus),
+        "会心ダメージ" => Some(ArtifactStatKey::CriticalDamage),
+        "会心率" => Some(ArtifactStatKey::Critical),
+        "攻撃力" => {
+            if is_percentage {
+                Some(ArtifactStatKey::AtkPercentage)
+            } else {
+                Some(ArtifactStatKey::Atk)
+            }
+        }
+        "元素熟知" => Some(ArtifactStatKey::ElementalMastery),
+        "元素チャージ効率" => Some(ArtifactStatKey::Recharge),
+        "HP" => {
+            if is_percentage {
+                Some(ArtifactStatKey::HpPercentage)
+            } else {
+                Some(ArtifactStatKey::Hp)
+            }
+        }
+        "防御力" => {
+            if is_percentage {
+                Some(ArtifactStatKey::DefPercentage)
+            } else {
+                Some(ArtifactStatKey::Def)
+            }
+        }
+        "雷元素ダメージ" => Some(ArtifactStatKey::ElectroBonus),
+        "炎元素ダメージ" => Some(ArtifactStatKey::PyroBonus),
+        "水元素ダメージ" => Some(ArtifactStatKey::HydroBonus),
+        "氷元素ダメージ" => Some(ArtifactStatKey::CryoBonus),
+        "風元素ダメージ" => Some(ArtifactStatKey::AnemoBonus),
+        "岩元素ダメージ" => Some(ArtifactStatKey::GeoBonus),
+        "物理ダメージ" => Some(ArtifactStatKey::PhysicalBonus),
+        _ => None,
+    }
+}
+
+// "鍾離が装備中", "装備中: 鍾離" -> "鍾離"
+pub fn equipped_name(s: &str) -> Option<&str> {
+    let s = s.trim();
+    let name = if s.ends_with("装備中") {
+        s.trim_end_matches("装備中").trim_end_matches('が')
+    } else if s.starts_with("装備中") {
+        s.trim_start_matches("装備中")
+            .trim_start_matches(|c: char| c == ':' || c == '：' || c.is_whitespace())
+    } else {
+        return None;
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
@@ -0,0 +1,192 @@
+use std::str::FromStr;
+
+use crate::artifact::fuzzy::{fuzzy_resolver, FuzzyMatch};
+use crate::artifact::game_data::game_data;
+use crate::artifact::internal_artifact::{
+    ArtifactSetKey, ArtifactSlotKey, ArtifactStat, ArtifactStatKey, CharacterKey,
+};
+use crate::artifact::parse_error::ParseErrorReason;
+use crate::weapon::internal_weapon::WeaponKey;
+
+pub mod en;
+pub mod ja;
+
+const ZH_CN_STAT_NAMES: [&str; 15] = [
+    "治疗加成",
+    "暴击伤害",
+    "暴击率",
+    "攻击力",
+    "元素精通",
+    "元素充能效率",
+    "生命值",
+    "防御力",
+    "雷元素伤害加成",
+    "火元素伤害加成",
+    "水元素伤害加成",
+    "冰元素伤害加成",
+    "风元素伤害加成",
+    "岩元素伤害加成",
+    "物理伤害加成",
+];
+
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    ZhCn,
+    En,
+    Ja,
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Language, String> {
+        match s.to_lowercase().as_str() {
+            "zh-cn" | "zh_cn" | "chs" => Ok(Language::ZhCn),
+            "en" | "en-us" => Ok(Language::En),
+            "ja" | "ja-jp" | "jp" => Ok(Language::Ja),
+            _ => Err(format!("unknown language {}", s)),
+        }
+    }
+}
+
+impl Language {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::ZhCn => "zh-cn",
+            Language::En => "en",
+            Language::Ja => "ja",
+        }
+    }
+
+    // "4,123" -> 4123, "10.5%" -> 10.5. All supported languages group thousands with ','
+    // and use '.' as decimal point, Japanese text may contain full-width forms.
+    pub fn parse_number(&self, s: &str) -> Option<f64> {
+        let s: String = s
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '０'..='９' => std::char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap(),
+                '．' => '.',
+                '，' => ',',
+                '％' => '%',
+                _ => c,
+            })
+            .filter(|c| *c != ',' && *c != '%')
+            .collect();
+
+        s.parse::<f64>().ok()
+    }
+
+    // Character name out of the equip line, e.g. "钟离已装备", "Equipped: Zhongli"
+    pub fn equipped_name<'a>(&self, s: &'a str) -> Option<&'a str> {
+        match self {
+            Language::ZhCn => {
+                let name = s.trim().strip_suffix("已装备")?;
+                if name.is_empty() {
+                    None
+                } else {
+                    Some(name)
+                }
+            }
+            Language::En => en::equipped_name(s),
+            Language::Ja => ja::equipped_name(s),
+        }
+    }
+
+    pub fn equipped_character(&self, s: &str) -> Option<CharacterKey> {
+        let name = self.equipped_name(s)?;
+        CharacterKey::from_lang(*self, name)
+    }
+
+    // Stat names as shown in game, flat and percentage stats share a name
+    pub fn stat_names(&self) -> &'static [&'static str] {
+        match self {
+            Language::ZhCn => &ZH_CN_STAT_NAMES,
+            Language::En => &en::STAT_NAMES,
+            Language::Ja => &ja::STAT_NAMES,
+        }
+    }
+
+    // Closest known piece name, spelling variants of the same piece are not ambiguous
+    pub fn resolve_piece(&self, raw: &str) -> Option<FuzzyMatch<'static>> {
+        let data = game_data();
+        let candidates: Vec<(&str, Option<(ArtifactSetKey, ArtifactSlotKey)>)> = data
+            .piece_names(*self)
+            .into_iter()
+            .map(|name| (name, data.find_piece(*self, name)))
+            .collect();
+        fuzzy_resolver().resolve(raw, &candidates)
+    }
+
+    pub fn resolve_stat(&self, raw: &str) -> Option<FuzzyMatch<'static>> {
+        let candidates: Vec<(&str, &str)> = self.stat_names().iter().map(|&n| (n, n)).collect();
+        fuzzy_resolver().resolve(raw, &candidates)
+    }
+
+    pub fn resolve_character(&self, raw: &str) -> Option<FuzzyMatch<'static>> {
+        let data = game_data();
+        let candidates: Vec<(&str, Option<CharacterKey>)> = data
+            .character_names(*self)
+            .into_iter()
+            .map(|name| (name, data.find_character(*self, name)))
+            .collect();
+        fuzzy_resolver().resolve(raw, &candidates)
+    }
+
+    pub fn resolve_weapon(&self, raw: &str) -> Option<FuzzyMatch<'static>> {
+        let data = game_data();
+        let candidates: Vec<(&str, Option<WeaponKey>)> = data
+            .weapon_names(*self)
+            .into_iter()
+            .map(|name| (name, data.find_weapon(*self, name)))
+            .collect();
+        fuzzy_resolver().resolve(raw, &candidates)
+    }
+}
+
+impl ArtifactStatKey {
+    pub fn from_lang(lang: Language, name: &str, is_percentage: bool) -> Option<ArtifactStatKey> {
+        match lang {
+            Language::ZhCn => ArtifactStatKey::from_zh_cn(name, is_percentage),
+            Language::En => en::stat_key(name, is_percentage),
+            Language::Ja => ja::stat_key(name, is_percentage),
+        }
+    }
+}
+
+impl ArtifactStat {
+    // e.g "生命值+4,123", "CRIT Rate+10.5%", "会心率+10.5%"
+    pub fn from_lang_raw(lang: Language, s: &str) -> Result<ArtifactStat, ParseErrorReason> {
+        let pos = s.rfind('+').ok_or(ParseErrorReason::MissingSeparator)?;
+        let name = s[..pos].trim();
+        let value = &s[pos + 1..];
+
+        let is_percentage = value.contains('%') || value.contains('％');
+        let key = ArtifactStatKey::from_lang(lang, name, is_percentage)
+            .ok_or(ParseErrorReason::UnknownName)?;
+        let value = lang
+            .parse_number(value)
+            .ok_or(ParseErrorReason::MalformedNumber)?;
+
+        Ok(ArtifactStat { key, value })
+    }
+}
+
+// Piece and character names come from the game data file
+impl ArtifactSetKey {
+    pub fn from_lang(lang: Language, s: &str) -> Option<ArtifactSetKey> {
+        game_data().find_piece(lang, s).map(|x| x.0)
+    }
+}
+
+impl ArtifactSlotKey {
+    pub fn from_lang(lang: Language, s: &str) -> Option<ArtifactSlotKey> {
+        game_data().find_piece(lang, s).map(|x| x.1)
+    }
+}
+
+impl CharacterKey {
+    pub fn from_lang(lang: Language, s: &str) -> Option<CharacterKey> {
+        game_data().find_character(lang, s)
+    }
+}
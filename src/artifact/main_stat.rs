@@ -0,0 +1,261 @@
+use crate::artifact::internal_artifact::{ArtifactSlotKey, ArtifactStatKey};
+use crate::artifact::rolls::{display_round, max_level};
+
+// Main stat value at level 0 and at max level, values in between grow linearly
+fn value_range(key: &ArtifactStatKey, rarity: u32) -> Option<(f64, f64)> {
+    let v = match rarity {
+        5 => match key {
+            ArtifactStatKey::Hp => (717.0, 4780.0),
+            ArtifactStatKey::Atk => (47.0, 311.0),
+            ArtifactStatKey::HpPercentage | ArtifactStatKey::AtkPercentage => (7.0, 46.6),
+            ArtifactStatKey::DefPercentage => (8.7, 58.3),
+            ArtifactStatKey::ElementalMastery => (28.0, 186.5),
+            ArtifactStatKey::Recharge => (7.8, 51.8),
+            ArtifactStatKey::Critical => (4.7, 31.1),
+            ArtifactStatKey::CriticalDamage => (9.3, 62.2),
+            ArtifactStatKey::HealingBonus => (5.4, 35.9),
+            ArtifactStatKey::PhysicalBonus => (8.7, 58.3),
+            ArtifactStatKey::Def => return None,
+            ArtifactStatKey::ElectroBonus
+            | ArtifactStatKey::PyroBonus
+            | ArtifactStatKey::HydroBonus
+            | ArtifactStatKey::CryoBonus
+            | ArtifactStatKey::AnemoBonus
+            | ArtifactStatKey::GeoBonus => (7.0, 46.6),
+        },
+        4 => match key {
+            ArtifactStatKey::Hp => (645.0, 3571.0),
+            ArtifactStatKey::Atk => (42.0, 232.0),
+            ArtifactStatKey::HpPercentage | ArtifactStatKey::AtkPercentage => (6.3, 34.8),
+            ArtifactStatKey::DefPercentage => (7.9, 43.5),
+            ArtifactStatKey::ElementalMastery => (25.2, 139.3),
+            ArtifactStatKey::Recharge => (7.0, 38.7),
+            ArtifactStatKey::Critical => (4.2, 23.2),
+            ArtifactStatKey::CriticalDamage => (8.4, 46.4),
+            ArtifactStatKey::HealingBonus => (4.8, 26.8),
+            ArtifactStatKey::PhysicalBonus => (7.9, 43.5),
+            ArtifactStatKey::Def => return None,
+            ArtifactStatKey::ElectroBonus
+            | ArtifactStatKey::PyroBonus
+            | ArtifactStatKey::HydroBonus
+            | ArtifactStatKey::CryoBonus
+            | ArtifactStatKey::AnemoBonus
+            | ArtifactStatKey::GeoBonus => (6.3, 34.8),
+        },
+        3 => match key {
+            ArtifactStatKey::Hp => (430.0, 1893.0),
+            ArtifactStatKey::Atk => (28.0, 123.0),
+            ArtifactStatKey::HpPercentage | ArtifactStatKey::AtkPercentage => (5.2, 22.7),
+            ArtifactStatKey::DefPercentage => (6.6, 28.3),
+            ArtifactStatKey::ElementalMastery => (21.0, 90.9),
+            ArtifactStatKey::Recharge => (5.8, 25.2),
+            ArtifactStatKey::Critical => (3.5, 15.2),
+            ArtifactStatKey::CriticalDamage => (7.0, 30.3),
+            ArtifactStatKey::HealingBonus => (4.0, 17.5),
+            ArtifactStatKey::PhysicalBonus => (6.6, 28.3),
+            ArtifactStatKey::Def => return None,
+            ArtifactStatKey::ElectroBonus
+            | ArtifactStatKey::PyroBonus
+            | ArtifactStatKey::HydroBonus
+            | ArtifactStatKey::CryoBonus
+            | ArtifactStatKey::AnemoBonus
+            | ArtifactStatKey::GeoBonus => (5.2, 22.7),
+        },
+        2 => match key {
+            ArtifactStatKey::Hp => (258.0, 785.0),
+            ArtifactStatKey::Atk => (17.0, 51.0),
+            ArtifactStatKey::HpPercentage | ArtifactStatKey::AtkPercentage => (4.2, 9.9),
+            ArtifactStatKey::DefPercentage => (5.2, 12.4),
+            ArtifactStatKey::ElementalMastery => (16.8, 39.9),
+            ArtifactStatKey::Recharge => (4.7, 11.0),
+            ArtifactStatKey::Critical => (2.8, 6.6),
+            ArtifactStatKey::CriticalDamage => (5.6, 13.2),
+            ArtifactStatKey::HealingBonus => (3.2, 7.6),
+            ArtifactStatKey::PhysicalBonus => (5.2, 12.4),
+            ArtifactStatKey::Def => return None,
+            ArtifactStatKey::ElectroBonus
+            | ArtifactStatKey::PyroBonus
+            | ArtifactStatKey::HydroBonus
+            | ArtifactStatKey::CryoBonus
+            | ArtifactStatKey::AnemoBonus
+            | ArtifactStatKey::GeoBonus => (4.2, 9.9),
+        },
+        _ => return None,
+    };
+
+    Some(v)
+}
+
+// Value shown in game for a main stat, None if there is no table for it
+pub fn main_stat_value(key: &ArtifactStatKey, rarity: u32, level: u32) -> Option<f64> {
+    let (base, max) = value_range(key, rarity)?;
+    let max_level = max_level(rarity);
+    if level > max_level {
+        return None;
+    }
+    let v = base + (max - base) * level as f64 / max_level as f64;
+
+    Some(display_round(key, v))
+}
+
+// Flower and plume always have flat HP and ATK, the other slots never have flat stats. Fixes
+// "攻击力" whose "%" was lost by recognition.
+pub fn normalize_main_stat_key(slot: &ArtifactSlotKey, key: ArtifactStatKey) -> ArtifactStatKey {
+    match (slot, key) {
+        (ArtifactSlotKey::Flower, _) => ArtifactStatKey::Hp,
+        (ArtifactSlotKey::Plume, _) => ArtifactStatKey::Atk,
+        (_, ArtifactStatKey::Hp) => ArtifactStatKey::HpPercentage,
+        (_, ArtifactStatKey::Atk) => ArtifactStatKey::AtkPercentage,
+        (_, ArtifactStatKey::Def) => ArtifactStatKey::DefPercentage,
+        (_, k) => k,
+    }
+}
+
+// The tables are interpolated, allow one display unit of difference
+fn matches(key: &ArtifactStatKey, expected: f64, value: f64) -> bool {
+    let unit = if key.is_percentage() { 0.1 } else { 1.0 };
+    (expected - value).abs() <= unit + 1e-6
+}
+
+// Levels at which `key` shows `value`
+pub fn levels_for_value(key: &ArtifactStatKey, rarity: u32, value: f64) -> Vec<u32> {
+    (0..=max_level(rarity))
+        .filter(|&l| match main_stat_value(key, rarity, l) {
+            Some(expected) => matches(key, expected, value),
+            None => false,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MainStatCheck {
+    Valid,
+    // level could not be read or is above the max level, taken from the main stat value
+    LevelInferred {
+        original: Option<u32>,
+        level: u32,
+    },
+    // value does not belong to the level, replaced by the table value
+    ValueCorrected {
+        original: Option<f64>,
+        corrected: f64,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct MainStatResolution {
+    pub level: u32,
+    pub value: f64,
+    pub check: MainStatCheck,
+}
+
+// Combines the recognized level and main stat value. A level read with its "+" and within the
+// max level is trusted and the value is checked against it, otherwise the level is inferred
+// from the value. None when neither is usable, or when there is no table and the level is
+// missing.
+pub fn resolve_main_stat(
+    key: &ArtifactStatKey,
+    rarity: u32,
+    level: Option<u32>,
+    value: Option<f64>,
+) -> Option<MainStatResolution> {
+    if let Some(level) = level.filter(|&l| l <= max_level(rarity)) {
+        let expected = match main_stat_value(key, rarity, level) {
+            Some(v) => v,
+            None => {
+                return value.map(|value| MainStatResolution {
+                    level,
+                    value,
+                    check: MainStatCheck::Valid,
+                })
+            }
+        };
+        return Some(match value {
+            Some(v) if matches(key, expected, v) => MainStatResolution {
+                level,
+                value: v,
+                check: MainStatCheck::Valid,
+            },
+            _ => MainStatResolution {
+                level,
+                value: expected,
+                check: MainStatCheck::ValueCorrected {
+                    original: value,
+                    corrected: expected,
+                },
+            },
+        });
+    }
+
+    let original = level;
+    let value = value?;
+    let levels = levels_for_value(key, rarity, value);
+    if levels.len() != 1 {
+        return None;
+    }
+
+    Some(MainStatResolution {
+        level: levels[0],
+        value,
+        check: MainStatCheck::LevelInferred {
+            original,
+            level: levels[0],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(level: Option<u32>, value: f64) -> (u32, f64, MainStatCheck) {
+        let r = resolve_main_stat(&ArtifactStatKey::AtkPercentage, 5, level, Some(value)).unwrap();
+        (r.level, r.value, r.check)
+    }
+
+    #[test]
+    fn value_is_checked_against_a_read_level() {
+        assert_eq!(resolve(Some(20), 46.6), (20, 46.6, MainStatCheck::Valid));
+        // 44.6 is the value at +19, the level is still trusted
+        assert_eq!(
+            resolve(Some(20), 44.6),
+            (
+                20,
+                46.6,
+                MainStatCheck::ValueCorrected {
+                    original: Some(44.6),
+                    corrected: 46.6,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn level_is_inferred_only_when_unusable() {
+        assert_eq!(
+            resolve(None, 44.6),
+            (
+                19,
+                44.6,
+                MainStatCheck::LevelInferred {
+                    original: None,
+                    level: 19,
+                }
+            )
+        );
+        // "+28" is not a level of a 5 star artifact
+        assert_eq!(
+            resolve(Some(28), 46.6),
+            (
+                20,
+                46.6,
+                MainStatCheck::LevelInferred {
+                    original: Some(28),
+                    level: 20,
+                }
+            )
+        );
+        // no level has that value
+        assert!(resolve_main_stat(&ArtifactStatKey::AtkPercentage, 5, None, Some(100.0)).is_none());
+    }
+}
@@ -1 +1,12 @@
-pub mod internal_artifact;
\ No newline at end of file
+pub mod analysis;
+pub mod filter;
+pub mod fuzzy;
+pub mod game_data;
+pub mod internal_artifact;
+pub mod lang;
+pub mod main_stat;
+pub mod parse_error;
+pub mod rolls;
+pub mod rules;
+pub mod score;
+pub mod upgrade;
\ No newline at end of file
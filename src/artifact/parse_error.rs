@@ -0,0 +1,55 @@
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorReason {
+    UnknownName,
+    MalformedNumber,
+    // "name+value" without the "+"
+    MissingSeparator,
+}
+
+impl ParseErrorReason {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ParseErrorReason::UnknownName => "unknown_name",
+            ParseErrorReason::MalformedNumber => "malformed_number",
+            ParseErrorReason::MissingSeparator => "missing_separator",
+        }
+    }
+}
+
+// Why a recognized field could not be turned into part of an artifact. `field` is named as
+// in the scan report, e.g. "title", "main_stat_value", "sub_stat_3", "equip".
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub field: &'static str,
+    pub reason: ParseErrorReason,
+    pub text: String,
+}
+
+impl ParseError {
+    pub fn new(field: &'static str, reason: ParseErrorReason, text: &str) -> ParseError {
+        ParseError {
+            field,
+            reason,
+            text: String::from(text),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        format!("{}: {} \"{}\"", self.field, self.reason.to_str(), self.text)
+    }
+}
+
+impl Serialize for ParseError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(3))?;
+        root.serialize_entry("field", self.field)?;
+        root.serialize_entry("reason", self.reason.to_str())?;
+        root.serialize_entry("text", &self.text)?;
+        root.end()
+    }
+}
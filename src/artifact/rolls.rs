@@ -0,0 +1,250 @@
+use crate::artifact::internal_artifact::{ArtifactStat, ArtifactStatKey, InternalArtifact};
+
+// Value of the highest roll tier, the other tiers are 70%, 80% and 90% of it
+fn max_roll(key: &ArtifactStatKey, rarity: u32) -> Option<f64> {
+    let v = match rarity {
+        5 => match key {
+            ArtifactStatKey::Hp => 298.75,
+            ArtifactStatKey::Atk => 19.45,
+            ArtifactStatKey::Def => 23.15,
+            ArtifactStatKey::HpPercentage => 5.8275,
+            ArtifactStatKey::AtkPercentage => 5.8275,
+            ArtifactStatKey::DefPercentage => 7.2875,
+            ArtifactStatKey::ElementalMastery => 23.31,
+            ArtifactStatKey::Recharge => 6.475,
+            ArtifactStatKey::Critical => 3.885,
+            ArtifactStatKey::CriticalDamage => 7.77,
+            _ => return None,
+        },
+        4 => match key {
+            ArtifactStatKey::Hp => 239.0,
+            ArtifactStatKey::Atk => 15.56,
+            ArtifactStatKey::Def => 18.52,
+            ArtifactStatKey::HpPercentage => 4.662,
+            ArtifactStatKey::AtkPercentage => 4.662,
+            ArtifactStatKey::DefPercentage => 5.83,
+            ArtifactStatKey::ElementalMastery => 18.648,
+            ArtifactStatKey::Recharge => 5.18,
+            ArtifactStatKey::Critical => 3.108,
+            ArtifactStatKey::CriticalDamage => 6.216,
+            _ => return None,
+        },
+        3 => match key {
+            ArtifactStatKey::Hp => 143.4,
+            ArtifactStatKey::Atk => 9.34,
+            ArtifactStatKey::Def => 11.11,
+            ArtifactStatKey::HpPercentage => 3.4965,
+            ArtifactStatKey::AtkPercentage => 3.4965,
+            ArtifactStatKey::DefPercentage => 4.3725,
+            ArtifactStatKey::ElementalMastery => 13.986,
+            ArtifactStatKey::Recharge => 3.885,
+            ArtifactStatKey::Critical => 2.331,
+            ArtifactStatKey::CriticalDamage => 4.662,
+            _ => return None,
+        },
+        // 1 and 2 star artifacts have different tiers, they are not checked
+        _ => return None,
+    };
+
+    Some(v)
+}
+
+// The four possible values of a single roll, None if `key` cannot be a substat
+pub fn roll_tiers(key: &ArtifactStatKey, rarity: u32) -> Option<[f64; 4]> {
+    let m = max_roll(key, rarity)?;
+    Some([m * 0.7, m * 0.8, m * 0.9, m])
+}
+
+pub fn max_level(rarity: u32) -> u32 {
+    if rarity >= 3 {
+        rarity * 4
+    } else {
+        4
+    }
+}
+
+// A substat gets one roll when it appears and at most one more every 4 levels
+pub fn max_rolls(rarity: u32, level: u32) -> u32 {
+    1 + level.min(max_level(rarity)) / 4
+}
+
+// Values as shown in game: percentages with one decimal, the rest as integers
+pub fn display_round(key: &ArtifactStatKey, value: f64) -> f64 {
+    if key.is_percentage() {
+        (value * 10.0).round() / 10.0
+    } else {
+        value.round()
+    }
+}
+
+// Every value the game can show for a substat made of 1 to `rolls` rolls, ascending
+pub fn reachable_values(key: &ArtifactStatKey, rarity: u32, rolls: u32) -> Vec<f64> {
+    let tiers = match roll_tiers(key, rarity) {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    let mut values: Vec<f64> = Vec::new();
+    // sums of n rolls, tier indices non-decreasing so every multiset is visited once
+    let mut stack: Vec<(u32, usize, f64)> = vec![(0, 0, 0.0)];
+    while let Some((n, first, sum)) = stack.pop() {
+        if n > 0 {
+            values.push(display_round(key, sum));
+        }
+        if n == rolls {
+            continue;
+        }
+        for (i, tier) in tiers.iter().enumerate().skip(first) {
+            stack.push((n + 1, i, sum + tier));
+        }
+    }
+
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+    values
+}
+
+// Ways to make up exactly `value` with 1 to `rolls` rolls, each given as the tier indices
+// (0 for 70% up to 3 for 100%) in ascending order, fewest rolls first
+pub fn roll_combinations(
+    key: &ArtifactStatKey,
+    rarity: u32,
+    rolls: u32,
+    value: f64,
+) -> Vec<Vec<usize>> {
+    let tiers = match roll_tiers(key, rarity) {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    let mut combinations: Vec<Vec<usize>> = Vec::new();
+    let mut stack: Vec<(Vec<usize>, f64)> = vec![(Vec::new(), 0.0)];
+    while let Some((combination, sum)) = stack.pop() {
+        if !combination.is_empty() && (display_round(key, sum) - value).abs() < 1e-6 {
+            combinations.push(combination.clone());
+        }
+        if combination.len() as u32 == rolls {
+            continue;
+        }
+        let first = combination.last().cloned().unwrap_or(0);
+        for (i, tier) in tiers.iter().enumerate().skip(first) {
+            let mut next = combination.clone();
+            next.push(i);
+            stack.push((next, sum + tier));
+        }
+    }
+
+    combinations.sort();
+    combinations.sort_by_key(|c| c.len());
+    combinations
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RollCheck {
+    Valid,
+    // within one display unit of a reachable value
+    Snapped(f64),
+    // e.g. "105" read for "10.5%"
+    DecimalRestored(f64),
+    Impossible,
+}
+
+fn display_unit(key: &ArtifactStatKey) -> f64 {
+    if key.is_percentage() {
+        0.1
+    } else {
+        1.0
+    }
+}
+
+pub fn check_substat(stat: &ArtifactStat, rarity: u32, level: u32) -> RollCheck {
+    let values = reachable_values(&stat.key, rarity, max_rolls(rarity, level));
+    if values.is_empty() {
+        // no table for this rarity, nothing to compare with
+        return RollCheck::Valid;
+    }
+
+    let eps = 1e-6;
+    let v = stat.value;
+    if values.iter().any(|x| (x - v).abs() < eps) {
+        return RollCheck::Valid;
+    }
+
+    let unit = display_unit(&stat.key);
+    let near: Vec<f64> = values
+        .iter()
+        .cloned()
+        .filter(|x| (x - v).abs() <= unit + eps)
+        .collect();
+    let nearest = near
+        .iter()
+        .cloned()
+        .fold(None, |acc: Option<f64>, x| match acc {
+            Some(a) if (a - v).abs() <= (x - v).abs() => Some(a),
+            _ => Some(x),
+        });
+    if let Some(x) = nearest {
+        // two candidates at the same distance, cannot tell which one was meant
+        let ties = near
+            .iter()
+            .filter(|y| ((*y - v).abs() - (x - v).abs()).abs() < eps)
+            .count();
+        if ties == 1 {
+            return RollCheck::Snapped(x);
+        }
+        return RollCheck::Impossible;
+    }
+
+    if stat.key.is_percentage() {
+        let shifted = v / 10.0;
+        if values.iter().any(|x| (x - shifted).abs() < eps) {
+            return RollCheck::DecimalRestored(shifted);
+        }
+    }
+
+    RollCheck::Impossible
+}
+
+#[derive(Debug, Clone)]
+pub struct SubstatCorrection {
+    pub field: &'static str,
+    pub key: ArtifactStatKey,
+    pub original: f64,
+    pub check: RollCheck,
+}
+
+// Checks every substat of `art` against the roll tables, applies the corrections and returns
+// everything that was not valid as read. Impossible values are kept as they are.
+pub fn validate_substats(art: &mut InternalArtifact) -> Vec<SubstatCorrection> {
+    let rarity = art.rarity;
+    let level = art.level;
+    let mut corrections: Vec<SubstatCorrection> = Vec::new();
+
+    let subs = [
+        ("sub_stat_1", &mut art.sub_stat_1),
+        ("sub_stat_2", &mut art.sub_stat_2),
+        ("sub_stat_3", &mut art.sub_stat_3),
+        ("sub_stat_4", &mut art.sub_stat_4),
+    ];
+    for (field, sub) in subs {
+        let stat = match sub {
+            Some(v) => v,
+            None => continue,
+        };
+        let check = check_substat(stat, rarity, level);
+        let original = stat.value;
+        match check {
+            RollCheck::Valid => continue,
+            RollCheck::Snapped(v) | RollCheck::DecimalRestored(v) => stat.value = v,
+            RollCheck::Impossible => (),
+        }
+        corrections.push(SubstatCorrection {
+            field,
+            key: stat.key.clone(),
+            original,
+            check,
+        });
+    }
+
+    corrections
+}
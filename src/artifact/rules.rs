@@ -0,0 +1,282 @@
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::artifact::game_data::game_data;
+use crate::artifact::internal_artifact::{
+    ArtifactSetKey, ArtifactSlotKey, ArtifactStatKey, InternalArtifact,
+};
+use crate::artifact::parse_error::ParseError;
+use crate::artifact::rolls::max_level;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    LevelAboveCap {
+        level: u32,
+        cap: u32,
+    },
+    SubstatCount {
+        count: u32,
+        min: u32,
+        max: u32,
+    },
+    SubstatIsMainStat {
+        field: &'static str,
+    },
+    DuplicateSubstat {
+        field: &'static str,
+    },
+    IllegalSubstat {
+        field: &'static str,
+        key: ArtifactStatKey,
+    },
+    IllegalMainStat {
+        slot: ArtifactSlotKey,
+        key: ArtifactStatKey,
+    },
+    IllegalSlotForSet {
+        set: ArtifactSetKey,
+        slot: ArtifactSlotKey,
+    },
+    IllegalRarityForSet {
+        set: ArtifactSetKey,
+        rarity: u32,
+    },
+    // a substat or the equip line that could not be parsed and was left empty
+    UnreadableField {
+        error: ParseError,
+    },
+}
+
+const SUB_STAT_FIELDS: [&str; 4] = ["sub_stat_1", "sub_stat_2", "sub_stat_3", "sub_stat_4"];
+
+impl Diagnostic {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Diagnostic::LevelAboveCap { .. } => "level_above_cap",
+            Diagnostic::SubstatCount { .. } => "substat_count",
+            Diagnostic::SubstatIsMainStat { .. } => "substat_is_main_stat",
+            Diagnostic::DuplicateSubstat { .. } => "duplicate_substat",
+            Diagnostic::IllegalSubstat { .. } => "illegal_substat",
+            Diagnostic::IllegalMainStat { .. } => "illegal_main_stat",
+            Diagnostic::IllegalSlotForSet { .. } => "illegal_slot_for_set",
+            Diagnostic::IllegalRarityForSet { .. } => "illegal_rarity_for_set",
+            Diagnostic::UnreadableField { .. } => "unreadable_field",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Diagnostic::LevelAboveCap { level, cap } => {
+                format!("level {} above cap {}", level, cap)
+            }
+            Diagnostic::SubstatCount { count, min, max } => {
+                format!("{} substats, expected {} to {}", count, min, max)
+            }
+            Diagnostic::SubstatIsMainStat { field } => format!("{} equals the main stat", field),
+            Diagnostic::DuplicateSubstat { field } => format!("{} is a duplicate", field),
+            Diagnostic::IllegalSubstat { field, key } => {
+                format!("{} cannot be {:?}", field, key)
+            }
+            Diagnostic::IllegalMainStat { slot, key } => {
+                format!("{:?} cannot have main stat {:?}", slot, key)
+            }
+            Diagnostic::IllegalSlotForSet { set, slot } => {
+                format!("{} has no {:?}", set.0, slot)
+            }
+            Diagnostic::IllegalRarityForSet { set, rarity } => {
+                format!("{} has no {} star pieces", set.0, rarity)
+            }
+            Diagnostic::UnreadableField { error } => error.message(),
+        }
+    }
+
+    // Recognized fields that are likely wrong, empty when only a review can tell
+    pub fn suspect_fields(&self, art: &InternalArtifact) -> Vec<&'static str> {
+        match self {
+            Diagnostic::LevelAboveCap { .. } => vec!["level"],
+            Diagnostic::SubstatCount { count, min, .. } if count < min => SUB_STAT_FIELDS
+                .iter()
+                .zip(substats(art).iter())
+                .filter(|(_, s)| s.is_none())
+                .map(|(f, _)| *f)
+                .collect(),
+            Diagnostic::SubstatCount { .. } => Vec::new(),
+            Diagnostic::SubstatIsMainStat { field } => vec![*field, "main_stat_name"],
+            Diagnostic::DuplicateSubstat { field } => vec![*field],
+            Diagnostic::IllegalSubstat { field, .. } => vec![*field],
+            Diagnostic::IllegalMainStat { .. } => vec!["main_stat_name"],
+            Diagnostic::IllegalSlotForSet { .. } | Diagnostic::IllegalRarityForSet { .. } => {
+                vec!["title"]
+            }
+            Diagnostic::UnreadableField { error } => vec![error.field],
+        }
+    }
+}
+
+impl Serialize for Diagnostic {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(2))?;
+        root.serialize_entry("kind", self.kind())?;
+        root.serialize_entry("message", &self.message())?;
+        root.end()
+    }
+}
+
+fn substats(art: &InternalArtifact) -> [Option<&ArtifactStatKey>; 4] {
+    [
+        art.sub_stat_1.as_ref().map(|s| &s.key),
+        art.sub_stat_2.as_ref().map(|s| &s.key),
+        art.sub_stat_3.as_ref().map(|s| &s.key),
+        art.sub_stat_4.as_ref().map(|s| &s.key),
+    ]
+}
+
+// Number of substats an artifact starts with, (min, max)
+pub fn initial_substats(rarity: u32) -> (u32, u32) {
+    match rarity {
+        5 => (3, 4),
+        4 => (2, 3),
+        3 => (1, 2),
+        2 => (0, 1),
+        _ => (0, 0),
+    }
+}
+
+pub fn can_be_substat(key: &ArtifactStatKey) -> bool {
+    matches!(
+        key,
+        ArtifactStatKey::Hp
+            | ArtifactStatKey::Atk
+            | ArtifactStatKey::Def
+            | ArtifactStatKey::HpPercentage
+            | ArtifactStatKey::AtkPercentage
+            | ArtifactStatKey::DefPercentage
+            | ArtifactStatKey::ElementalMastery
+            | ArtifactStatKey::Recharge
+            | ArtifactStatKey::Critical
+            | ArtifactStatKey::CriticalDamage
+    )
+}
+
+pub fn can_be_main_stat(slot: &ArtifactSlotKey, key: &ArtifactStatKey) -> bool {
+    match slot {
+        ArtifactSlotKey::Flower => *key == ArtifactStatKey::Hp,
+        ArtifactSlotKey::Plume => *key == ArtifactStatKey::Atk,
+        ArtifactSlotKey::Sands => matches!(
+            key,
+            ArtifactStatKey::HpPercentage
+                | ArtifactStatKey::AtkPercentage
+                | ArtifactStatKey::DefPercentage
+                | ArtifactStatKey::ElementalMastery
+                | ArtifactStatKey::Recharge
+        ),
+        ArtifactSlotKey::Goblet => matches!(
+            key,
+            ArtifactStatKey::HpPercentage
+                | ArtifactStatKey::AtkPercentage
+                | ArtifactStatKey::DefPercentage
+                | ArtifactStatKey::ElementalMastery
+                | ArtifactStatKey::ElectroBonus
+                | ArtifactStatKey::PyroBonus
+                | ArtifactStatKey::HydroBonus
+                | ArtifactStatKey::CryoBonus
+                | ArtifactStatKey::AnemoBonus
+                | ArtifactStatKey::GeoBonus
+                | ArtifactStatKey::PhysicalBonus
+        ),
+        ArtifactSlotKey::Circlet => matches!(
+            key,
+            ArtifactStatKey::HpPercentage
+                | ArtifactStatKey::AtkPercentage
+                | ArtifactStatKey::DefPercentage
+                | ArtifactStatKey::ElementalMastery
+                | ArtifactStatKey::Critical
+                | ArtifactStatKey::CriticalDamage
+                | ArtifactStatKey::HealingBonus
+        ),
+    }
+}
+
+// Domain invariants every real artifact satisfies. An empty result does not mean the
+// artifact was recognized correctly, only that nothing contradicts itself.
+pub fn check_artifact(art: &InternalArtifact) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    let cap = max_level(art.rarity);
+    if art.level > cap {
+        diagnostics.push(Diagnostic::LevelAboveCap {
+            level: art.level,
+            cap,
+        });
+    }
+
+    let subs = substats(art);
+    let count = subs.iter().filter(|s| s.is_some()).count() as u32;
+    let (init_min, init_max) = initial_substats(art.rarity);
+    let upgrades = art.level.min(cap) / 4;
+    let min = (init_min + upgrades).min(4);
+    let max = (init_max + upgrades).min(4);
+    if count < min || count > max {
+        diagnostics.push(Diagnostic::SubstatCount { count, min, max });
+    }
+
+    for (i, sub) in subs.iter().enumerate() {
+        let key = match sub {
+            Some(k) => *k,
+            None => continue,
+        };
+        let field = SUB_STAT_FIELDS[i];
+        if !can_be_substat(key) {
+            diagnostics.push(Diagnostic::IllegalSubstat {
+                field,
+                key: key.clone(),
+            });
+        }
+        if *key == art.main_stat.key {
+            diagnostics.push(Diagnostic::SubstatIsMainStat { field });
+        }
+        if subs[..i].contains(&Some(key)) {
+            diagnostics.push(Diagnostic::DuplicateSubstat { field });
+        }
+    }
+
+    if !can_be_main_stat(&art.slot_key, &art.main_stat.key) {
+        diagnostics.push(Diagnostic::IllegalMainStat {
+            slot: art.slot_key.clone(),
+            key: art.main_stat.key.clone(),
+        });
+    }
+
+    if let Some(set) = game_data().set(&art.set_key) {
+        if set.piece(&art.slot_key).is_none() {
+            diagnostics.push(Diagnostic::IllegalSlotForSet {
+                set: art.set_key,
+                slot: art.slot_key.clone(),
+            });
+        }
+        if art.rarity < set.min_rarity || art.rarity > set.max_rarity {
+            diagnostics.push(Diagnostic::IllegalRarityForSet {
+                set: art.set_key,
+                rarity: art.rarity,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+// Fields worth recognizing again, each listed once
+pub fn suspect_fields(art: &InternalArtifact, diagnostics: &[Diagnostic]) -> Vec<&'static str> {
+    let mut fields: Vec<&'static str> = Vec::new();
+    for d in diagnostics.iter() {
+        for f in d.suspect_fields(art) {
+            if !fields.contains(&f) {
+                fields.push(f);
+            }
+        }
+    }
+
+    fields
+}
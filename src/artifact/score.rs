@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use log::{info, warn};
+use serde_json::Value;
+
+use crate::artifact::internal_artifact::{
+    ArtifactStat, ArtifactStatKey, CharacterKey, InternalArtifact,
+};
+use crate::artifact::rolls::roll_tiers;
+
+pub const SCORE_PROFILES_VERSION: u64 = 1;
+const BUILTIN_SCORE_PROFILES: &str = include_str!("../../data/score_profiles.json");
+
+// Stat weights for one kind of character, e.g. crit and ATK% for most damage dealers
+#[derive(Debug, Clone)]
+pub struct ScoreProfile {
+    pub name: String,
+    pub weights: HashMap<ArtifactStatKey, f64>,
+    // characters built this way, their equipped artifacts are scored with this profile
+    pub characters: Vec<CharacterKey>,
+}
+
+impl ScoreProfile {
+    pub fn weight(&self, key: &ArtifactStatKey) -> f64 {
+        *self.weights.get(key).unwrap_or(&0.0)
+    }
+
+    // Weighted roll value, see `roll_value`
+    pub fn score(&self, art: &InternalArtifact) -> f64 {
+        round_score(
+            art.substats()
+                .iter()
+                .map(|s| self.weight(&s.key) * roll_fraction(s))
+                .sum::<f64>()
+                * 100.0,
+        )
+    }
+}
+
+// Scores are shown with two decimals
+fn round_score(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+// Value as a multiple of the highest 5 star roll, which puts flat and percentage stats on
+// the same scale and counts a 70% roll as 0.7
+fn roll_fraction(stat: &ArtifactStat) -> f64 {
+    match roll_tiers(&stat.key, 5) {
+        Some(tiers) => stat.value / tiers[3],
+        None => 0.0,
+    }
+}
+
+// Crit rate counted twice plus crit damage, substats only
+pub fn crit_value(art: &InternalArtifact) -> f64 {
+    round_score(
+        art.substats()
+            .iter()
+            .map(|s| match s.key {
+                ArtifactStatKey::Critical => s.value * 2.0,
+                ArtifactStatKey::CriticalDamage => s.value,
+                _ => 0.0,
+            })
+            .sum(),
+    )
+}
+
+// Sum of all substats in percent of a max roll, 100 per max roll
+pub fn roll_value(art: &InternalArtifact) -> f64 {
+    round_score(art.substats().iter().map(|s| roll_fraction(s)).sum::<f64>() * 100.0)
+}
+
+// A way to score an artifact, for ranking and thresholds
+#[derive(Debug, Clone)]
+pub enum ScoreMetric {
+    CritValue,
+    RollValue,
+    Profile(ScoreProfile),
+}
+
+impl ScoreMetric {
+    // "crit_value", "roll_value" or the name of one of `profiles`
+    pub fn from_name(name: &str, profiles: &[ScoreProfile]) -> Option<ScoreMetric> {
+        match name {
+            "crit_value" => Some(ScoreMetric::CritValue),
+            "roll_value" => Some(ScoreMetric::RollValue),
+            _ => profiles
+                .iter()
+                .find(|p| p.name == name)
+                .map(|p| ScoreMetric::Profile(p.clone())),
+        }
+    }
+
+    pub fn score(&self, art: &InternalArtifact) -> f64 {
+        match self {
+            ScoreMetric::CritValue => crit_value(art),
+            ScoreMetric::RollValue => roll_value(art),
+            ScoreMetric::Profile(p) => p.score(art),
+        }
+    }
+
+    // Score added by one point of a substat, every metric is linear in the substats
+    pub fn stat_weight(&self, key: &ArtifactStatKey) -> f64 {
+        let per_roll = match roll_tiers(key, 5) {
+            Some(tiers) => 100.0 / tiers[3],
+            None => 0.0,
+        };
+        match self {
+            ScoreMetric::CritValue => match key {
+                ArtifactStatKey::Critical => 2.0,
+                ArtifactStatKey::CriticalDamage => 1.0,
+                _ => 0.0,
+            },
+            ScoreMetric::RollValue => per_roll,
+            ScoreMetric::Profile(p) => p.weight(key) * per_roll,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArtifactScore {
+    pub crit_value: f64,
+    pub roll_value: f64,
+    // one score per profile, in profile order
+    pub profiles: Vec<(String, f64)>,
+    // profile of the character wearing the artifact, if any
+    pub equipped_profile: Option<String>,
+}
+
+impl ArtifactScore {
+    pub fn new(art: &InternalArtifact, profiles: &[ScoreProfile]) -> ArtifactScore {
+        let equipped_profile = art.location.and_then(|c| {
+            profiles
+                .iter()
+                .find(|p| p.characters.contains(&c))
+                .map(|p| p.name.clone())
+        });
+
+        ArtifactScore {
+            crit_value: crit_value(art),
+            roll_value: roll_value(art),
+            profiles: profiles
+                .iter()
+                .map(|p| (p.name.clone(), p.score(art)))
+                .collect(),
+            equipped_profile,
+        }
+    }
+
+    // Highest scoring profile, ties go to the earlier one
+    pub fn best(&self) -> Option<&(String, f64)> {
+        self.profiles
+            .iter()
+            .fold(None, |acc: Option<&(String, f64)>, x| match acc {
+                Some(a) if a.1 >= x.1 => Some(a),
+                _ => Some(x),
+            })
+    }
+}
+
+// Indices into `results`, best first
+pub fn rank(results: &[InternalArtifact], profile: &ScoreProfile) -> Vec<(usize, f64)> {
+    let mut ranked: Vec<(usize, f64)> = results
+        .iter()
+        .enumerate()
+        .map(|(i, art)| (i, profile.score(art)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+
+    ranked
+}
+
+// {"version": 1, "profiles": [{"name": ..., "weights": {"critRate_": 1, ...},
+// "characters": ["HuTao", ...]}]}, stats and characters use GOOD keys
+pub fn score_profiles_from_str(s: &str) -> Result<Vec<ScoreProfile>, String> {
+    let json: Value = match serde_json::from_str(s) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("cannot parse score profiles: {}", e)),
+    };
+
+    let version = match json["version"].as_u64() {
+        Some(v) => v,
+        None => return Err(String::from("missing version")),
+    };
+    if version > SCORE_PROFILES_VERSION {
+        return Err(format!(
+            "score profile version {} is newer than supported {}",
+            version, SCORE_PROFILES_VERSION
+        ));
+    }
+
+    let mut profiles: Vec<ScoreProfile> = Vec::new();
+    for (i, item) in json["profiles"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .enumerate()
+    {
+        let name = match item["name"].as_str() {
+            Some(s) if !s.is_empty() => String::from(s),
+            _ => return Err(format!("profiles[{}]: missing name", i)),
+        };
+
+        let mut weights: HashMap<ArtifactStatKey, f64> = HashMap::new();
+        if let Some(obj) = item["weights"].as_object() {
+            for (stat, weight) in obj.iter() {
+                let key = match ArtifactStatKey::from_good(stat) {
+                    Some(v) => v,
+                    None => return Err(format!("{}: unknown stat {}", name, stat)),
+                };
+                let weight = match weight.as_f64() {
+                    Some(v) => v,
+                    None => return Err(format!("{}: weight of {} is not a number", name, stat)),
+                };
+                weights.insert(key, weight);
+            }
+        }
+        if weights.is_empty() {
+            return Err(format!("{}: no weights", name));
+        }
+
+        let mut characters: Vec<CharacterKey> = Vec::new();
+        for c in item["characters"].as_array().unwrap_or(&Vec::new()).iter() {
+            let s = c.as_str().unwrap_or("");
+            match CharacterKey::from_good(s) {
+                Some(v) => characters.push(v),
+                // a profile written for a newer game version still works without them
+                None => warn!("{}: unknown character {}", name, s),
+            }
+        }
+
+        profiles.push(ScoreProfile {
+            name,
+            weights,
+            characters,
+        });
+    }
+
+    if profiles.is_empty() {
+        return Err(String::from("no score profiles"));
+    }
+
+    Ok(profiles)
+}
+
+// Profiles from `path`, or `score_profiles.json` in the working directory, falling back to
+// the compiled-in ones
+pub fn load_score_profiles(path: Option<&str>) -> Vec<ScoreProfile> {
+    let path = match path {
+        Some(p) => Some(String::from(p)),
+        None if Path::new("score_profiles.json").exists() => {
+            Some(String::from("score_profiles.json"))
+        }
+        None => None,
+    };
+
+    if let Some(p) = path {
+        match std::fs::read_to_string(&p)
+            .map_err(|e| format!("cannot read {}: {}", p, e))
+            .and_then(|s| score_profiles_from_str(&s))
+        {
+            Ok(v) => {
+                info!("score profiles: {}", p);
+                return v;
+            }
+            Err(e) => warn!("{}, using builtin score profiles", e),
+        }
+    }
+
+    score_profiles_from_str(BUILTIN_SCORE_PROFILES).expect("builtin score profiles are broken")
+}
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+
+use crate::artifact::internal_artifact::{ArtifactStatKey, InternalArtifact};
+use crate::artifact::rolls::{max_level, roll_tiers};
+use crate::artifact::score::ScoreMetric;
+
+// How likely each stat is to be picked as a new substat
+const SUBSTAT_WEIGHTS: [(ArtifactStatKey, f64); 10] = [
+    (ArtifactStatKey::Hp, 6.0),
+    (ArtifactStatKey::Atk, 6.0),
+    (ArtifactStatKey::Def, 6.0),
+    (ArtifactStatKey::HpPercentage, 4.0),
+    (ArtifactStatKey::AtkPercentage, 4.0),
+    (ArtifactStatKey::DefPercentage, 4.0),
+    (ArtifactStatKey::Recharge, 4.0),
+    (ArtifactStatKey::ElementalMastery, 4.0),
+    (ArtifactStatKey::Critical, 3.0),
+    (ArtifactStatKey::CriticalDamage, 3.0),
+];
+
+// Scores are kept as integers in units of 1/SCALE so equal outcomes add up
+const SCALE: f64 = 10000.0;
+
+// Score distribution, scaled score to probability
+type Distribution = BTreeMap<i64, f64>;
+
+// Adds one of `steps` (score change, probability) to every outcome
+fn convolve(dist: &Distribution, steps: &[(f64, f64)]) -> Distribution {
+    let mut result = Distribution::new();
+    for (&score, &p) in dist.iter() {
+        for &(delta, q) in steps.iter() {
+            let key = score + (delta * SCALE).round() as i64;
+            *result.entry(key).or_insert(0.0) += p * q;
+        }
+    }
+    result
+}
+
+// The four equally likely values a roll of `key` adds to the score
+fn tier_steps(key: &ArtifactStatKey, rarity: u32, metric: &ScoreMetric) -> Vec<(f64, f64)> {
+    match roll_tiers(key, rarity) {
+        Some(tiers) => tiers
+            .iter()
+            .map(|t| (t * metric.stat_weight(key), 0.25))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn mean_roll(key: &ArtifactStatKey, rarity: u32) -> f64 {
+    roll_tiers(key, rarity).map_or(0.0, |t| t.iter().sum::<f64>() / 4.0)
+}
+
+// A substat the artifact has or may get
+#[derive(Debug, Clone)]
+pub struct SubstatOutlook {
+    pub key: ArtifactStatKey,
+    // None if the artifact does not have it yet
+    pub current: Option<f64>,
+    // chance of having it at max level
+    pub chance: f64,
+    // expected value at max level, 0 where it does not appear
+    pub expected: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpgradeSimulation {
+    pub upgrades: u32,
+    pub current: f64,
+    // final score and its probability, ascending by score
+    pub distribution: Vec<(f64, f64)>,
+    pub substats: Vec<SubstatOutlook>,
+}
+
+// One way the new substats can turn out
+struct Branch {
+    keys: Vec<ArtifactStatKey>,
+    probability: f64,
+    upgrades: u32,
+    scores: Distribution,
+}
+
+// Distribution of `metric` once `art` reaches max level. Every upgrade first adds a substat
+// until there are four, picked by SUBSTAT_WEIGHTS among the stats the artifact does not
+// have, then raises one of the four chosen uniformly by one of the four tiers, also uniformly.
+// Values are not rounded the way the game displays them. None below 3 stars.
+pub fn simulate_upgrades(
+    art: &InternalArtifact,
+    metric: &ScoreMetric,
+) -> Option<UpgradeSimulation> {
+    roll_tiers(&ArtifactStatKey::Critical, art.rarity)?;
+    let cap = max_level(art.rarity);
+    let upgrades = cap / 4 - art.level.min(cap) / 4;
+    let current = metric.score(art);
+
+    let mut start = Distribution::new();
+    start.insert((current * SCALE).round() as i64, 1.0);
+    let mut pending = vec![Branch {
+        keys: art.substats().iter().map(|s| s.key.clone()).collect(),
+        probability: 1.0,
+        upgrades,
+        scores: start,
+    }];
+
+    // new substats first
+    let mut branches: Vec<Branch> = Vec::new();
+    while let Some(b) = pending.pop() {
+        if b.keys.len() >= 4 || b.upgrades == 0 {
+            branches.push(b);
+            continue;
+        }
+        let candidates: Vec<&(ArtifactStatKey, f64)> = SUBSTAT_WEIGHTS
+            .iter()
+            .filter(|(k, _)| *k != art.main_stat.key && !b.keys.contains(k))
+            .collect();
+        let total: f64 = candidates.iter().map(|(_, w)| w).sum();
+        for (key, w) in candidates {
+            let mut keys = b.keys.clone();
+            keys.push(key.clone());
+            pending.push(Branch {
+                keys,
+                probability: b.probability * w / total,
+                upgrades: b.upgrades - 1,
+                scores: convolve(&b.scores, &tier_steps(key, art.rarity, metric)),
+            });
+        }
+    }
+
+    let mut total = Distribution::new();
+    let mut substats: Vec<SubstatOutlook> = Vec::new();
+    for b in branches.iter() {
+        let mut steps: Vec<(f64, f64)> = Vec::new();
+        for key in b.keys.iter() {
+            for (delta, p) in tier_steps(key, art.rarity, metric) {
+                steps.push((delta, p / b.keys.len() as f64));
+            }
+        }
+        let mut scores = b.scores.clone();
+        for _ in 0..b.upgrades {
+            scores = convolve(&scores, &steps);
+        }
+        for (score, p) in scores {
+            *total.entry(score).or_insert(0.0) += p * b.probability;
+        }
+
+        for key in b.keys.iter() {
+            let current = art
+                .substats()
+                .iter()
+                .find(|s| s.key == *key)
+                .map(|s| s.value);
+            let rolls = b.upgrades as f64 / b.keys.len() as f64;
+            let expected =
+                current.unwrap_or(mean_roll(key, art.rarity)) + rolls * mean_roll(key, art.rarity);
+            match substats.iter_mut().find(|s| s.key == *key) {
+                Some(s) => {
+                    s.chance += b.probability;
+                    s.expected += b.probability * expected;
+                }
+                None => substats.push(SubstatOutlook {
+                    key: key.clone(),
+                    current,
+                    chance: b.probability,
+                    expected: b.probability * expected,
+                }),
+            }
+        }
+    }
+    // existing substats first, then the likeliest new ones
+    substats.sort_by(|a, b| {
+        b.current
+            .is_some()
+            .cmp(&a.current.is_some())
+            .then(b.chance.partial_cmp(&a.chance).unwrap())
+    });
+
+    Some(UpgradeSimulation {
+        upgrades,
+        current,
+        distribution: total
+            .into_iter()
+            .map(|(score, p)| (score as f64 / SCALE, p))
+            .collect(),
+        substats,
+    })
+}
+
+impl UpgradeSimulation {
+    pub fn expected(&self) -> f64 {
+        self.distribution.iter().map(|(s, p)| s * p).sum()
+    }
+
+    // Score that a share `q` (0 to 1) of outcomes stays at or below, 0.5 for the median
+    pub fn percentile(&self, q: f64) -> f64 {
+        let mut cumulative = 0.0;
+        for &(score, p) in self.distribution.iter() {
+            cumulative += p;
+            if cumulative >= q - 1e-9 {
+                return score;
+            }
+        }
+        self.distribution.last().map_or(self.current, |d| d.0)
+    }
+
+    // Chance of ending at or above `target`
+    pub fn chance_at_least(&self, target: f64) -> f64 {
+        self.distribution
+            .iter()
+            .filter(|(s, _)| *s >= target - 1e-6)
+            .fold(0.0, |sum, (_, p)| sum + p)
+    }
+}
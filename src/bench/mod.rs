@@ -0,0 +1 @@
+pub mod ocr;
\ No newline at end of file
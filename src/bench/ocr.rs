@@ -0,0 +1,469 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::common::RawImage;
+use crate::inference::digit::{
+    numeric_tail, DigitRecognizer, DigitTemplateBuilder, DIGIT_ALPHABET,
+};
+use crate::inference::inference::CRNNModel;
+use crate::inference::pre_process::{image_to_raw, pre_process};
+use crate::inference::title::{save_title_templates, TitleMatcher};
+
+// A labeled corpus is a directory of raw crops, each `xxx.png` next to a `xxx.txt` holding the
+// expected string. Files written by `--dump` (`title_12.png` + `title_12.txt`) can be used
+// directly once the txt files are corrected; preprocessed `p_*.png` dumps are skipped.
+pub struct OcrFixture {
+    pub field: String,
+    pub path: PathBuf,
+    pub label: String,
+}
+
+pub struct OcrSample {
+    pub field: String,
+    pub path: PathBuf,
+    pub label: String,
+    pub predict: String,
+    pub distance: usize,
+}
+
+impl OcrSample {
+    pub fn cer(&self) -> f64 {
+        let len = self.label.chars().count();
+        if len == 0 {
+            return if self.distance == 0 { 0.0 } else { 1.0 };
+        }
+        self.distance as f64 / len as f64
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct FieldStat {
+    pub samples: u32,
+    pub exact: u32,
+    pub distance: usize,
+    pub chars: usize,
+}
+
+impl FieldStat {
+    fn add(&mut self, sample: &OcrSample) {
+        self.samples += 1;
+        if sample.distance == 0 {
+            self.exact += 1;
+        }
+        self.distance += sample.distance;
+        self.chars += sample.label.chars().count();
+    }
+
+    pub fn cer(&self) -> f64 {
+        if self.chars == 0 {
+            return 0.0;
+        }
+        self.distance as f64 / self.chars as f64
+    }
+
+    pub fn exact_match_rate(&self) -> f64 {
+        if self.samples == 0 {
+            return 1.0;
+        }
+        self.exact as f64 / self.samples as f64
+    }
+}
+
+pub struct OcrBenchReport {
+    pub samples: Vec<OcrSample>,
+    pub fields: BTreeMap<String, FieldStat>,
+    pub total: FieldStat,
+    // ms per sample, pre_process included
+    pub avg_inference_time: f64,
+}
+
+// "title_12" -> "title", "sub_stat_3_12" -> "sub_stat"
+pub fn field_of(stem: &str) -> String {
+    let mut field = stem;
+    if let Some(pos) = field.rfind('_') {
+        if pos + 1 < field.len() && field[pos + 1..].chars().all(|c| c.is_ascii_digit()) {
+            field = &field[..pos];
+        }
+    }
+    if field.starts_with("sub_stat_") {
+        return String::from("sub_stat");
+    }
+    String::from(field)
+}
+
+// Expected output of the digit recognizer for a numeric field, and whether the whole crop
+// is numeric. For "暴击率+3.9%" it is "3.9%", for "圣遗物 1234/1500" it is "1234/1500".
+pub fn digit_label(field: &str, label: &str) -> Option<(String, bool)> {
+    match field {
+        "level" | "main_stat_value" => Some((String::from(label), true)),
+        "sub_stat" | "count" => {
+            let chars: Vec<char> = label.chars().collect();
+            let len = chars
+                .iter()
+                .rev()
+                .take_while(|c| **c != '+' && DIGIT_ALPHABET.contains(**c))
+                .count();
+            if len == 0 {
+                return None;
+            }
+            Some((chars[chars.len() - len..].iter().collect(), false))
+        }
+        _ => None,
+    }
+}
+
+pub fn load_corpus<P: AsRef<Path>>(dir: P) -> Result<Vec<OcrFixture>, String> {
+    let entries = match fs::read_dir(dir.as_ref()) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("cannot read {}: {}", dir.as_ref().display(), e)),
+    };
+
+    let mut fixtures: Vec<OcrFixture> = Vec::new();
+    for entry in entries {
+        let path = match entry {
+            Ok(v) => v.path(),
+            Err(e) => return Err(e.to_string()),
+        };
+        if path.extension().is_none_or(|e| e != "png") {
+            continue;
+        }
+        let stem = path.file_stem().unwrap().to_string_lossy().to_string();
+        if stem.starts_with("p_") {
+            continue;
+        }
+        let label = match fs::read_to_string(path.with_extension("txt")) {
+            Ok(v) => v.trim().to_string(),
+            // unlabeled crop
+            Err(_) => continue,
+        };
+
+        fixtures.push(OcrFixture {
+            field: field_of(&stem),
+            path,
+            label,
+        });
+    }
+    fixtures.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(fixtures)
+}
+
+fn load_crop(path: &Path) -> Result<RawImage, String> {
+    let img = match image::open(path) {
+        Ok(v) => v.to_luma8(),
+        Err(e) => return Err(format!("cannot open {}: {}", path.display(), e)),
+    };
+
+    Ok(pre_process(image_to_raw(img)))
+}
+
+// Numeric samples are additionally run through `digit` and reported as "digit_<field>",
+// titles through `title` as "template_title"
+pub fn run_benchmark(
+    model: &CRNNModel,
+    digit: Option<&DigitRecognizer>,
+    title: Option<&TitleMatcher>,
+    fixtures: Vec<OcrFixture>,
+) -> Result<OcrBenchReport, String> {
+    let mut samples: Vec<OcrSample> = Vec::new();
+    let mut fields: BTreeMap<String, FieldStat> = BTreeMap::new();
+    let mut total = FieldStat::default();
+
+    let now = SystemTime::now();
+    for fixture in fixtures {
+        let processed_img = load_crop(&fixture.path)?;
+        let predict = model.inference_string(&processed_img);
+
+        let digit_sample = match (digit, digit_label(&fixture.field, &fixture.label)) {
+            (Some(digit), Some((label, whole))) => {
+                let digit_predict = if whole {
+                    digit.recognize(&processed_img)
+                } else if fixture.field == "count" {
+                    // on the length the model found, as the scanner reads it
+                    let len = numeric_tail(&predict).chars().count();
+                    digit.recognize_tail(&processed_img, len)
+                } else {
+                    digit.recognize_suffix(&processed_img)
+                };
+                let digit_predict = digit_predict.unwrap_or_default();
+                Some(OcrSample {
+                    field: format!("digit_{}", fixture.field),
+                    path: fixture.path.clone(),
+                    distance: edit_distance::edit_distance(&label, &digit_predict),
+                    label,
+                    predict: digit_predict,
+                })
+            }
+            _ => None,
+        };
+        let title_sample = match title {
+            Some(title) if fixture.field == "title" => {
+                let predict = title.recognize(&processed_img).unwrap_or_default();
+                Some(OcrSample {
+                    field: String::from("template_title"),
+                    path: fixture.path.clone(),
+                    label: fixture.label.clone(),
+                    distance: edit_distance::edit_distance(&fixture.label, &predict),
+                    predict,
+                })
+            }
+            _ => None,
+        };
+
+        let distance = edit_distance::edit_distance(&fixture.label, &predict);
+
+        let sample = OcrSample {
+            field: fixture.field,
+            path: fixture.path,
+            label: fixture.label,
+            predict,
+            distance,
+        };
+        fields.entry(sample.field.clone()).or_default().add(&sample);
+        total.add(&sample);
+        samples.push(sample);
+
+        for sample in digit_sample.into_iter().chain(title_sample) {
+            fields.entry(sample.field.clone()).or_default().add(&sample);
+            samples.push(sample);
+        }
+    }
+
+    let avg_inference_time = if samples.is_empty() {
+        0.0
+    } else {
+        now.elapsed().unwrap().as_millis() as f64 / samples.len() as f64
+    };
+
+    Ok(OcrBenchReport {
+        samples,
+        fields,
+        total,
+        avg_inference_time,
+    })
+}
+
+// Builds digit templates from the numeric fixtures, returns the recognizer and how many
+// crops were usable
+pub fn build_digit_templates(fixtures: &[OcrFixture]) -> Result<(DigitRecognizer, u32), String> {
+    let mut builder = DigitTemplateBuilder::new();
+    let mut used = 0;
+    for fixture in fixtures {
+        if digit_label(&fixture.field, &fixture.label).is_none() {
+            continue;
+        }
+        let processed_img = load_crop(&fixture.path)?;
+        if builder.add(&processed_img, &fixture.label) {
+            used += 1;
+        }
+    }
+
+    Ok((builder.build(), used))
+}
+
+// Writes one template per known artifact name from the labeled title fixtures
+pub fn build_title_templates(fixtures: &[OcrFixture], dir: &str) -> Result<u32, String> {
+    let crops: Vec<(String, &Path)> = fixtures
+        .iter()
+        .filter(|fixture| fixture.field == "title")
+        .map(|fixture| (fixture.label.clone(), fixture.path.as_path()))
+        .collect();
+
+    save_title_templates(&crops, dir)
+}
+
+impl OcrBenchReport {
+    pub fn worst(&self, n: usize) -> Vec<&OcrSample> {
+        let mut wrong: Vec<&OcrSample> = self.samples.iter().filter(|s| s.distance > 0).collect();
+        wrong.sort_by(|a, b| {
+            b.cer()
+                .partial_cmp(&a.cer())
+                .unwrap()
+                .then(b.distance.cmp(&a.distance))
+        });
+        wrong.truncate(n);
+        wrong
+    }
+
+    pub fn print(&self, worst: usize) {
+        println!(
+            "{:<16}{:>8}{:>10}{:>10}",
+            "field", "samples", "exact", "cer"
+        );
+        for (field, stat) in self.fields.iter() {
+            println!(
+                "{:<16}{:>8}{:>9.2}%{:>9.2}%",
+                field,
+                stat.samples,
+                stat.exact_match_rate() * 100.0,
+                stat.cer() * 100.0
+            );
+        }
+        println!(
+            "{:<16}{:>8}{:>9.2}%{:>9.2}%",
+            "total",
+            self.total.samples,
+            self.total.exact_match_rate() * 100.0,
+            self.total.cer() * 100.0
+        );
+        println!("avg inference time: {:.2}ms", self.avg_inference_time);
+
+        let worst = self.worst(worst);
+        if !worst.is_empty() {
+            println!();
+            println!("worst cases:");
+            for sample in worst {
+                println!(
+                    "{} [{}] label: {}, predict: {}, distance: {}",
+                    sample.path.display(),
+                    sample.field,
+                    sample.label,
+                    sample.predict,
+                    sample.distance
+                );
+            }
+        }
+    }
+
+    pub fn save(&self, path: String) {
+        let mut file = match File::create(&path) {
+            Err(why) => panic!("couldn't create {}: {}", path, why),
+            Ok(file) => file,
+        };
+        let s = serde_json::to_string(&self).unwrap();
+
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+}
+
+struct FieldStatEntry<'a> {
+    stat: &'a FieldStat,
+}
+
+impl<'a> Serialize for FieldStatEntry<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(4))?;
+        root.serialize_entry("samples", &self.stat.samples)?;
+        root.serialize_entry("exact", &self.stat.exact)?;
+        root.serialize_entry("exactMatchRate", &self.stat.exact_match_rate())?;
+        root.serialize_entry("cer", &self.stat.cer())?;
+        root.end()
+    }
+}
+
+struct SampleEntry<'a> {
+    sample: &'a OcrSample,
+}
+
+impl<'a> Serialize for SampleEntry<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(5))?;
+        root.serialize_entry("path", &self.sample.path.to_string_lossy())?;
+        root.serialize_entry("field", &self.sample.field)?;
+        root.serialize_entry("label", &self.sample.label)?;
+        root.serialize_entry("predict", &self.sample.predict)?;
+        root.serialize_entry("distance", &self.sample.distance)?;
+        root.end()
+    }
+}
+
+impl Serialize for OcrBenchReport {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let fields: BTreeMap<&String, FieldStatEntry> = self
+            .fields
+            .iter()
+            .map(|(k, stat)| (k, FieldStatEntry { stat }))
+            .collect();
+        let failed: Vec<SampleEntry> = self
+            .worst(self.samples.len())
+            .into_iter()
+            .map(|sample| SampleEntry { sample })
+            .collect();
+
+        let mut root = serializer.serialize_map(Some(4))?;
+        root.serialize_entry("total", &FieldStatEntry { stat: &self.total })?;
+        root.serialize_entry("fields", &fields)?;
+        root.serialize_entry("avgInferenceTime", &self.avg_inference_time)?;
+        root.serialize_entry("failed", &failed)?;
+        root.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(label: &str, predict: &str) -> OcrSample {
+        OcrSample {
+            field: String::from("title"),
+            path: PathBuf::from("title_0.png"),
+            label: String::from(label),
+            predict: String::from(predict),
+            distance: edit_distance::edit_distance(label, predict),
+        }
+    }
+
+    #[test]
+    fn cer_counts_characters() {
+        assert_eq!(sample("角斗士的留恋", "角斗土的留恋").cer(), 1.0 / 6.0);
+        assert_eq!(sample("+20", "+20").cer(), 0.0);
+        assert_eq!(sample("", "").cer(), 0.0);
+        assert_eq!(sample("", "x").cer(), 1.0);
+
+        let mut total = FieldStat::default();
+        total.add(&sample("+20", "+20"));
+        total.add(&sample("46.6%", "4.6%"));
+        assert_eq!(total.cer(), 1.0 / 8.0);
+        assert_eq!(total.exact_match_rate(), 0.5);
+        assert_eq!(FieldStat::default().cer(), 0.0);
+    }
+
+    #[test]
+    fn field_of_strips_the_index() {
+        assert_eq!(field_of("title_12"), "title");
+        assert_eq!(field_of("main_stat_value_3"), "main_stat_value");
+        assert_eq!(field_of("sub_stat_3_12"), "sub_stat");
+        assert_eq!(field_of("sub_stat_3"), "sub_stat");
+        assert_eq!(field_of("level"), "level");
+        assert_eq!(field_of("count_"), "count_");
+    }
+
+    #[test]
+    fn digit_label_takes_the_numeric_part() {
+        assert_eq!(
+            digit_label("level", "+20"),
+            Some((String::from("+20"), true))
+        );
+        assert_eq!(
+            digit_label("main_stat_value", "46.6%"),
+            Some((String::from("46.6%"), true))
+        );
+        assert_eq!(
+            digit_label("sub_stat", "暴击率+3.9%"),
+            Some((String::from("3.9%"), false))
+        );
+        assert_eq!(
+            digit_label("count", "圣遗物 1234/1500"),
+            Some((String::from("1234/1500"), false))
+        );
+        assert_eq!(digit_label("sub_stat", "2件套：攻击力"), None);
+        assert_eq!(digit_label("title", "角斗士的留恋"), None);
+    }
+}
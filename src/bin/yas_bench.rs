@@ -0,0 +1,191 @@
+use std::process;
+use std::str::FromStr;
+
+use yas::bench::ocr::{build_digit_templates, build_title_templates, load_corpus, run_benchmark};
+use yas::inference::digit::DigitRecognizer;
+use yas::inference::inference::CRNNModel;
+use yas::inference::title::TitleMatcher;
+
+use clap::{App, Arg, ArgMatches};
+use env_logger::Builder;
+use log::{error, info, LevelFilter};
+
+// Value of a numeric option, exits when it is not a number
+fn number_arg<T: FromStr>(matches: &ArgMatches, name: &str) -> Option<T> {
+    matches.value_of(name).map(|v| match v.parse::<T>() {
+        Ok(v) => v,
+        Err(_) => {
+            error!("--{} is not a number: {}", name, v);
+            process::exit(2);
+        }
+    })
+}
+
+fn main() {
+    Builder::new().filter_level(LevelFilter::Info).init();
+
+    let matches = App::new("YAS OCR Benchmark")
+        .about("在标注过的截图集上评估识别准确率")
+        .arg(
+            Arg::with_name("corpus")
+                .required(true)
+                .takes_value(true)
+                .help("标注数据目录（xxx.png + xxx.txt）"),
+        )
+        .arg(
+            Arg::with_name("worst")
+                .long("worst")
+                .takes_value(true)
+                .default_value("10")
+                .help("列出错误最严重的样本数"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .help("保存json报告"),
+        )
+        .arg(
+            Arg::with_name("max-cer")
+                .long("max-cer")
+                .takes_value(true)
+                .help("字符错误率上限，超出时返回非零退出码"),
+        )
+        .arg(
+            Arg::with_name("min-exact")
+                .long("min-exact")
+                .takes_value(true)
+                .help("完全匹配率下限，低于时返回非零退出码"),
+        )
+        .arg(
+            Arg::with_name("digit-templates")
+                .long("digit-templates")
+                .takes_value(true)
+                .help("同时评估数字模板识别"),
+        )
+        .arg(
+            Arg::with_name("build-digit-templates")
+                .long("build-digit-templates")
+                .takes_value(true)
+                .help("用数字类字段（等级、数值、数量）生成数字模板并保存"),
+        )
+        .arg(
+            Arg::with_name("title-templates")
+                .long("title-templates")
+                .takes_value(true)
+                .help("同时评估圣遗物名称模板匹配"),
+        )
+        .arg(
+            Arg::with_name("build-title-templates")
+                .long("build-title-templates")
+                .takes_value(true)
+                .help("用标注的名称截图生成圣遗物名称模板目录"),
+        )
+        .get_matches();
+    let worst: usize = number_arg(&matches, "worst").unwrap();
+    let max_cer: Option<f64> = number_arg(&matches, "max-cer");
+    let min_exact: Option<f64> = number_arg(&matches, "min-exact");
+
+    let fixtures = match load_corpus(matches.value_of("corpus").unwrap()) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(2);
+        }
+    };
+    info!("loaded {} labeled crops", fixtures.len());
+    // an empty corpus would pass any threshold
+    if fixtures.is_empty() {
+        error!(
+            "no labeled crops in {}",
+            matches.value_of("corpus").unwrap()
+        );
+        process::exit(2);
+    }
+
+    if let Some(path) = matches.value_of("build-digit-templates") {
+        match build_digit_templates(&fixtures) {
+            Ok((digit, used)) => {
+                info!("built digit templates from {} crops", used);
+                digit.save(String::from(path));
+            }
+            Err(e) => {
+                error!("{}", e);
+                process::exit(2);
+            }
+        }
+        return;
+    }
+
+    if let Some(dir) = matches.value_of("build-title-templates") {
+        match build_title_templates(&fixtures, dir) {
+            Ok(count) => info!("saved {} title templates", count),
+            Err(e) => {
+                error!("{}", e);
+                process::exit(2);
+            }
+        }
+        return;
+    }
+
+    let digit = match matches.value_of("digit-templates") {
+        Some(path) => match DigitRecognizer::from_file(path) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                error!("{}", e);
+                process::exit(2);
+            }
+        },
+        None => None,
+    };
+
+    let title = match matches.value_of("title-templates") {
+        Some(dir) => match TitleMatcher::from_dir(dir) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                error!("{}", e);
+                process::exit(2);
+            }
+        },
+        None => None,
+    };
+
+    let model = CRNNModel::new(
+        String::from("model_training.onnx"),
+        String::from("index_2_word.json"),
+    );
+    let report = match run_benchmark(&model, digit.as_ref(), title.as_ref(), fixtures) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    report.print(worst);
+    if let Some(path) = matches.value_of("output") {
+        report.save(String::from(path));
+    }
+
+    let mut failed = false;
+    if let Some(max_cer) = max_cer {
+        if report.total.cer() > max_cer {
+            error!("cer {:.4} > {:.4}", report.total.cer(), max_cer);
+            failed = true;
+        }
+    }
+    if let Some(min_exact) = min_exact {
+        if report.total.exact_match_rate() < min_exact {
+            error!(
+                "exact match rate {:.4} < {:.4}",
+                report.total.exact_match_rate(),
+                min_exact
+            );
+            failed = true;
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
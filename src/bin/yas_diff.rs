@@ -0,0 +1,68 @@
+use std::process;
+
+use yas::artifact::game_data::init_game_data;
+use yas::inventory::diff::{read_inventory, Inventory, InventoryDiff};
+
+use clap::{App, Arg};
+use env_logger::Builder;
+use log::{error, warn, LevelFilter};
+
+fn load(path: &str) -> Inventory {
+    match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|s| read_inventory(&s))
+    {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}: {}", path, e);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    Builder::new().filter_level(LevelFilter::Info).init();
+
+    let matches = App::new("YAS Inventory Diff")
+        .about("比较两次扫描结果，列出新增、移除、强化、更换装备和改变锁定的圣遗物")
+        .arg(
+            Arg::with_name("old")
+                .required(true)
+                .takes_value(true)
+                .help("较早的导出文件（good.json 或 mona.json）"),
+        )
+        .arg(
+            Arg::with_name("new")
+                .required(true)
+                .takes_value(true)
+                .help("较新的导出文件（good.json 或 mona.json）"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .help("将比较结果保存为json"),
+        )
+        .arg(
+            Arg::with_name("game-data")
+                .long("game-data")
+                .takes_value(true)
+                .help("游戏数据文件"),
+        )
+        .get_matches();
+
+    init_game_data(matches.value_of("game-data"));
+
+    let old = load(matches.value_of("old").unwrap());
+    let new = load(matches.value_of("new").unwrap());
+    let compare_state = old.has_state && new.has_state;
+    if !compare_state {
+        warn!("mona files have no lock state or location, only comparing stats");
+    }
+
+    let diff = InventoryDiff::new(&old.artifacts, &new.artifacts, compare_state);
+    diff.print();
+    if let Some(path) = matches.value_of("output") {
+        diff.save(String::from(path));
+    }
+}
@@ -0,0 +1,153 @@
+use std::path::Path;
+use std::process;
+
+use yas::artifact::game_data::init_game_data;
+use yas::inventory::history::{format_time, parse_time, HistoryStore};
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use env_logger::Builder;
+use log::{error, info, LevelFilter};
+
+fn exit_on_error<T>(r: Result<T, String>) -> T {
+    match r {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(2);
+        }
+    }
+}
+
+// Scan chosen by `--scan` or `--at`, the latest one otherwise
+fn select_scan(store: &HistoryStore, matches: &ArgMatches) -> u32 {
+    let entry = if let Some(id) = matches.value_of("scan") {
+        store.scan(id.parse::<u32>().unwrap_or(0))
+    } else if let Some(at) = matches.value_of("at") {
+        store.at(exit_on_error(parse_time(at)))
+    } else {
+        store.scans().last()
+    };
+
+    match entry {
+        Some(v) => v.id,
+        None => {
+            error!("no such scan");
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    Builder::new().filter_level(LevelFilter::Info).init();
+
+    let scan_args = || {
+        vec![
+            Arg::with_name("scan")
+                .long("scan")
+                .takes_value(true)
+                .help("扫描编号"),
+            Arg::with_name("at")
+                .long("at")
+                .takes_value(true)
+                .conflicts_with("scan")
+                .help("使用该时间（UTC，YYYY-MM-DD [HH:MM[:SS]]）之前最后一次扫描"),
+        ]
+    };
+    let matches = App::new("YAS History")
+        .about("查看保存的扫描历史")
+        .arg(
+            Arg::with_name("dir")
+                .required(true)
+                .takes_value(true)
+                .help("扫描时--history指定的目录"),
+        )
+        .arg(
+            Arg::with_name("game-data")
+                .long("game-data")
+                .takes_value(true)
+                .help("游戏数据文件"),
+        )
+        .subcommand(SubCommand::with_name("list").about("列出所有扫描"))
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("重新生成某次扫描的mona.json、genmo.json和good.json")
+                .args(&scan_args())
+                .arg(
+                    Arg::with_name("output-dir")
+                        .long("output-dir")
+                        .takes_value(true)
+                        .default_value(".")
+                        .help("输出目录"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("track")
+                .about("追踪一个圣遗物在各次扫描中的变化")
+                .args(&scan_args())
+                .arg(
+                    Arg::with_name("index")
+                        .required(true)
+                        .takes_value(true)
+                        .help("圣遗物在该次扫描good.json中的序号（从0开始）"),
+                ),
+        )
+        .get_matches();
+
+    init_game_data(matches.value_of("game-data"));
+    let store = exit_on_error(HistoryStore::open(Path::new(
+        matches.value_of("dir").unwrap(),
+    )));
+
+    match matches.subcommand() {
+        ("export", Some(m)) => {
+            let id = select_scan(&store, m);
+            exit_on_error(store.export(id, Path::new(m.value_of("output-dir").unwrap())));
+            info!("exported scan {}", id);
+        }
+        ("track", Some(m)) => {
+            let id = select_scan(&store, m);
+            let index = match m.value_of("index").unwrap().parse::<usize>() {
+                Ok(v) => v,
+                Err(_) => {
+                    error!("index is not a number");
+                    process::exit(2);
+                }
+            };
+            for (scan, position) in exit_on_error(store.track(id, index)) {
+                let time = format_time(store.scan(scan).unwrap().time);
+                match position {
+                    Some(p) => {
+                        let art = &exit_on_error(store.load(scan))[p];
+                        let subs: Vec<String> = art
+                            .substats()
+                            .iter()
+                            .map(|s| format!("{} {}", s.key.to_good(), s.value))
+                            .collect();
+                        println!(
+                            "#{} {}  [{}] {} +{} [{}]{}",
+                            scan,
+                            time,
+                            p,
+                            art.display_name(),
+                            art.level,
+                            subs.join(", "),
+                            if art.lock { " 已锁定" } else { "" }
+                        );
+                    }
+                    None => println!("#{} {}  -", scan, time),
+                }
+            }
+        }
+        _ => {
+            for s in store.scans() {
+                println!(
+                    "#{} {}  {} 件，识别失败 {}",
+                    s.id,
+                    format_time(s.time),
+                    s.artifacts,
+                    s.error_indices.len()
+                );
+            }
+        }
+    }
+}
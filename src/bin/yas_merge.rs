@@ -0,0 +1,63 @@
+use std::fs;
+use std::process;
+
+use yas::expo::good::merge_good;
+
+use clap::{App, Arg};
+use env_logger::Builder;
+use log::{error, info, warn, LevelFilter};
+use serde_json::Value;
+
+fn main() {
+    Builder::new().filter_level(LevelFilter::Info).init();
+
+    let matches = App::new("YAS GOOD Merge")
+        .about("将圣遗物、武器、角色和材料的扫描结果合并为一个完整的GOOD文件，供配装工具导入")
+        .arg(
+            Arg::with_name("input")
+                .required(true)
+                .multiple(true)
+                .takes_value(true)
+                .help("GOOD格式的导出文件，如 good.json good_weapons.json good_characters.json good_materials.json"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .default_value("good_all.json")
+                .help("合并结果"),
+        )
+        .get_matches();
+
+    let mut exports: Vec<(String, Value)> = Vec::new();
+    for path in matches.values_of("input").unwrap() {
+        let json = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str::<Value>(&s).map_err(|e| e.to_string()));
+        match json {
+            Ok(v) => exports.push((String::from(path), v)),
+            Err(e) => {
+                error!("{}: {}", path, e);
+                process::exit(2);
+            }
+        }
+    }
+
+    let (merged, warnings) = match merge_good(&exports) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(2);
+        }
+    };
+    for w in warnings.iter() {
+        warn!("{}", w);
+    }
+
+    let output = matches.value_of("output").unwrap();
+    if let Err(e) = fs::write(output, serde_json::to_string(&merged).unwrap()) {
+        error!("{}: {}", output, e);
+        process::exit(2);
+    }
+    info!("written to {}", output);
+}
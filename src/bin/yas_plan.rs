@@ -0,0 +1,146 @@
+use std::path::Path;
+use std::process;
+
+use yas::artifact::game_data::init_game_data;
+use yas::artifact::score::load_score_profiles;
+use yas::expo::good::read_good_artifacts;
+use yas::lock::planner::{lock_rules_from_str, plan_locks, LockAction};
+use yas::scanner::report::{grid_indices, read_error_indices, read_item_count};
+
+use clap::{App, Arg};
+use env_logger::Builder;
+use log::{error, info, warn, LevelFilter};
+
+fn read_file(path: &str) -> String {
+    match std::fs::read_to_string(path) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("cannot read {}: {}", path, e);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    Builder::new().filter_level(LevelFilter::Info).init();
+
+    let matches = App::new("YAS Lock Planner")
+        .about("根据规则和扫描结果生成 lock.json")
+        .arg(
+            Arg::with_name("good")
+                .required(true)
+                .takes_value(true)
+                .help("扫描得到的 good.json"),
+        )
+        .arg(
+            Arg::with_name("rules")
+                .long("rules")
+                .required(true)
+                .takes_value(true)
+                .help("锁定规则文件"),
+        )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .takes_value(true)
+                .help(
+                    "扫描报告 report.json，用于跳过识别失败的格子，默认使用 good.json 同目录下的",
+                ),
+        )
+        .arg(
+            Arg::with_name("output-dir")
+                .long("output-dir")
+                .takes_value(true)
+                .help("输出目录，默认与 good.json 相同"),
+        )
+        .arg(
+            Arg::with_name("score-profiles")
+                .long("score-profiles")
+                .takes_value(true)
+                .help("评分方案文件"),
+        )
+        .arg(
+            Arg::with_name("game-data")
+                .long("game-data")
+                .takes_value(true)
+                .help("游戏数据文件"),
+        )
+        .get_matches();
+
+    init_game_data(matches.value_of("game-data"));
+    let profiles = load_score_profiles(matches.value_of("score-profiles"));
+
+    let good_path = matches.value_of("good").unwrap();
+    let artifacts = match read_good_artifacts(&read_file(good_path)) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}: {}", good_path, e);
+            process::exit(2);
+        }
+    };
+    let rules_path = matches.value_of("rules").unwrap();
+    let rules = match lock_rules_from_str(&read_file(rules_path), &profiles) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}: {}", rules_path, e);
+            process::exit(2);
+        }
+    };
+
+    let good_dir = Path::new(good_path).parent().unwrap_or(Path::new("."));
+    let report_path = match matches.value_of("report") {
+        Some(p) => Some(String::from(p)),
+        None => {
+            let p = good_dir.join("report.json");
+            if p.exists() {
+                Some(String::from(p.to_str().unwrap()))
+            } else {
+                None
+            }
+        }
+    };
+    let report = report_path.map(|p| (read_file(&p), p));
+    let error_indices = match report {
+        Some((ref s, ref p)) => match read_error_indices(s) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("{}: {}", p, e);
+                process::exit(2);
+            }
+        },
+        None => {
+            warn!("no scan report, assuming every grid cell was recognized");
+            Vec::new()
+        }
+    };
+    let item_count = report.and_then(|(s, _)| read_item_count(&s));
+    if item_count.is_none() {
+        warn!("no item count in the scan report, the bag size cannot be checked later");
+    }
+    let indices = grid_indices(artifacts.len(), &error_indices);
+
+    let plan = plan_locks(&artifacts, &indices, &rules);
+    info!(
+        "lock {}, unlock {}, already fine {}, no rule {}",
+        plan.count(LockAction::Lock),
+        plan.count(LockAction::Unlock),
+        plan.satisfied,
+        plan.untouched
+    );
+
+    let output_dir = match matches.value_of("output-dir") {
+        Some(p) => Path::new(p).to_path_buf(),
+        None => good_dir.to_path_buf(),
+    };
+    plan.save_lock_file(
+        String::from(output_dir.join("lock.json").to_str().unwrap()),
+        &artifacts,
+        item_count,
+    );
+    plan.save_text(
+        String::from(output_dir.join("lock_plan.txt").to_str().unwrap()),
+        &artifacts,
+        &rules,
+    );
+    info!("written to {}", output_dir.display());
+}
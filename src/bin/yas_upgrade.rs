@@ -0,0 +1,112 @@
+use std::process;
+
+use yas::artifact::filter::Filter;
+use yas::artifact::game_data::init_game_data;
+use yas::artifact::score::{load_score_profiles, ScoreMetric};
+use yas::expo::good::read_good_artifacts;
+use yas::expo::upgrade::UpgradeReport;
+
+use clap::{App, Arg};
+use env_logger::Builder;
+use log::{error, LevelFilter};
+
+fn main() {
+    Builder::new().filter_level(LevelFilter::Info).init();
+
+    let matches = App::new("YAS Upgrade Outlook")
+        .about("估计未满级圣遗物强化到满级后的得分分布，判断是否值得强化")
+        .arg(
+            Arg::with_name("good")
+                .required(true)
+                .takes_value(true)
+                .help("扫描得到的 good.json"),
+        )
+        .arg(
+            Arg::with_name("by")
+                .long("by")
+                .takes_value(true)
+                .default_value("crit_value")
+                .help("得分：crit_value 双暴分、roll_value 词条分或评分方案名"),
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .takes_value(true)
+                .help("目标得分，按达到目标的概率排序"),
+        )
+        .arg(
+            Arg::with_name("filter")
+                .long("filter")
+                .takes_value(true)
+                .help("只估计符合条件的圣遗物"),
+        )
+        .arg(
+            Arg::with_name("top")
+                .long("top")
+                .takes_value(true)
+                .default_value("20")
+                .help("列出的数量"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .help("将全部结果保存为json"),
+        )
+        .arg(
+            Arg::with_name("score-profiles")
+                .long("score-profiles")
+                .takes_value(true)
+                .help("评分方案文件"),
+        )
+        .arg(
+            Arg::with_name("game-data")
+                .long("game-data")
+                .takes_value(true)
+                .help("游戏数据文件"),
+        )
+        .get_matches();
+
+    init_game_data(matches.value_of("game-data"));
+    let profiles = load_score_profiles(matches.value_of("score-profiles"));
+
+    let by = matches.value_of("by").unwrap();
+    let metric = match ScoreMetric::from_name(by, &profiles) {
+        Some(v) => v,
+        None => {
+            error!("unknown score {}", by);
+            process::exit(2);
+        }
+    };
+    let target = matches
+        .value_of("target")
+        .map(|v| v.parse::<f64>().unwrap());
+    let filter = match matches.value_of("filter").map(Filter::parse) {
+        Some(Ok(f)) => Some(f),
+        Some(Err(e)) => {
+            error!("{}", e);
+            process::exit(2);
+        }
+        None => None,
+    };
+
+    let path = matches.value_of("good").unwrap();
+    let artifacts = match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|s| read_good_artifacts(&s))
+    {
+        Ok(v) => v,
+        Err(e) => {
+            error!("{}: {}", path, e);
+            process::exit(2);
+        }
+    };
+
+    let report = UpgradeReport::new(&artifacts, &metric, target, |art| {
+        filter.as_ref().is_none_or(|f| f.matches(art))
+    });
+    report.print(matches.value_of("top").unwrap().parse::<usize>().unwrap());
+    if let Some(path) = matches.value_of("output") {
+        report.save(String::from(path));
+    }
+}
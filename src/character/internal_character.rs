@@ -0,0 +1,112 @@
+use std::str::FromStr;
+
+use crate::artifact::game_data::game_data;
+use crate::artifact::internal_artifact::CharacterKey;
+use crate::artifact::lang::Language;
+
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum Talent {
+    Auto,
+    Skill,
+    Burst,
+}
+
+#[derive(Debug, Hash, Clone, PartialEq, Eq)]
+pub struct InternalCharacter {
+    pub key: CharacterKey,
+    pub level: u32,
+    // 0 to 6
+    pub ascension: u32,
+    // 0 to 6
+    pub constellation: u32,
+    // talent levels without the bonus of the 3rd and 5th constellation
+    pub talent_auto: u32,
+    pub talent_skill: u32,
+    pub talent_burst: u32,
+}
+
+impl FromStr for Talent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Talent, String> {
+        match s {
+            "auto" => Ok(Talent::Auto),
+            "skill" => Ok(Talent::Skill),
+            "burst" => Ok(Talent::Burst),
+            _ => Err(format!("unknown talent {}", s)),
+        }
+    }
+}
+
+impl InternalCharacter {
+    // Chinese name, e.g. "钟离", for logs and reports
+    pub fn display_name(&self) -> String {
+        let name = game_data()
+            .character(&self.key)
+            .and_then(|c| c.names(Language::ZhCn).first().cloned());
+        match name {
+            Some(v) => v,
+            None => String::from(self.key.0),
+        }
+    }
+}
+
+// e.g. "Lv.9", "Lv. 12" with a constellation bonus
+pub fn parse_talent_level(s: &str) -> Option<u32> {
+    let digits: String = s.chars().filter(|c| c.is_ascii_digit()).collect();
+    match digits.parse::<u32>() {
+        Ok(v) if (1..=15).contains(&v) => Some(v),
+        _ => None,
+    }
+}
+
+// The talent screen shows auto attack, skill and burst levels with the +3 of the 3rd and 5th
+// constellation included, one raises the skill and the other the burst. Which one comes
+// first is in the game data, as are bonuses some characters always have.
+pub fn base_talent_levels(
+    key: &CharacterKey,
+    shown: [u32; 3],
+    constellation: u32,
+) -> Result<[u32; 3], String> {
+    let mut levels = shown;
+    if let Some(c) = game_data().character(key) {
+        for (level, bonus) in levels.iter_mut().zip(c.talent_bonus.iter()) {
+            *level = level.saturating_sub(*bonus);
+        }
+    }
+    if constellation >= 3 {
+        let first = match game_data().character(key).and_then(|c| c.c3_talent) {
+            Some(v) => v,
+            None => {
+                return Err(format!(
+                    "{}: unknown talent of the 3rd constellation",
+                    key.0
+                ))
+            }
+        };
+        let second = if first == Talent::Skill {
+            Talent::Burst
+        } else {
+            Talent::Skill
+        };
+        let mut boosted = vec![first];
+        if constellation >= 5 {
+            boosted.push(second);
+        }
+        for talent in boosted {
+            let i = talent as usize;
+            if levels[i] <= 3 {
+                return Err(format!("{}: talent level {} too low", key.0, levels[i]));
+            }
+            levels[i] -= 3;
+        }
+    }
+    if levels.iter().any(|&l| !(1..=10).contains(&l)) {
+        return Err(format!(
+            "{}: talent levels {:?} out of range",
+            key.0, levels
+        ));
+    }
+
+    Ok(levels)
+}
@@ -0,0 +1 @@
+pub mod internal_character;
\ No newline at end of file
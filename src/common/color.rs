@@ -1,5 +1,11 @@
 #[derive(Debug)]
-pub struct Color (pub u8, pub u8, pub u8);
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Color {
     pub fn is_same(&self, other: &Color) -> bool {
@@ -11,8 +17,7 @@ impl Color {
     pub fn dis_2(&self, other: &Color) -> u32 {
         let dis = (self.0 as i32 - other.0 as i32) * (self.0 as i32 - other.0 as i32)
             + (self.1 as i32 - other.1 as i32) * (self.1 as i32 - other.1 as i32)
-            + (self.2 as i32 - other.2 as i32) * (self.2 as i32 - other.2 as i32)
-            ;
+            + (self.2 as i32 - other.2 as i32) * (self.2 as i32 - other.2 as i32);
         dis as u32
     }
 
@@ -23,4 +28,4 @@ impl Color {
     pub fn from(r: u8, g: u8, b: u8) -> Color {
         Color(r, g, b)
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,17 @@
+use crate::inference::pre_process::{raw_to_img, uint8_raw_to_img};
+use image::GrayImage;
+
+#[cfg(windows)]
 use crate::capture;
-use crate::inference::pre_process::{pre_process, raw_to_img, to_gray, uint8_raw_to_img};
+#[cfg(windows)]
+use crate::inference::pre_process::{pre_process, to_gray};
+#[cfg(windows)]
 use crate::info::info::ScanInfo;
-use image::{GrayImage, RgbImage};
+#[cfg(windows)]
+use image::RgbImage;
+#[cfg(windows)]
 use log::info;
+#[cfg(windows)]
 use std::time::SystemTime;
 
 pub mod buffer;
@@ -34,6 +43,7 @@ pub struct PixelRectBound {
     pub bottom: i32,
 }
 
+#[cfg(windows)]
 impl PixelRectBound {
     pub fn capture_absolute(&self) -> Result<RawImage, String> {
         let w = self.right - self.left;
@@ -54,8 +64,8 @@ impl PixelRectBound {
         let w = self.right - self.left;
         let h = self.bottom - self.top;
         let rect = PixelRect {
-            left: self.left + info.left as i32,
-            top: self.top + info.top as i32,
+            left: self.left + info.left,
+            top: self.top + info.top,
             width: w,
             height: h,
         };
@@ -72,8 +82,8 @@ impl PixelRectBound {
         let w = self.right - self.left;
         let h = self.bottom - self.top;
         let rect = PixelRect {
-            left: self.left + info.left as i32,
-            top: self.top + info.top as i32,
+            left: self.left + info.left,
+            top: self.top + info.top,
             width: w,
             height: h,
         };
@@ -96,11 +106,11 @@ pub struct RawCaptureImage {
 
 impl RawImage {
     pub fn to_gray_image(&self) -> GrayImage {
-        raw_to_img(&self)
+        raw_to_img(self)
     }
 
     pub fn grayscale_to_gray_image(&self) -> GrayImage {
-        uint8_raw_to_img(&self)
+        uint8_raw_to_img(self)
     }
 }
 
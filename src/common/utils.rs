@@ -1,31 +1,45 @@
-use std::ffi::OsStr;
 use std::fs;
 use std::io::stdin;
-use std::iter::once;
-use std::os::windows::ffi::OsStrExt;
 use std::process;
-use std::ptr::null_mut;
 use std::{thread, time};
 
 use log::error;
+
+#[cfg(windows)]
+use std::ffi::OsStr;
+#[cfg(windows)]
+use std::iter::once;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(windows)]
+use std::ptr::null_mut;
+
+#[cfg(windows)]
 use winapi::shared::windef::{HWND, POINT as WinPoint, RECT as WinRect};
+#[cfg(windows)]
 use winapi::um::winuser::{
     ClientToScreen, FindWindowW, GetAsyncKeyState, GetClientRect, VK_RBUTTON,
 };
 
+#[cfg(windows)]
 use crate::common::PixelRect;
+#[cfg(windows)]
 use winapi::shared::minwindef::BOOL;
+#[cfg(windows)]
 use winapi::um::securitybaseapi::{AllocateAndInitializeSid, CheckTokenMembership, FreeSid};
+#[cfg(windows)]
 use winapi::um::winnt::{
     DOMAIN_ALIAS_RID_ADMINS, PSID, SECURITY_BUILTIN_DOMAIN_RID, SECURITY_NT_AUTHORITY,
     SID_IDENTIFIER_AUTHORITY,
 };
 
+#[cfg(windows)]
 pub fn encode_wide(s: String) -> Vec<u16> {
     let wide: Vec<u16> = OsStr::new(&s).encode_wide().chain(once(0)).collect();
     wide
 }
 
+#[cfg(windows)]
 pub fn find_window(title: String) -> Result<HWND, String> {
     let wide = encode_wide(title);
     let result: HWND = unsafe { FindWindowW(null_mut(), wide.as_ptr()) };
@@ -36,6 +50,7 @@ pub fn find_window(title: String) -> Result<HWND, String> {
     }
 }
 
+#[cfg(windows)]
 unsafe fn get_client_rect_unsafe(hwnd: HWND) -> Result<PixelRect, String> {
     let mut rect: WinRect = WinRect {
         left: 0,
@@ -60,6 +75,7 @@ unsafe fn get_client_rect_unsafe(hwnd: HWND) -> Result<PixelRect, String> {
     })
 }
 
+#[cfg(windows)]
 pub fn get_client_rect(hwnd: HWND) -> Result<PixelRect, String> {
     unsafe { get_client_rect_unsafe(hwnd) }
 }
@@ -81,6 +97,7 @@ pub fn error_and_quit(msg: &str) -> ! {
     process::exit(0);
 }
 
+#[cfg(windows)]
 unsafe fn is_admin_unsafe() -> bool {
     let mut authority: SID_IDENTIFIER_AUTHORITY = SID_IDENTIFIER_AUTHORITY {
         Value: SECURITY_NT_AUTHORITY,
@@ -110,10 +127,12 @@ unsafe fn is_admin_unsafe() -> bool {
     b != 0
 }
 
+#[cfg(windows)]
 pub fn is_admin() -> bool {
     unsafe { is_admin_unsafe() }
 }
 
+#[cfg(windows)]
 pub fn is_rmb_down() -> bool {
     unsafe {
         let state = GetAsyncKeyState(VK_RBUTTON);
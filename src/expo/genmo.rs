@@ -1,3 +1,4 @@
+use crate::artifact::game_data::game_data;
 use crate::artifact::internal_artifact::{
     ArtifactSetKey, ArtifactSlotKey, ArtifactStat, ArtifactStatKey, InternalArtifact,
 };
@@ -26,7 +27,7 @@ impl<'a> Serialize for GenmoArtifact<'a> {
 
         let artifact = &self.artifact;
         let mut root = serializer.serialize_map(Some(13))?;
-        root.serialize_entry("asKey", artifact.set_key.to_genmo())?;
+        root.serialize_entry("asKey", artifact.set_key.to_genmo().unwrap_or(""))?;
         root.serialize_entry("rarity", &artifact.rarity)?;
         root.serialize_entry("slot", artifact.slot_key.to_genmo())?;
         root.serialize_entry("level", &artifact.level)?;
@@ -81,48 +82,9 @@ impl ArtifactSlotKey {
 }
 
 impl ArtifactSetKey {
-    pub fn to_genmo(&self) -> &'static str {
-        match self {
-            ArtifactSetKey::ArchaicPetra => "archaic_petra",
-            ArtifactSetKey::HeartOfDepth => "heart_of_depth",
-            ArtifactSetKey::BlizzardStrayer => "blizzard_walker",
-            ArtifactSetKey::RetracingBolide => "retracing_bolide",
-            ArtifactSetKey::NoblesseOblige => "noblesse_oblige",
-            ArtifactSetKey::GladiatorsFinale => "gladiators_finale",
-            ArtifactSetKey::MaidenBeloved => "maiden_beloved",
-            ArtifactSetKey::ViridescentVenerer => "viridescent_venerer",
-            ArtifactSetKey::Lavawalker => "lavawalker",
-            ArtifactSetKey::CrimsonWitchOfFlames => "crimson_witch_of_flames",
-            ArtifactSetKey::Thundersoother => "thundersoother",
-            ArtifactSetKey::ThunderingFury => "thundering_fury",
-            ArtifactSetKey::BloodstainedChivalry => "bloodstained_chivalry",
-            ArtifactSetKey::WanderersTroupe => "wanderers_troupe",
-            ArtifactSetKey::Scholar => "scholar",
-            ArtifactSetKey::Gambler => "gambler",
-            ArtifactSetKey::TinyMiracle => "tiny_miracle",
-            ArtifactSetKey::MartialArtist => "martial_artist",
-            ArtifactSetKey::BraveHeart => "brave_heart",
-            ArtifactSetKey::ResolutionOfSojourner => "resolution_of_sojourner",
-            ArtifactSetKey::DefenderWill => "defenders_will",
-            ArtifactSetKey::Berserker => "berserker",
-            ArtifactSetKey::Instructor => "instructor",
-            ArtifactSetKey::Exile => "the_exile",
-            ArtifactSetKey::PrayersForWisdom => "prayers_of_wisdom",
-            ArtifactSetKey::PrayersToSpringtime => "prayers_of_springtime",
-            ArtifactSetKey::PrayersForIllumination => "prayers_of_illumination",
-            ArtifactSetKey::PrayersForDestiny => "prayers_of_destiny",
-            ArtifactSetKey::PaleFlame => "pale_flame",
-            ArtifactSetKey::TenacityOfTheMillelith => "tenacity_of_the_millelith",
-            ArtifactSetKey::EmblemOfSeveredFate => "seal_of_insulation",
-            ArtifactSetKey::ShimenawasReminiscence => "reminiscence_of_shime",
-            ArtifactSetKey::HuskOfOpulentDreams => "husk_of_opulent_dreams",
-            ArtifactSetKey::OceanHuedClam => "divine_chorus",
-
-            // Not supported by Mingyulab
-            ArtifactSetKey::Adventurer => unreachable!(),
-            ArtifactSetKey::LuckyDog => unreachable!(),
-            ArtifactSetKey::TravelingDoctor => unreachable!(),
-        }
+    // None for sets not supported by Mingyulab
+    pub fn to_genmo(&self) -> Option<&'static str> {
+        game_data().set(self).and_then(|s| s.export_key("genmo"))
     }
 }
 
@@ -131,14 +93,10 @@ pub struct GenmoFormat<'a> {
 }
 
 impl<'a> GenmoFormat<'a> {
-    pub fn new(results: &'a Vec<InternalArtifact>) -> GenmoFormat {
+    pub fn new(results: &'a [InternalArtifact]) -> GenmoFormat<'a> {
         let artifacts: Vec<GenmoArtifact<'a>> = results
-            .into_iter()
-            .filter(|artifact| {
-                artifact.set_key != ArtifactSetKey::Adventurer
-                    && artifact.set_key != ArtifactSetKey::LuckyDog
-                    && artifact.set_key != ArtifactSetKey::TravelingDoctor
-            })
+            .iter()
+            .filter(|artifact| artifact.set_key.to_genmo().is_some())
             .map(|artifact| GenmoArtifact { artifact })
             .collect();
         GenmoFormat { artifacts }
@@ -150,9 +108,8 @@ impl<'a> GenmoFormat<'a> {
             Ok(file) => file,
         };
         let s = serde_json::to_string(&self.artifacts).unwrap();
-        match file.write_all(s.as_bytes()) {
-            Err(why) => panic!("couldn't write to {}: {}", path, why),
-            _ => {}
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
         }
     }
 }
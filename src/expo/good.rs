@@ -3,10 +3,17 @@ use std::fs::File;
 use std::io::prelude::*;
 
 use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
 
+use crate::artifact::analysis::{ArtifactAnalysis, SubstatAnalysis};
+use crate::artifact::game_data::game_data;
 use crate::artifact::internal_artifact::{
     ArtifactSetKey, ArtifactSlotKey, ArtifactStat, ArtifactStatKey, CharacterKey, InternalArtifact,
 };
+use crate::artifact::main_stat::main_stat_value;
+use crate::artifact::score::{ArtifactScore, ScoreProfile};
+use crate::character::internal_character::InternalCharacter;
+use crate::weapon::internal_weapon::{InternalWeapon, WeaponKey};
 
 impl ArtifactStatKey {
     pub fn to_good(&self) -> String {
@@ -32,51 +39,49 @@ impl ArtifactStatKey {
         };
         String::from(temp)
     }
+
+    pub fn from_good(s: &str) -> Option<ArtifactStatKey> {
+        let key = match s {
+            "heal_" => ArtifactStatKey::HealingBonus,
+            "critDMG_" => ArtifactStatKey::CriticalDamage,
+            "critRate_" => ArtifactStatKey::Critical,
+            "atk" => ArtifactStatKey::Atk,
+            "atk_" => ArtifactStatKey::AtkPercentage,
+            "eleMas" => ArtifactStatKey::ElementalMastery,
+            "enerRech_" => ArtifactStatKey::Recharge,
+            "hp_" => ArtifactStatKey::HpPercentage,
+            "hp" => ArtifactStatKey::Hp,
+            "def_" => ArtifactStatKey::DefPercentage,
+            "def" => ArtifactStatKey::Def,
+            "electro_dmg_" => ArtifactStatKey::ElectroBonus,
+            "pyro_dmg_" => ArtifactStatKey::PyroBonus,
+            "hydro_dmg_" => ArtifactStatKey::HydroBonus,
+            "cryo_dmg_" => ArtifactStatKey::CryoBonus,
+            "anemo_dmg_" => ArtifactStatKey::AnemoBonus,
+            "geo_dmg_" => ArtifactStatKey::GeoBonus,
+            "physical_dmg_" => ArtifactStatKey::PhysicalBonus,
+            _ => return None,
+        };
+        Some(key)
+    }
 }
 
 impl ArtifactSetKey {
     pub fn to_good(&self) -> String {
-        let temp = match self {
-            ArtifactSetKey::ArchaicPetra => "ArchaicPetra",
-            ArtifactSetKey::HeartOfDepth => "HeartOfDepth",
-            ArtifactSetKey::BlizzardStrayer => "BlizzardStrayer",
-            ArtifactSetKey::RetracingBolide => "RetracingBolide",
-            ArtifactSetKey::NoblesseOblige => "NoblesseOblige",
-            ArtifactSetKey::GladiatorsFinale => "GladiatorsFinale",
-            ArtifactSetKey::MaidenBeloved => "MaidenBeloved",
-            ArtifactSetKey::ViridescentVenerer => "ViridescentVenerer",
-            ArtifactSetKey::Lavawalker => "Lavawalker",
-            ArtifactSetKey::CrimsonWitchOfFlames => "CrimsonWitchOfFlames",
-            ArtifactSetKey::Thundersoother => "Thundersoother",
-            ArtifactSetKey::ThunderingFury => "ThunderingFury",
-            ArtifactSetKey::BloodstainedChivalry => "BloodstainedChivalry",
-            ArtifactSetKey::WanderersTroupe => "WanderersTroupe",
-            ArtifactSetKey::Scholar => "Scholar",
-            ArtifactSetKey::Gambler => "Gambler",
-            ArtifactSetKey::TinyMiracle => "TinyMiracle",
-            ArtifactSetKey::MartialArtist => "MartialArtist",
-            ArtifactSetKey::BraveHeart => "BraveHeart",
-            ArtifactSetKey::ResolutionOfSojourner => "ResolutionOfSojourner",
-            ArtifactSetKey::DefenderWill => "DefenderWill",
-            ArtifactSetKey::Berserker => "Berserker",
-            ArtifactSetKey::Instructor => "Instructor",
-            ArtifactSetKey::Exile => "Exile",
-            ArtifactSetKey::Adventurer => "Adventurer",
-            ArtifactSetKey::LuckyDog => "LuckyDog",
-            ArtifactSetKey::TravelingDoctor => "TravelingDoctor",
-            ArtifactSetKey::PrayersForWisdom => "PrayersForWisdom",
-            ArtifactSetKey::PrayersToSpringtime => "PrayersToSpringtime",
-            ArtifactSetKey::PrayersForIllumination => "PrayersForIllumination",
-            ArtifactSetKey::PrayersForDestiny => "PrayersForDestiny",
-            ArtifactSetKey::PaleFlame => "PaleFlame",
-            ArtifactSetKey::TenacityOfTheMillelith => "TenacityOfTheMillelith",
-            ArtifactSetKey::EmblemOfSeveredFate => "EmblemOfSeveredFate",
-            ArtifactSetKey::ShimenawasReminiscence => "ShimenawasReminiscence",
-            ArtifactSetKey::HuskOfOpulentDreams => "HuskOfOpulentDreams",
-            ArtifactSetKey::OceanHuedClam => "OceanHuedClam",
-        };
+        let temp = game_data()
+            .set(self)
+            .and_then(|s| s.export_key("good"))
+            .unwrap_or(self.0);
         String::from(temp)
     }
+
+    pub fn from_good(s: &str) -> Option<ArtifactSetKey> {
+        game_data()
+            .sets
+            .iter()
+            .find(|set| set.export_key("good").unwrap_or(set.key.0) == s)
+            .map(|set| set.key)
+    }
 }
 
 impl ArtifactSlotKey {
@@ -90,58 +95,54 @@ impl ArtifactSlotKey {
         };
         String::from(temp)
     }
+
+    pub fn from_good(s: &str) -> Option<ArtifactSlotKey> {
+        let key = match s {
+            "flower" => ArtifactSlotKey::Flower,
+            "plume" => ArtifactSlotKey::Plume,
+            "sands" => ArtifactSlotKey::Sands,
+            "goblet" => ArtifactSlotKey::Goblet,
+            "circlet" => ArtifactSlotKey::Circlet,
+            _ => return None,
+        };
+        Some(key)
+    }
 }
 
 impl CharacterKey {
     pub fn to_good(&self) -> String {
-        let temp = match self {
-            CharacterKey::Albedo => "Albedo",
-            CharacterKey::Aloy => "Aloy",
-            CharacterKey::Amber => "Amber",
-            CharacterKey::AratakiItto => "AratakiItto",
-            CharacterKey::Barbara => "Barbara",
-            CharacterKey::Beidou => "Beidou",
-            CharacterKey::Bennett => "Bennett",
-            CharacterKey::Chongyun => "Chongyun",
-            CharacterKey::Diluc => "Diluc",
-            CharacterKey::Diona => "Diona",
-            CharacterKey::Eula => "Eula",
-            CharacterKey::Fischl => "Fischl",
-            CharacterKey::Ganyu => "Ganyu",
-            CharacterKey::Gorou => "Gorou",
-            CharacterKey::HuTao => "HuTao",
-            CharacterKey::Jean => "Jean",
-            CharacterKey::KaedeharaKazuha => "KaedeharaKazuha",
-            CharacterKey::Kaeya => "Kaeya",
-            CharacterKey::KamisatoAyaka => "KamisatoAyaka",
-            CharacterKey::Keqing => "Keqing",
-            CharacterKey::Klee => "Klee",
-            CharacterKey::KujouSara => "KujouSara",
-            CharacterKey::Lisa => "Lisa",
-            CharacterKey::Mona => "Mona",
-            CharacterKey::Ningguang => "Ningguang",
-            CharacterKey::Noelle => "Noelle",
-            CharacterKey::Qiqi => "Qiqi",
-            CharacterKey::RaidenShogun => "RaidenShogun",
-            CharacterKey::Razor => "Razor",
-            CharacterKey::Rosaria => "Rosaria",
-            CharacterKey::SangonomiyaKokomi => "SangonomiyaKokomi",
-            CharacterKey::Sayu => "Sayu",
-            CharacterKey::Sucrose => "Sucrose",
-            CharacterKey::Tartaglia => "Tartaglia",
-            CharacterKey::Thoma => "Thoma",
-            CharacterKey::Traveler => "Traveler",
-            CharacterKey::Venti => "Venti",
-            CharacterKey::Xiangling => "Xiangling",
-            CharacterKey::Xiao => "Xiao",
-            CharacterKey::Xingqiu => "Xingqiu",
-            CharacterKey::Xinyan => "Xinyan",
-            CharacterKey::Yanfei => "Yanfei",
-            CharacterKey::Yoimiya => "Yoimiya",
-            CharacterKey::Zhongli => "Zhongli",
-        };
+        let temp = game_data()
+            .character(self)
+            .and_then(|c| c.export_key("good"))
+            .unwrap_or(self.0);
+        String::from(temp)
+    }
+
+    pub fn from_good(s: &str) -> Option<CharacterKey> {
+        game_data()
+            .characters
+            .iter()
+            .find(|c| c.export_key("good").unwrap_or(c.key.0) == s)
+            .map(|c| c.key)
+    }
+}
+
+impl WeaponKey {
+    pub fn to_good(&self) -> String {
+        let temp = game_data()
+            .weapon(self)
+            .and_then(|w| w.export_key("good"))
+            .unwrap_or(self.0);
         String::from(temp)
     }
+
+    pub fn from_good(s: &str) -> Option<WeaponKey> {
+        game_data()
+            .weapons
+            .iter()
+            .find(|w| w.export_key("good").unwrap_or(w.key.0) == s)
+            .map(|w| w.key)
+    }
 }
 
 struct GoodArtifactStat<'a> {
@@ -160,8 +161,97 @@ impl<'a> Serialize for GoodArtifactStat<'a> {
     }
 }
 
+struct GoodSubstatRolls<'a> {
+    substat: &'a SubstatAnalysis,
+}
+
+impl<'a> Serialize for GoodSubstatRolls<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(5))?;
+        root.serialize_entry("key", &self.substat.key.to_good())?;
+        root.serialize_entry("value", &self.substat.value)?;
+        root.serialize_entry("rolls", &self.substat.rolls)?;
+        root.serialize_entry("possibleRolls", &self.substat.possible_rolls)?;
+        root.serialize_entry("tiers", &self.substat.tiers)?;
+        root.end()
+    }
+}
+
+// Not part of GOOD, importers ignore unknown fields
+struct GoodRollAnalysis<'a> {
+    analysis: &'a ArtifactAnalysis,
+}
+
+impl<'a> Serialize for GoodRollAnalysis<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let substats: Vec<GoodSubstatRolls> = self
+            .analysis
+            .substats
+            .iter()
+            .map(|substat| GoodSubstatRolls { substat })
+            .collect();
+
+        let mut root = serializer.serialize_map(Some(5))?;
+        root.serialize_entry("initialSubstats", &self.analysis.initial_substats)?;
+        root.serialize_entry("upgradesDone", &self.analysis.upgrades_done)?;
+        root.serialize_entry("upgradesRemaining", &self.analysis.upgrades_remaining)?;
+        root.serialize_entry("consistent", &self.analysis.consistent)?;
+        root.serialize_entry("substats", &substats)?;
+        root.end()
+    }
+}
+
+// Score per profile name, in profile order
+struct GoodProfileScores<'a> {
+    profiles: &'a [(String, f64)],
+}
+
+impl<'a> Serialize for GoodProfileScores<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(self.profiles.len()))?;
+        for (name, score) in self.profiles.iter() {
+            root.serialize_entry(name, score)?;
+        }
+        root.end()
+    }
+}
+
+struct GoodArtifactScore<'a> {
+    score: &'a ArtifactScore,
+}
+
+impl<'a> Serialize for GoodArtifactScore<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let profiles = GoodProfileScores {
+            profiles: &self.score.profiles,
+        };
+
+        let mut root = serializer.serialize_map(Some(5))?;
+        root.serialize_entry("critValue", &self.score.crit_value)?;
+        root.serialize_entry("rollValue", &self.score.roll_value)?;
+        root.serialize_entry("profiles", &profiles)?;
+        root.serialize_entry("best", &self.score.best().map(|x| &x.0))?;
+        root.serialize_entry("equippedProfile", &self.score.equipped_profile)?;
+        root.end()
+    }
+}
+
 struct GoodArtifact<'a> {
     artifact: &'a InternalArtifact,
+    rolls: Option<ArtifactAnalysis>,
+    score: Option<ArtifactScore>,
 }
 
 impl<'a> Serialize for GoodArtifact<'a> {
@@ -169,7 +259,8 @@ impl<'a> Serialize for GoodArtifact<'a> {
     where
         S: Serializer,
     {
-        let mut root = serializer.serialize_map(Some(8))?;
+        let len = 8 + self.rolls.is_some() as usize + self.score.is_some() as usize;
+        let mut root = serializer.serialize_map(Some(len))?;
 
         root.serialize_entry("setKey", &self.artifact.set_key.to_good())?;
         root.serialize_entry("slotKey", &self.artifact.slot_key.to_good())?;
@@ -196,15 +287,109 @@ impl<'a> Serialize for GoodArtifact<'a> {
             substats.push(GoodArtifactStat { stat: s });
         }
         root.serialize_entry("substats", &substats)?;
+        if let Some(ref analysis) = self.rolls {
+            root.serialize_entry("yasRolls", &GoodRollAnalysis { analysis })?;
+        }
+        if let Some(ref score) = self.score {
+            root.serialize_entry("yasScore", &GoodArtifactScore { score })?;
+        }
+        root.end()
+    }
+}
+
+struct GoodWeapon<'a> {
+    weapon: &'a InternalWeapon,
+}
+
+impl<'a> Serialize for GoodWeapon<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(6))?;
+        root.serialize_entry("key", &self.weapon.key.to_good())?;
+        root.serialize_entry("level", &self.weapon.level)?;
+        root.serialize_entry("ascension", &self.weapon.ascension)?;
+        root.serialize_entry("refinement", &self.weapon.refinement)?;
+        let location = match &self.weapon.location {
+            Some(v) => v.to_good(),
+            None => String::from(""),
+        };
+        root.serialize_entry("location", &location)?;
+        root.serialize_entry("lock", &self.weapon.lock)?;
+        root.end()
+    }
+}
+
+struct GoodTalent<'a> {
+    character: &'a InternalCharacter,
+}
+
+impl<'a> Serialize for GoodTalent<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(3))?;
+        root.serialize_entry("auto", &self.character.talent_auto)?;
+        root.serialize_entry("skill", &self.character.talent_skill)?;
+        root.serialize_entry("burst", &self.character.talent_burst)?;
+        root.end()
+    }
+}
+
+struct GoodCharacter<'a> {
+    character: &'a InternalCharacter,
+}
+
+impl<'a> Serialize for GoodCharacter<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(5))?;
+        root.serialize_entry("key", &self.character.key.to_good())?;
+        root.serialize_entry("level", &self.character.level)?;
+        root.serialize_entry("constellation", &self.character.constellation)?;
+        root.serialize_entry("ascension", &self.character.ascension)?;
+        root.serialize_entry(
+            "talent",
+            &GoodTalent {
+                character: self.character,
+            },
+        )?;
+        root.end()
+    }
+}
+
+// GOOD key to count, e.g. {"HeroesWit": 120}
+struct GoodMaterials<'a> {
+    materials: &'a [(String, u32)],
+}
+
+impl<'a> Serialize for GoodMaterials<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(self.materials.len()))?;
+        for (key, count) in self.materials.iter() {
+            root.serialize_entry(key, count)?;
+        }
         root.end()
     }
 }
 
+// Sections that were not scanned are left out rather than written empty, importers would
+// take an empty list for an empty inventory
 pub struct GoodFormat<'a> {
     format: String,
     version: u32,
     source: String,
-    artifacts: Vec<GoodArtifact<'a>>,
+    artifacts: Option<Vec<GoodArtifact<'a>>>,
+    weapons: Option<Vec<GoodWeapon<'a>>>,
+    characters: Option<Vec<GoodCharacter<'a>>>,
+    materials: Option<GoodMaterials<'a>>,
 }
 
 impl<'a> Serialize for GoodFormat<'a> {
@@ -212,30 +397,107 @@ impl<'a> Serialize for GoodFormat<'a> {
     where
         S: Serializer,
     {
-        let mut root = serializer.serialize_map(Some(4))?;
+        let len = 3
+            + self.artifacts.is_some() as usize
+            + self.weapons.is_some() as usize
+            + self.characters.is_some() as usize
+            + self.materials.is_some() as usize;
+        let mut root = serializer.serialize_map(Some(len))?;
         root.serialize_entry("format", &self.format)?;
         root.serialize_entry("version", &self.version)?;
         root.serialize_entry("source", &self.source)?;
-        root.serialize_entry("artifacts", &self.artifacts)?;
+        if let Some(ref artifacts) = self.artifacts {
+            root.serialize_entry("artifacts", artifacts)?;
+        }
+        if let Some(ref weapons) = self.weapons {
+            root.serialize_entry("weapons", weapons)?;
+        }
+        if let Some(ref characters) = self.characters {
+            root.serialize_entry("characters", characters)?;
+        }
+        if let Some(ref materials) = self.materials {
+            root.serialize_entry("materials", materials)?;
+        }
         root.end()
     }
 }
 
 impl<'a> GoodFormat<'a> {
-    pub fn new(results: &'a Vec<InternalArtifact>) -> GoodFormat {
+    pub fn new(results: &'a [InternalArtifact]) -> GoodFormat<'a> {
+        GoodFormat::new_with_extras(results, false, None)
+    }
+
+    // Fields outside GOOD: the roll analysis under "yasRolls" and the scores under
+    // "yasScore", each only when asked for
+    pub fn new_with_extras(
+        results: &'a [InternalArtifact],
+        roll_analysis: bool,
+        score_profiles: Option<&[ScoreProfile]>,
+    ) -> GoodFormat<'a> {
         let artifacts: Vec<GoodArtifact<'a>> = results
-            .into_iter()
-            .map(|artifact| GoodArtifact { artifact })
+            .iter()
+            .map(|artifact| GoodArtifact {
+                artifact,
+                rolls: if roll_analysis {
+                    Some(ArtifactAnalysis::from_artifact(artifact))
+                } else {
+                    None
+                },
+                score: score_profiles.map(|p| ArtifactScore::new(artifact, p)),
+            })
             .collect();
 
+        GoodFormat {
+            artifacts: Some(artifacts),
+            ..GoodFormat::empty()
+        }
+    }
+
+    fn empty() -> GoodFormat<'a> {
         GoodFormat {
             format: String::from("GOOD"),
             version: 1,
             source: String::from("yas-lock"),
-            artifacts,
+            artifacts: None,
+            weapons: None,
+            characters: None,
+            materials: None,
         }
     }
 
+    // Only the weapons section, e.g. for a weapon scan
+    pub fn from_weapons(weapons: &'a [InternalWeapon]) -> GoodFormat<'a> {
+        GoodFormat::empty().with_weapons(weapons)
+    }
+
+    pub fn from_characters(characters: &'a [InternalCharacter]) -> GoodFormat<'a> {
+        GoodFormat::empty().with_characters(characters)
+    }
+
+    pub fn from_materials(materials: &'a [(String, u32)]) -> GoodFormat<'a> {
+        GoodFormat::empty().with_materials(materials)
+    }
+
+    pub fn with_weapons(mut self, weapons: &'a [InternalWeapon]) -> GoodFormat<'a> {
+        self.weapons = Some(weapons.iter().map(|weapon| GoodWeapon { weapon }).collect());
+        self
+    }
+
+    pub fn with_characters(mut self, characters: &'a [InternalCharacter]) -> GoodFormat<'a> {
+        self.characters = Some(
+            characters
+                .iter()
+                .map(|character| GoodCharacter { character })
+                .collect(),
+        );
+        self
+    }
+
+    pub fn with_materials(mut self, materials: &'a [(String, u32)]) -> GoodFormat<'a> {
+        self.materials = Some(GoodMaterials { materials });
+        self
+    }
+
     pub fn save(&self, path: String) {
         let mut file = match File::create(&path) {
             Err(why) => panic!("couldn't create {}: {}", path, why),
@@ -243,9 +505,135 @@ impl<'a> GoodFormat<'a> {
         };
         let s = serde_json::to_string(&self).unwrap();
 
-        match file.write_all(s.as_bytes()) {
-            Err(why) => panic!("couldn't write to {}: {}", path, why),
-            _ => {}
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+}
+
+fn artifact_from_good(value: &Value, what: &str) -> Result<InternalArtifact, String> {
+    let str_field = |name: &str| match value[name].as_str() {
+        Some(s) => Ok(s),
+        None => Err(format!("{}: missing {}", what, name)),
+    };
+    let u32_field = |name: &str| match value[name].as_u64() {
+        Some(v) => Ok(v as u32),
+        None => Err(format!("{}: missing {}", what, name)),
+    };
+    let stat_key = |s: &str| match ArtifactStatKey::from_good(s) {
+        Some(v) => Ok(v),
+        None => Err(format!("{}: unknown stat {}", what, s)),
+    };
+
+    let set_key = match ArtifactSetKey::from_good(str_field("setKey")?) {
+        Some(v) => v,
+        None => return Err(format!("{}: unknown set {}", what, value["setKey"])),
+    };
+    let slot_key = match ArtifactSlotKey::from_good(str_field("slotKey")?) {
+        Some(v) => v,
+        None => return Err(format!("{}: unknown slot {}", what, value["slotKey"])),
+    };
+    let rarity = u32_field("rarity")?;
+    let level = u32_field("level")?;
+    let location = match value["location"].as_str().unwrap_or("") {
+        "" => None,
+        s => match CharacterKey::from_good(s) {
+            Some(v) => Some(v),
+            None => return Err(format!("{}: unknown character {}", what, s)),
+        },
+    };
+
+    // GOOD has no main stat value, it follows from the level
+    let main_stat_key = stat_key(str_field("mainStatKey")?)?;
+    let main_stat = ArtifactStat {
+        value: main_stat_value(&main_stat_key, rarity, level).unwrap_or(0.0),
+        key: main_stat_key,
+    };
+
+    let mut subs: Vec<Option<ArtifactStat>> = vec![None, None, None, None];
+    for (i, s) in value["substats"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .take(4)
+        .enumerate()
+    {
+        let key = stat_key(s["key"].as_str().unwrap_or(""))?;
+        let value = match s["value"].as_f64() {
+            Some(v) => v,
+            None => return Err(format!("{}: substat {} without value", what, i)),
+        };
+        subs[i] = Some(ArtifactStat { key, value });
+    }
+    let mut subs = subs.into_iter();
+
+    Ok(InternalArtifact {
+        set_key,
+        slot_key,
+        rarity,
+        level,
+        location,
+        lock: value["lock"].as_bool().unwrap_or(false),
+        main_stat,
+        sub_stat_1: subs.next().unwrap(),
+        sub_stat_2: subs.next().unwrap(),
+        sub_stat_3: subs.next().unwrap(),
+        sub_stat_4: subs.next().unwrap(),
+    })
+}
+
+// Artifacts of a GOOD file in file order, e.g. a previous good.json
+pub fn read_good_artifacts(s: &str) -> Result<Vec<InternalArtifact>, String> {
+    let json: Value = match serde_json::from_str(s) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("cannot parse GOOD file: {}", e)),
+    };
+    if json["format"].as_str() != Some("GOOD") {
+        return Err(String::from("not a GOOD file"));
+    }
+
+    let mut artifacts: Vec<InternalArtifact> = Vec::new();
+    for (i, item) in json["artifacts"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .enumerate()
+    {
+        artifacts.push(artifact_from_good(item, &format!("artifacts[{}]", i))?);
+    }
+
+    Ok(artifacts)
+}
+
+const GOOD_SECTIONS: [&str; 4] = ["artifacts", "weapons", "characters", "materials"];
+
+// One GOOD export with the sections of several, e.g. good.json from an artifact scan with
+// good_weapons.json and good_characters.json from the other modes, each given with its name.
+// A section found in more than one export is taken from the last, the warnings say which.
+pub fn merge_good(exports: &[(String, Value)]) -> Result<(Value, Vec<String>), String> {
+    let mut merged = serde_json::Map::new();
+    merged.insert(String::from("format"), Value::from("GOOD"));
+    merged.insert(String::from("version"), Value::from(1));
+    merged.insert(String::from("source"), Value::from("yas-lock"));
+
+    let mut warnings: Vec<String> = Vec::new();
+    let mut taken_from: Vec<(&str, &str)> = Vec::new();
+    for (name, json) in exports.iter() {
+        if json["format"].as_str() != Some("GOOD") {
+            return Err(format!("{}: not a GOOD export", name));
+        }
+        for &section in GOOD_SECTIONS.iter() {
+            if json[section].is_null() {
+                continue;
+            }
+            if let Some(&(_, earlier)) = taken_from.iter().find(|(s, _)| *s == section) {
+                warnings.push(format!("{} of {} replaced by {}", section, earlier, name));
+            }
+            taken_from.retain(|(s, _)| *s != section);
+            taken_from.push((section, name));
+            merged.insert(String::from(section), json[section].clone());
         }
     }
+
+    Ok((Value::Object(merged), warnings))
 }
@@ -1,3 +1,5 @@
 pub mod genmo;
 pub mod good;
 pub mod mona_uranai;
+pub mod ranking;
+pub mod upgrade;
\ No newline at end of file
@@ -3,7 +3,9 @@ use std::fs::File;
 use std::io::prelude::*;
 
 use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
 
+use crate::artifact::game_data::game_data;
 use crate::artifact::internal_artifact::{
     ArtifactSetKey, ArtifactSlotKey, ArtifactStat, ArtifactStatKey, InternalArtifact,
 };
@@ -34,51 +36,49 @@ impl ArtifactStatKey {
         };
         String::from(temp)
     }
+
+    pub fn from_mona(s: &str) -> Option<ArtifactStatKey> {
+        let key = match s {
+            "cureEffect" => ArtifactStatKey::HealingBonus,
+            "criticalDamage" => ArtifactStatKey::CriticalDamage,
+            "critical" => ArtifactStatKey::Critical,
+            "attackStatic" => ArtifactStatKey::Atk,
+            "attackPercentage" => ArtifactStatKey::AtkPercentage,
+            "elementalMastery" => ArtifactStatKey::ElementalMastery,
+            "recharge" => ArtifactStatKey::Recharge,
+            "lifePercentage" => ArtifactStatKey::HpPercentage,
+            "lifeStatic" => ArtifactStatKey::Hp,
+            "defendPercentage" => ArtifactStatKey::DefPercentage,
+            "defendStatic" => ArtifactStatKey::Def,
+            "thunderBonus" => ArtifactStatKey::ElectroBonus,
+            "fireBonus" => ArtifactStatKey::PyroBonus,
+            "waterBonus" => ArtifactStatKey::HydroBonus,
+            "iceBonus" => ArtifactStatKey::CryoBonus,
+            "windBonus" => ArtifactStatKey::AnemoBonus,
+            "rockBonus" => ArtifactStatKey::GeoBonus,
+            "physicalBonus" => ArtifactStatKey::PhysicalBonus,
+            _ => return None,
+        };
+        Some(key)
+    }
 }
 
 impl ArtifactSetKey {
     pub fn to_mona(&self) -> String {
-        let temp = match self {
-            ArtifactSetKey::ArchaicPetra => "archaicPetra",
-            ArtifactSetKey::HeartOfDepth => "heartOfDepth",
-            ArtifactSetKey::BlizzardStrayer => "blizzardStrayer",
-            ArtifactSetKey::RetracingBolide => "retracingBolide",
-            ArtifactSetKey::NoblesseOblige => "noblesseOblige",
-            ArtifactSetKey::GladiatorsFinale => "gladiatorFinale",
-            ArtifactSetKey::MaidenBeloved => "maidenBeloved",
-            ArtifactSetKey::ViridescentVenerer => "viridescentVenerer",
-            ArtifactSetKey::Lavawalker => "lavaWalker",
-            ArtifactSetKey::CrimsonWitchOfFlames => "crimsonWitch",
-            ArtifactSetKey::Thundersoother => "thunderSmoother",
-            ArtifactSetKey::ThunderingFury => "thunderingFury",
-            ArtifactSetKey::BloodstainedChivalry => "bloodstainedChivalry",
-            ArtifactSetKey::WanderersTroupe => "wandererTroupe",
-            ArtifactSetKey::Scholar => "scholar",
-            ArtifactSetKey::Gambler => "gambler",
-            ArtifactSetKey::TinyMiracle => "tinyMiracle",
-            ArtifactSetKey::MartialArtist => "martialArtist",
-            ArtifactSetKey::BraveHeart => "braveHeart",
-            ArtifactSetKey::ResolutionOfSojourner => "resolutionOfSojourner",
-            ArtifactSetKey::DefenderWill => "defenderWill",
-            ArtifactSetKey::Berserker => "berserker",
-            ArtifactSetKey::Instructor => "instructor",
-            ArtifactSetKey::Exile => "exile",
-            ArtifactSetKey::Adventurer => "adventurer",
-            ArtifactSetKey::LuckyDog => "luckyDog",
-            ArtifactSetKey::TravelingDoctor => "travelingDoctor",
-            ArtifactSetKey::PrayersForWisdom => "prayersForWisdom",
-            ArtifactSetKey::PrayersToSpringtime => "prayersToSpringtime",
-            ArtifactSetKey::PrayersForIllumination => "prayersForIllumination",
-            ArtifactSetKey::PrayersForDestiny => "prayersForDestiny",
-            ArtifactSetKey::PaleFlame => "paleFlame",
-            ArtifactSetKey::TenacityOfTheMillelith => "tenacityOfTheMillelith",
-            ArtifactSetKey::EmblemOfSeveredFate => "emblemOfSeveredFate",
-            ArtifactSetKey::ShimenawasReminiscence => "shimenawaReminiscence",
-            ArtifactSetKey::HuskOfOpulentDreams => "huskOfOpulentDreams",
-            ArtifactSetKey::OceanHuedClam => "oceanHuedClam",
-        };
+        let temp = game_data()
+            .set(self)
+            .and_then(|s| s.export_key("mona"))
+            .unwrap_or(self.0);
         String::from(temp)
     }
+
+    pub fn from_mona(s: &str) -> Option<ArtifactSetKey> {
+        game_data()
+            .sets
+            .iter()
+            .find(|set| set.export_key("mona").unwrap_or(set.key.0) == s)
+            .map(|set| set.key)
+    }
 }
 
 impl ArtifactSlotKey {
@@ -92,6 +92,18 @@ impl ArtifactSlotKey {
         };
         String::from(temp)
     }
+
+    pub fn from_mona(s: &str) -> Option<ArtifactSlotKey> {
+        let key = match s {
+            "flower" => ArtifactSlotKey::Flower,
+            "feather" => ArtifactSlotKey::Plume,
+            "sand" => ArtifactSlotKey::Sands,
+            "cup" => ArtifactSlotKey::Goblet,
+            "head" => ArtifactSlotKey::Circlet,
+            _ => return None,
+        };
+        Some(key)
+    }
 }
 
 impl Serialize for ArtifactStat {
@@ -180,7 +192,7 @@ impl<'a> Serialize for MonaFormat<'a> {
 }
 
 impl<'a> MonaFormat<'a> {
-    pub fn new(results: &Vec<InternalArtifact>) -> MonaFormat {
+    pub fn new(results: &[InternalArtifact]) -> MonaFormat<'_> {
         let mut flower: Vec<&MonaArtifact> = Vec::new();
         let mut feather: Vec<&MonaArtifact> = Vec::new();
         let mut cup: Vec<&MonaArtifact> = Vec::new();
@@ -215,9 +227,94 @@ impl<'a> MonaFormat<'a> {
         };
         let s = serde_json::to_string(&self).unwrap();
 
-        match file.write_all(s.as_bytes()) {
-            Err(why) => panic!("couldn't write to {}: {}", path, why),
-            _ => {}
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+}
+
+fn stat_from_mona(value: &Value, what: &str) -> Result<ArtifactStat, String> {
+    let name = value["name"].as_str().unwrap_or("");
+    let key = match ArtifactStatKey::from_mona(name) {
+        Some(v) => v,
+        None => return Err(format!("{}: unknown stat {}", what, name)),
+    };
+    let v = match value["value"].as_f64() {
+        Some(v) => v,
+        None => return Err(format!("{}: stat {} without value", what, name)),
+    };
+    // percentages are stored as fractions
+    let value = if key.is_percentage() {
+        (v * 1000.0).round() / 10.0
+    } else {
+        v
+    };
+
+    Ok(ArtifactStat { key, value })
+}
+
+fn artifact_from_mona(value: &Value, what: &str) -> Result<InternalArtifact, String> {
+    let set_name = value["setName"].as_str().unwrap_or("");
+    let set_key = match ArtifactSetKey::from_mona(set_name) {
+        Some(v) => v,
+        None => return Err(format!("{}: unknown set {}", what, set_name)),
+    };
+    let position = value["position"].as_str().unwrap_or("");
+    let slot_key = match ArtifactSlotKey::from_mona(position) {
+        Some(v) => v,
+        None => return Err(format!("{}: unknown position {}", what, position)),
+    };
+
+    let mut subs: Vec<Option<ArtifactStat>> = vec![None, None, None, None];
+    for (i, s) in value["normalTags"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .take(4)
+        .enumerate()
+    {
+        subs[i] = Some(stat_from_mona(s, what)?);
+    }
+    let mut subs = subs.into_iter();
+
+    Ok(InternalArtifact {
+        set_key,
+        slot_key,
+        rarity: value["star"].as_u64().unwrap_or(0) as u32,
+        level: value["level"].as_u64().unwrap_or(0) as u32,
+        // not part of the format
+        lock: false,
+        location: None,
+        main_stat: stat_from_mona(&value["mainTag"], what)?,
+        sub_stat_1: subs.next().unwrap(),
+        sub_stat_2: subs.next().unwrap(),
+        sub_stat_3: subs.next().unwrap(),
+        sub_stat_4: subs.next().unwrap(),
+    })
+}
+
+// Artifacts of a mona.json, grouped by slot in the order flower, feather, sand, cup, head.
+// Lock state and location are not stored there and come back unlocked and unequipped.
+pub fn read_mona_artifacts(s: &str) -> Result<Vec<InternalArtifact>, String> {
+    let json: Value = match serde_json::from_str(s) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("cannot parse mona file: {}", e)),
+    };
+    if !json["flower"].is_array() {
+        return Err(String::from("not a mona file"));
+    }
+
+    let mut artifacts: Vec<InternalArtifact> = Vec::new();
+    for position in ["flower", "feather", "sand", "cup", "head"].iter() {
+        for (i, item) in json[*position]
+            .as_array()
+            .unwrap_or(&Vec::new())
+            .iter()
+            .enumerate()
+        {
+            artifacts.push(artifact_from_mona(item, &format!("{}[{}]", position, i))?);
         }
     }
+
+    Ok(artifacts)
 }
@@ -0,0 +1,105 @@
+use std::fs::File;
+use std::io::prelude::*;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::artifact::internal_artifact::InternalArtifact;
+use crate::artifact::score::{crit_value, rank, roll_value, ScoreProfile};
+
+struct RankingEntry<'a> {
+    // position in the scan results, the same as in good.json
+    index: usize,
+    artifact: &'a InternalArtifact,
+    score: f64,
+}
+
+impl<'a> Serialize for RankingEntry<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let location = match &self.artifact.location {
+            Some(v) => v.to_good(),
+            None => String::from(""),
+        };
+
+        let mut root = serializer.serialize_map(Some(10))?;
+        root.serialize_entry("index", &self.index)?;
+        root.serialize_entry("setKey", &self.artifact.set_key.to_good())?;
+        root.serialize_entry("slotKey", &self.artifact.slot_key.to_good())?;
+        root.serialize_entry("rarity", &self.artifact.rarity)?;
+        root.serialize_entry("level", &self.artifact.level)?;
+        root.serialize_entry("mainStatKey", &self.artifact.main_stat.key.to_good())?;
+        root.serialize_entry("location", &location)?;
+        root.serialize_entry("score", &self.score)?;
+        root.serialize_entry("critValue", &crit_value(self.artifact))?;
+        root.serialize_entry("rollValue", &roll_value(self.artifact))?;
+        root.end()
+    }
+}
+
+struct ProfileRanking<'a> {
+    name: &'a str,
+    entries: Vec<RankingEntry<'a>>,
+}
+
+impl<'a> Serialize for ProfileRanking<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(2))?;
+        root.serialize_entry("profile", self.name)?;
+        root.serialize_entry("ranking", &self.entries)?;
+        root.end()
+    }
+}
+
+// Every artifact ranked by each score profile, best first
+pub struct RankingReport<'a> {
+    rankings: Vec<ProfileRanking<'a>>,
+}
+
+impl<'a> Serialize for RankingReport<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(1))?;
+        root.serialize_entry("profiles", &self.rankings)?;
+        root.end()
+    }
+}
+
+impl<'a> RankingReport<'a> {
+    pub fn new(results: &'a [InternalArtifact], profiles: &'a [ScoreProfile]) -> RankingReport<'a> {
+        let rankings = profiles
+            .iter()
+            .map(|profile| ProfileRanking {
+                name: &profile.name,
+                entries: rank(results, profile)
+                    .into_iter()
+                    .map(|(index, score)| RankingEntry {
+                        index,
+                        artifact: &results[index],
+                        score,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        RankingReport { rankings }
+    }
+
+    pub fn save(&self, path: String) {
+        let mut file = match File::create(&path) {
+            Err(why) => panic!("couldn't create {}: {}", path, why),
+            Ok(file) => file,
+        };
+        let s = serde_json::to_string(&self).unwrap();
+
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::fs::File;
+use std::io::prelude::*;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::artifact::internal_artifact::InternalArtifact;
+use crate::artifact::score::ScoreMetric;
+use crate::artifact::upgrade::{simulate_upgrades, SubstatOutlook, UpgradeSimulation};
+
+struct OutlookEntry<'a> {
+    outlook: &'a SubstatOutlook,
+}
+
+impl<'a> Serialize for OutlookEntry<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(4))?;
+        root.serialize_entry("key", &self.outlook.key.to_good())?;
+        root.serialize_entry("current", &self.outlook.current)?;
+        root.serialize_entry("chance", &self.outlook.chance)?;
+        root.serialize_entry("expected", &self.outlook.expected)?;
+        root.end()
+    }
+}
+
+struct UpgradeEntry<'a> {
+    // position in the scan results, the same as in good.json
+    index: usize,
+    artifact: &'a InternalArtifact,
+    simulation: UpgradeSimulation,
+    chance: Option<f64>,
+}
+
+impl<'a> Serialize for UpgradeEntry<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let sim = &self.simulation;
+        let substats: Vec<OutlookEntry> = sim
+            .substats
+            .iter()
+            .map(|outlook| OutlookEntry { outlook })
+            .collect();
+
+        let mut root = serializer.serialize_map(Some(12))?;
+        root.serialize_entry("index", &self.index)?;
+        root.serialize_entry("setKey", &self.artifact.set_key.to_good())?;
+        root.serialize_entry("slotKey", &self.artifact.slot_key.to_good())?;
+        root.serialize_entry("rarity", &self.artifact.rarity)?;
+        root.serialize_entry("level", &self.artifact.level)?;
+        root.serialize_entry("upgrades", &sim.upgrades)?;
+        root.serialize_entry("current", &sim.current)?;
+        root.serialize_entry("expected", &sim.expected())?;
+        root.serialize_entry(
+            "percentiles",
+            &[
+                sim.percentile(0.1),
+                sim.percentile(0.5),
+                sim.percentile(0.9),
+            ],
+        )?;
+        root.serialize_entry("chance", &self.chance)?;
+        root.serialize_entry("substats", &substats)?;
+        root.end()
+    }
+}
+
+// Outlook of every artifact that can still be upgraded, the most promising first: by the
+// chance of reaching `target` if given, by the expected score otherwise
+pub struct UpgradeReport<'a> {
+    entries: Vec<UpgradeEntry<'a>>,
+    target: Option<f64>,
+}
+
+impl<'a> Serialize for UpgradeReport<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(2))?;
+        root.serialize_entry("target", &self.target)?;
+        root.serialize_entry("artifacts", &self.entries)?;
+        root.end()
+    }
+}
+
+impl<'a> UpgradeReport<'a> {
+    // `select` picks the artifacts to include, e.g. a filter
+    pub fn new(
+        results: &'a [InternalArtifact],
+        metric: &ScoreMetric,
+        target: Option<f64>,
+        select: impl Fn(&InternalArtifact) -> bool,
+    ) -> UpgradeReport<'a> {
+        let mut entries: Vec<UpgradeEntry<'a>> = Vec::new();
+        for (index, artifact) in results.iter().enumerate() {
+            if !select(artifact) {
+                continue;
+            }
+            let simulation = match simulate_upgrades(artifact, metric) {
+                Some(v) if v.upgrades > 0 => v,
+                _ => continue,
+            };
+            entries.push(UpgradeEntry {
+                index,
+                artifact,
+                chance: target.map(|t| simulation.chance_at_least(t)),
+                simulation,
+            });
+        }
+
+        let key = |e: &UpgradeEntry| e.chance.unwrap_or(e.simulation.expected());
+        entries.sort_by(|a, b| {
+            key(b)
+                .partial_cmp(&key(a))
+                .unwrap()
+                .then(a.index.cmp(&b.index))
+        });
+
+        UpgradeReport { entries, target }
+    }
+
+    pub fn print(&self, top: usize) {
+        println!("可强化 {} 件", self.entries.len());
+        for e in self.entries.iter().take(top) {
+            let sim = &e.simulation;
+            let chance = match e.chance {
+                Some(c) => format!("  达标概率 {:.1}%", c * 100.0),
+                None => String::new(),
+            };
+            println!(
+                "[{}] {} {}★+{}  当前 {:.1}  期望 {:.1}  10%/50%/90%: {:.1}/{:.1}/{:.1}{}",
+                e.index,
+                e.artifact.display_name(),
+                e.artifact.rarity,
+                e.artifact.level,
+                sim.current,
+                sim.expected(),
+                sim.percentile(0.1),
+                sim.percentile(0.5),
+                sim.percentile(0.9),
+                chance
+            );
+        }
+    }
+
+    pub fn save(&self, path: String) {
+        let mut file = match File::create(&path) {
+            Err(why) => panic!("couldn't create {}: {}", path, why),
+            Ok(file) => file,
+        };
+        let s = serde_json::to_string(&self).unwrap();
+
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::prelude::*;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+
+use crate::common::RawImage;
+
+// Characters that appear in levels, stat values and the artifact count
+pub const DIGIT_ALPHABET: &str = "0123456789+,.%/";
+
+const GLYPH_W: u32 = 24;
+const GLYPH_H: u32 = 32;
+
+// Template matching recognizer for numeric crops. Works on images after `pre_process`
+// (32 x 384, text is 1.0), splits them into glyphs by column projection and compares each
+// glyph with the averaged templates built from labeled crops (see `DigitTemplateBuilder`).
+pub struct DigitRecognizer {
+    templates: Vec<(char, Vec<f32>)>,
+    pub max_distance: f32,
+}
+
+fn is_digit_char(c: char) -> bool {
+    DIGIT_ALPHABET.contains(c)
+}
+
+// Trailing numeric part of a text up to a '+' or any other character, e.g. "1234/1500" of
+// "圣遗物 1234/1500"
+pub fn numeric_tail(s: &str) -> &str {
+    let start = s
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| *c != '+' && is_digit_char(*c))
+        .last()
+        .map_or(s.len(), |(i, _)| i);
+
+    &s[start..]
+}
+
+// Column ranges [start, end) that contain text
+pub fn segment(im: &RawImage) -> Vec<(u32, u32)> {
+    let mut spans: Vec<(u32, u32)> = Vec::new();
+    let mut start: Option<u32> = None;
+    for x in 0..im.w {
+        let has_text = (0..im.h).any(|y| im.data[(y * im.w + x) as usize] > 0.5);
+        match (has_text, start) {
+            (true, None) => start = Some(x),
+            (false, Some(s)) => {
+                spans.push((s, x));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, im.w));
+    }
+
+    spans
+}
+
+fn extract_glyph(im: &RawImage, span: (u32, u32)) -> Vec<f32> {
+    let (start, end) = span;
+    let width = end - start;
+    let mut glyph = vec![0.0; (GLYPH_W * GLYPH_H) as usize];
+    for gx in 0..GLYPH_W {
+        let x = if width <= GLYPH_W {
+            let offset = (GLYPH_W - width) / 2;
+            if gx < offset || gx >= offset + width {
+                continue;
+            }
+            start + gx - offset
+        } else {
+            start + gx * width / GLYPH_W
+        };
+        for y in 0..GLYPH_H.min(im.h) {
+            glyph[(y * GLYPH_W + gx) as usize] = im.data[(y * im.w + x) as usize];
+        }
+    }
+
+    glyph
+}
+
+// Mean absolute difference, allowing the glyph to be off by one column
+fn glyph_distance(a: &[f32], b: &[f32]) -> f32 {
+    let mut best = f32::MAX;
+    for shift in -1_i32..=1 {
+        let mut sum = 0.0;
+        for y in 0..GLYPH_H {
+            for x in 0..GLYPH_W {
+                let sx = x as i32 + shift;
+                let pa = a[(y * GLYPH_W + x) as usize];
+                let pb = if sx < 0 || sx >= GLYPH_W as i32 {
+                    0.0
+                } else {
+                    b[(y * GLYPH_W + sx as u32) as usize]
+                };
+                sum += (pa - pb).abs();
+            }
+        }
+        best = best.min(sum / (GLYPH_W * GLYPH_H) as f32);
+    }
+
+    best
+}
+
+impl DigitRecognizer {
+    pub fn from_file(path: &str) -> Result<DigitRecognizer, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("cannot read {}: {}", path, e)),
+        };
+        let json: Value = match serde_json::from_str(content.as_str()) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("cannot parse {}: {}", path, e)),
+        };
+
+        if json["width"].as_u64() != Some(GLYPH_W as u64)
+            || json["height"].as_u64() != Some(GLYPH_H as u64)
+        {
+            return Err(String::from("glyph size mismatch"));
+        }
+        let glyphs = match json["glyphs"].as_object() {
+            Some(v) => v,
+            None => return Err(String::from("missing glyphs")),
+        };
+
+        let mut templates: Vec<(char, Vec<f32>)> = Vec::new();
+        for (key, value) in glyphs.iter() {
+            let c = match key.chars().next() {
+                Some(c) if is_digit_char(c) => c,
+                _ => return Err(format!("unexpected glyph {}", key)),
+            };
+            let data: Vec<f32> = match value.as_array() {
+                Some(arr) => arr
+                    .iter()
+                    .map(|p| p.as_f64().unwrap_or(0.0) as f32)
+                    .collect(),
+                None => return Err(format!("bad glyph {}", key)),
+            };
+            if data.len() != (GLYPH_W * GLYPH_H) as usize {
+                return Err(format!("bad glyph {}", key));
+            }
+            templates.push((c, data));
+        }
+
+        Ok(DigitRecognizer {
+            templates,
+            max_distance: 0.15,
+        })
+    }
+
+    pub fn save(&self, path: String) {
+        let mut file = match File::create(&path) {
+            Err(why) => panic!("couldn't create {}: {}", path, why),
+            Ok(file) => file,
+        };
+        let s = serde_json::to_string(&self).unwrap();
+
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+
+    fn match_glyph(&self, glyph: &[f32]) -> Option<char> {
+        let mut best: Option<(char, f32)> = None;
+        for (c, template) in self.templates.iter() {
+            let dis = glyph_distance(glyph, template);
+            if best.is_none_or(|(_, d)| dis < d) {
+                best = Some((*c, dis));
+            }
+        }
+
+        match best {
+            Some((c, dis)) if dis <= self.max_distance => Some(c),
+            _ => None,
+        }
+    }
+
+    // The whole crop must be numeric, e.g. "+20", "4,123", "46.6%"
+    pub fn recognize(&self, im: &RawImage) -> Option<String> {
+        let spans = segment(im);
+        if spans.is_empty() {
+            return None;
+        }
+
+        let mut ans = String::new();
+        for span in spans {
+            ans.push(self.match_glyph(&extract_glyph(im, span))?);
+        }
+
+        Some(ans)
+    }
+
+    // Numeric part after the last '+' of a crop, e.g. "3.9%" of "暴击率+3.9%". None unless
+    // every glyph up to the '+' is recognized, stopping at a missed digit would give a shorter
+    // value that still looks right.
+    pub fn recognize_suffix(&self, im: &RawImage) -> Option<String> {
+        let mut chars: Vec<char> = Vec::new();
+        for span in segment(im).into_iter().rev() {
+            match self.match_glyph(&extract_glyph(im, span))? {
+                '+' if !chars.is_empty() => return Some(chars.into_iter().rev().collect()),
+                '+' => return None,
+                c => chars.push(c),
+            }
+        }
+
+        None
+    }
+
+    // The last `len` glyphs of a crop with a text prefix, e.g. "1234/1500" of
+    // "圣遗物 1234/1500", where `len` is the length of the `numeric_tail` another reader found.
+    // None unless all of them are recognized and the glyph before is not numeric, otherwise
+    // that reader missed a digit.
+    pub fn recognize_tail(&self, im: &RawImage, len: usize) -> Option<String> {
+        let spans = segment(im);
+        if len == 0 || spans.len() < len {
+            return None;
+        }
+        let prefix_len = spans.len() - len;
+        if prefix_len > 0 {
+            let before = extract_glyph(im, spans[prefix_len - 1]);
+            if matches!(self.match_glyph(&before), Some(c) if c != '+') {
+                return None;
+            }
+        }
+
+        spans[prefix_len..]
+            .iter()
+            .map(|span| self.match_glyph(&extract_glyph(im, *span)))
+            .collect()
+    }
+}
+
+impl Serialize for DigitRecognizer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let glyphs: BTreeMap<String, &Vec<f32>> = self
+            .templates
+            .iter()
+            .map(|(c, data)| (c.to_string(), data))
+            .collect();
+
+        let mut root = serializer.serialize_map(Some(3))?;
+        root.serialize_entry("width", &GLYPH_W)?;
+        root.serialize_entry("height", &GLYPH_H)?;
+        root.serialize_entry("glyphs", &glyphs)?;
+        root.end()
+    }
+}
+
+// Averages glyphs cut from labeled crops into templates
+pub struct DigitTemplateBuilder {
+    sums: BTreeMap<char, (Vec<f32>, u32)>,
+}
+
+impl Default for DigitTemplateBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DigitTemplateBuilder {
+    pub fn new() -> DigitTemplateBuilder {
+        DigitTemplateBuilder {
+            sums: BTreeMap::new(),
+        }
+    }
+
+    // Uses the trailing numeric part of `label`. Returns false when the segmentation does not
+    // agree with the label (touching glyphs, noise), in which case the crop is ignored.
+    pub fn add(&mut self, im: &RawImage, label: &str) -> bool {
+        let chars: Vec<char> = label.chars().collect();
+        let suffix_len = chars
+            .iter()
+            .rev()
+            .take_while(|c| is_digit_char(**c))
+            .count();
+        if suffix_len == 0 {
+            return false;
+        }
+
+        let spans = segment(im);
+        let whole = suffix_len == chars.len();
+        if (whole && spans.len() != suffix_len) || spans.len() < suffix_len {
+            return false;
+        }
+
+        let spans = &spans[spans.len() - suffix_len..];
+        let suffix = &chars[chars.len() - suffix_len..];
+        for (span, c) in spans.iter().zip(suffix.iter()) {
+            let glyph = extract_glyph(im, *span);
+            let entry = self
+                .sums
+                .entry(*c)
+                .or_insert_with(|| (vec![0.0; (GLYPH_W * GLYPH_H) as usize], 0));
+            for (s, p) in entry.0.iter_mut().zip(glyph.iter()) {
+                *s += *p;
+            }
+            entry.1 += 1;
+        }
+
+        true
+    }
+
+    pub fn build(&self) -> DigitRecognizer {
+        let templates = self
+            .sums
+            .iter()
+            .map(|(c, (sum, count))| (*c, sum.iter().map(|p| *p / *count as f32).collect()))
+            .collect();
+
+        DigitRecognizer {
+            templates,
+            max_distance: 0.15,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Glyphs are full width bars in 8 bands of 4 rows, an even number of bands each so any two
+    // differ by at least two bands. All bands on is a glyph that is not numeric.
+    const UNKNOWN: u32 = 0xff;
+
+    fn bands(c: char) -> u32 {
+        let codes: Vec<u32> = (1..UNKNOWN)
+            .filter(|b: &u32| b.count_ones() & 1 == 0)
+            .collect();
+        match DIGIT_ALPHABET.find(c) {
+            Some(i) => codes[i],
+            None => UNKNOWN,
+        }
+    }
+
+    // Strip of glyphs 4 columns apart, `None` for a smudged one no template matches
+    fn strip(glyphs: &[Option<char>]) -> RawImage {
+        let w = 384.max(4 + glyphs.len() as u32 * (GLYPH_W + 4));
+        let mut data = vec![0.0; (w * GLYPH_H) as usize];
+        for (i, glyph) in glyphs.iter().enumerate() {
+            let left = 2 + i as u32 * (GLYPH_W + 4);
+            // a smudged glyph is a narrow blot
+            let (code, columns) = match glyph {
+                Some(c) => (bands(*c), left..left + GLYPH_W),
+                None => (UNKNOWN, left + 9..left + 15),
+            };
+            for y in 0..GLYPH_H {
+                if code & (1 << (y / 4)) == 0 {
+                    continue;
+                }
+                for x in columns.clone() {
+                    data[(y * w + x) as usize] = 1.0;
+                }
+            }
+        }
+
+        RawImage {
+            data,
+            w,
+            h: GLYPH_H,
+        }
+    }
+
+    fn text(s: &str) -> Vec<Option<char>> {
+        s.chars().map(Some).collect()
+    }
+
+    fn recognizer() -> DigitRecognizer {
+        let mut builder = DigitTemplateBuilder::new();
+        assert!(builder.add(&strip(&text(DIGIT_ALPHABET)), DIGIT_ALPHABET));
+        builder.build()
+    }
+
+    // The glyphs of `s` with the one at `missed` smudged
+    fn missing(s: &str, missed: usize) -> Vec<Option<char>> {
+        let mut glyphs = text(s);
+        glyphs[missed] = None;
+        glyphs
+    }
+
+    #[test]
+    fn smudged_glyph_does_not_match() {
+        let digit = recognizer();
+        assert_eq!(
+            digit.recognize(&strip(&text("46.6%"))),
+            Some(String::from("46.6%"))
+        );
+        assert_eq!(digit.recognize(&strip(&missing("46.6%", 1))), None);
+    }
+
+    #[test]
+    fn suffix_reaches_plus() {
+        let digit = recognizer();
+        assert_eq!(
+            digit.recognize_suffix(&strip(&text("暴击率+3.9%"))),
+            Some(String::from("3.9%"))
+        );
+        assert_eq!(
+            digit.recognize_suffix(&strip(&text("攻击力+1,234"))),
+            Some(String::from("1,234"))
+        );
+    }
+
+    #[test]
+    fn suffix_with_missed_digit_is_rejected() {
+        let digit = recognizer();
+        // "9%" and "234" look fine on their own
+        assert_eq!(
+            digit.recognize_suffix(&strip(&missing("暴击率+3.9%", 5))),
+            None
+        );
+        assert_eq!(
+            digit.recognize_suffix(&strip(&missing("攻击力+1,234", 4))),
+            None
+        );
+        // no '+' to end at
+        assert_eq!(
+            digit.recognize_suffix(&strip(&text("圣遗物1234/1500"))),
+            None
+        );
+        assert_eq!(digit.recognize_suffix(&strip(&text("+"))), None);
+    }
+
+    #[test]
+    fn tail_needs_a_text_glyph_before() {
+        let digit = recognizer();
+        let count = strip(&text("圣遗物1234/1500"));
+        assert_eq!(
+            digit.recognize_tail(&count, 9),
+            Some(String::from("1234/1500"))
+        );
+        // the model read "234/1500"
+        assert_eq!(digit.recognize_tail(&count, 8), None);
+        assert_eq!(digit.recognize_tail(&count, 0), None);
+        assert_eq!(digit.recognize_tail(&count, 13), None);
+        assert_eq!(
+            digit.recognize_tail(&strip(&missing("圣遗物1234/1500", 3)), 9),
+            None
+        );
+        assert_eq!(
+            digit.recognize_tail(&strip(&text("1234/1500")), 9),
+            Some(String::from("1234/1500"))
+        );
+    }
+
+    #[test]
+    fn numeric_tail_stops_at_text_and_plus() {
+        assert_eq!(numeric_tail("圣遗物 1234/1500"), "1234/1500");
+        assert_eq!(numeric_tail("Artifacts 1234/1500"), "1234/1500");
+        assert_eq!(numeric_tail("暴击率+3.9%"), "3.9%");
+        assert_eq!(numeric_tail("圣遗物"), "");
+    }
+}
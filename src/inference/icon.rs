@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+
+use image::imageops::{self, FilterType};
+use image::RgbImage;
+
+// Closed-vocabulary icon recognizer for the material grid. The icon set is a directory of
+// `<dir>/<key>.png`, one cell icon per material named by its GOOD key, e.g. a crop saved by
+// `--dump` and renamed. Icons are compared in color since the tiers of a material mostly
+// differ by their background, and at a fixed size so a set taken at one resolution works
+// at the others.
+pub struct IconMatcher {
+    templates: Vec<(String, RgbImage)>,
+    pub max_distance: f32,
+}
+
+const ICON_SIZE: u32 = 32;
+// standard deviation of the brightness of an empty cell, out of 255
+const BLANK_MAX_DEVIATION: f32 = 6.0;
+
+fn normalize(im: &RgbImage) -> RgbImage {
+    imageops::resize(im, ICON_SIZE, ICON_SIZE, FilterType::Triangle)
+}
+
+// An empty cell shows only the plain background of the grid, any item icon has some contrast
+pub fn is_blank(im: &RgbImage) -> bool {
+    let n = (im.width() * im.height()) as f32;
+    if n == 0.0 {
+        return true;
+    }
+    let luma: Vec<f32> = im
+        .pixels()
+        .map(|p| 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32)
+        .collect();
+    let mean = luma.iter().sum::<f32>() / n;
+    let variance = luma.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / n;
+
+    variance.sqrt() < BLANK_MAX_DEVIATION
+}
+
+// Mean absolute difference over all channels, in [0, 1]
+fn icon_distance(a: &RgbImage, b: &RgbImage) -> f32 {
+    let sum: u32 = a
+        .as_raw()
+        .iter()
+        .zip(b.as_raw().iter())
+        .map(|(&x, &y)| (x as i32 - y as i32).unsigned_abs())
+        .sum();
+
+    sum as f32 / (a.as_raw().len() as f32 * 255.0)
+}
+
+impl IconMatcher {
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<IconMatcher, String> {
+        let dir = dir.as_ref();
+        let entries = match fs::read_dir(dir) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("cannot read {}: {}", dir.display(), e)),
+        };
+
+        let mut templates: Vec<(String, RgbImage)> = Vec::new();
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "png") {
+                continue;
+            }
+            let key = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(v) => String::from(v),
+                None => continue,
+            };
+            let img = match image::open(&path) {
+                Ok(v) => v.to_rgb8(),
+                Err(e) => return Err(format!("cannot open {}: {}", path.display(), e)),
+            };
+            templates.push((key, normalize(&img)));
+        }
+
+        if templates.is_empty() {
+            return Err(format!("no material icons in {}", dir.display()));
+        }
+        templates.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(IconMatcher {
+            templates,
+            max_distance: 0.1,
+        })
+    }
+
+    // Best matching key and its distance
+    pub fn best_match(&self, im: &RgbImage) -> Option<(String, f32)> {
+        let im = normalize(im);
+        let mut best: Option<(&String, f32)> = None;
+        for (key, template) in self.templates.iter() {
+            let dis = icon_distance(&im, template);
+            if best.is_none_or(|(_, d)| dis < d) {
+                best = Some((key, dis));
+            }
+        }
+
+        best.map(|(key, dis)| (key.clone(), dis))
+    }
+
+    // Like `best_match` but only accepts matches within `max_distance`
+    pub fn recognize(&self, im: &RgbImage) -> Option<String> {
+        match self.best_match(im) {
+            Some((key, dis)) if dis <= self.max_distance => Some(key),
+            _ => None,
+        }
+    }
+}
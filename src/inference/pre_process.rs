@@ -14,7 +14,7 @@ pub fn to_gray(raw: Vec<u8>, width: u32, height: u32) -> RawImage {
         for j in 0..height {
             let x = i;
             let y = height - j - 1;
-            let b = raw[((y * width + x) * 4 + 0) as usize];
+            let b = raw[((y * width + x) * 4) as usize];
             let g = raw[((y * width + x) * 4 + 1) as usize];
             let r = raw[((y * width + x) * 4 + 2) as usize];
 
@@ -22,7 +22,7 @@ pub fn to_gray(raw: Vec<u8>, width: u32, height: u32) -> RawImage {
             let g = g as f32 / 255.0;
             let b = b as f32 / 255.0;
 
-            let gray = r as f32 * 0.2989 + g as f32 * 0.5870 + b as f32 * 0.1140;
+            let gray = r * 0.2989 + g * 0.5870 + b * 0.1140;
             let index = get_index(width, i, j);
             ans[index] = gray;
         }
@@ -141,15 +141,13 @@ pub fn raw_to_img(im: &RawImage) -> GrayImage {
     let height = im.h;
     let data = &im.data;
 
-    let img = ImageBuffer::from_fn(width, height, |x, y| {
+    ImageBuffer::from_fn(width, height, |x, y| {
         let index = get_index(width, x, y);
         let p = data[index];
         let pixel = (p * 255.0) as u32;
         let pixel: u8 = if pixel > 255 { 255 } else { pixel as u8 };
         image::Luma([pixel])
-    });
-
-    img
+    })
 }
 
 pub fn uint8_raw_to_img(im: &RawImage) -> GrayImage {
@@ -157,14 +155,12 @@ pub fn uint8_raw_to_img(im: &RawImage) -> GrayImage {
     let height = im.h;
     let data = &im.data;
 
-    let img = ImageBuffer::from_fn(width, height, |x, y| {
+    ImageBuffer::from_fn(width, height, |x, y| {
         let index = get_index(width, x, y);
         let pixel = data[index] as u32;
         let pixel: u8 = if pixel > 255 { 255 } else { pixel as u8 };
         image::Luma([pixel])
-    });
-
-    img
+    })
 }
 
 pub fn resize_and_pad(im: &RawImage) -> RawImage {
@@ -173,7 +169,7 @@ pub fn resize_and_pad(im: &RawImage) -> RawImage {
 
     let new_width = (32.0 / h as f64 * w as f64) as u32;
 
-    let img = raw_to_img(&im);
+    let img = raw_to_img(im);
     let img = resize(&img, new_width, 32, image::imageops::FilterType::Triangle);
 
     let mut data: Vec<f32> = vec![0.0; 32 * 384];
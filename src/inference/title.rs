@@ -0,0 +1,137 @@
+use std::fs;
+use std::path::Path;
+
+use log::warn;
+
+use crate::artifact::game_data::game_data;
+use crate::artifact::lang::Language;
+use crate::common::RawImage;
+use crate::inference::pre_process::{image_to_raw, pre_process};
+
+// Closed-vocabulary title recognizer. Every known artifact name has a captured title crop
+// (`<dir>/<name>.png`, e.g. from `--dump`), the crop to recognize is compared against all of
+// them after `pre_process` and the closest one wins.
+pub struct TitleMatcher {
+    templates: Vec<(String, RawImage)>,
+    pub max_distance: f32,
+}
+
+// Right-most column that contains text
+fn text_extent(im: &RawImage) -> u32 {
+    for x in (0..im.w).rev() {
+        if (0..im.h).any(|y| im.data[(y * im.w + x) as usize] > 0.5) {
+            return x + 1;
+        }
+    }
+    0
+}
+
+// Mean absolute difference over the text area, allowing a shift of two columns
+fn title_distance(a: &RawImage, b: &RawImage) -> f32 {
+    let width = text_extent(a).max(text_extent(b));
+    if width == 0 {
+        return 1.0;
+    }
+
+    let mut best = f32::MAX;
+    for shift in -2_i32..=2 {
+        let mut sum = 0.0;
+        for y in 0..a.h {
+            for x in 0..width {
+                let sx = x as i32 + shift;
+                let pa = a.data[(y * a.w + x) as usize];
+                let pb = if sx < 0 || sx >= b.w as i32 {
+                    0.0
+                } else {
+                    b.data[(y * b.w + sx as u32) as usize]
+                };
+                sum += (pa - pb).abs();
+            }
+        }
+        best = best.min(sum / (width * a.h) as f32);
+    }
+
+    best
+}
+
+impl TitleMatcher {
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<TitleMatcher, String> {
+        let dir = dir.as_ref();
+        let names = game_data().piece_names(Language::ZhCn);
+        let mut templates: Vec<(String, RawImage)> = Vec::new();
+        for &name in names.iter() {
+            let path = dir.join(format!("{}.png", name));
+            if !path.exists() {
+                continue;
+            }
+            let img = match image::open(&path) {
+                Ok(v) => v.to_luma8(),
+                Err(e) => return Err(format!("cannot open {}: {}", path.display(), e)),
+            };
+            templates.push((String::from(name), pre_process(image_to_raw(img))));
+        }
+
+        if templates.is_empty() {
+            return Err(format!("no title templates in {}", dir.display()));
+        }
+        if templates.len() < names.len() {
+            warn!(
+                "title templates: {}/{} names covered",
+                templates.len(),
+                names.len()
+            );
+        }
+
+        Ok(TitleMatcher {
+            templates,
+            max_distance: 0.08,
+        })
+    }
+
+    // Best matching known name and its distance, `im` must be preprocessed
+    pub fn best_match(&self, im: &RawImage) -> Option<(String, f32)> {
+        let mut best: Option<(&String, f32)> = None;
+        for (name, template) in self.templates.iter() {
+            let dis = title_distance(im, template);
+            if best.is_none_or(|(_, d)| dis < d) {
+                best = Some((name, dis));
+            }
+        }
+
+        best.map(|(name, dis)| (name.clone(), dis))
+    }
+
+    // Like `best_match` but only accepts matches within `max_distance`
+    pub fn recognize(&self, im: &RawImage) -> Option<String> {
+        match self.best_match(im) {
+            Some((name, dis)) if dis <= self.max_distance => Some(name),
+            _ => None,
+        }
+    }
+}
+
+// Copies labeled title crops into a template directory, one per known name. Returns the
+// number of names written.
+pub fn save_title_templates<P: AsRef<Path>>(
+    crops: &[(String, &Path)],
+    dir: P,
+) -> Result<u32, String> {
+    let dir = dir.as_ref();
+    if let Err(e) = fs::create_dir_all(dir) {
+        return Err(format!("cannot create {}: {}", dir.display(), e));
+    }
+
+    let mut count = 0;
+    for &name in game_data().piece_names(Language::ZhCn).iter() {
+        let crop = match crops.iter().find(|(label, _)| label == name) {
+            Some(v) => v.1,
+            None => continue,
+        };
+        if let Err(e) = fs::copy(crop, dir.join(format!("{}.png", name))) {
+            return Err(format!("cannot copy {}: {}", crop.display(), e));
+        }
+        count += 1;
+    }
+
+    Ok(count)
+}
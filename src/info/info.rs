@@ -21,6 +21,16 @@ pub struct ScanInfo {
     pub equip_position: PixelRectBound,
     pub art_count_position: PixelRectBound,
 
+    // weapon panel, the other fields are where the artifact panel has them
+    pub weapon_level_position: PixelRectBound,
+    pub refinement_position: PixelRectBound,
+
+    // material grid, relative to the top left of a cell
+    pub material_icon_position: PixelRectBound,
+    pub material_count_position: PixelRectBound,
+    // tabs holding the development items and the other materials, (x, y)
+    pub material_tabs: [(u32, u32); 2],
+
     pub art_width: u32,
     pub art_height: u32,
     pub art_gap_x: u32,
@@ -47,6 +57,23 @@ pub struct ScanInfo {
     pub lock_y: u32,
 
     pub pool_position: PixelRectBound,
+
+    pub character: CharacterScanInfo,
+}
+
+#[derive(Clone, Debug)]
+pub struct CharacterScanInfo {
+    pub name_position: PixelRectBound,
+    pub level_position: PixelRectBound,
+    // auto attack, skill and burst
+    pub talent_positions: [PixelRectBound; 3],
+
+    pub attribute_tab: (u32, u32),
+    pub constellation_tab: (u32, u32),
+    pub talent_tab: (u32, u32),
+    pub next_character: (u32, u32),
+
+    pub constellations: [(u32, u32); 6],
 }
 
 impl ScanInfo {
@@ -1,5 +1,5 @@
 use crate::common::PixelRectBound;
-use crate::info::info::ScanInfo;
+use crate::info::info::{CharacterScanInfo, ScanInfo};
 
 pub struct Rect(f64, f64, f64, f64); // top, right, bottom, left
 
@@ -21,6 +21,14 @@ pub struct WindowInfo {
     pub equip_pos: Rect,
     pub art_count_pos: Rect,
 
+    pub weapon_level_pos: Rect,
+    pub refinement_pos: Rect,
+
+    // relative to the top left of a grid cell
+    pub material_icon_pos: Rect,
+    pub material_count_pos: Rect,
+    pub material_tabs: [(f64, f64); 2],
+
     pub art_width: f64,
     pub art_height: f64,
     pub art_gap_x: f64,
@@ -42,6 +50,26 @@ pub struct WindowInfo {
     pub lock_y: f64,
 
     pub pool_pos: Rect,
+
+    pub character: CharacterWindowInfo,
+}
+
+// Character screen, opened on the first character
+pub struct CharacterWindowInfo {
+    // attribute tab
+    pub name_pos: Rect,
+    pub level_pos: Rect,
+    // talent tab, auto attack, skill and burst
+    pub talent_pos: [Rect; 3],
+
+    // points to click, (x, y)
+    pub attribute_tab: (f64, f64),
+    pub constellation_tab: (f64, f64),
+    pub talent_tab: (f64, f64),
+    pub next_character: (f64, f64),
+
+    // the six constellations in order, lit once activated
+    pub constellations: [(f64, f64); 6],
 }
 
 impl WindowInfo {
@@ -64,6 +92,31 @@ impl WindowInfo {
 
         let convert_y = |y: f64| y / self.height * h;
 
+        let convert_point = |p: &(f64, f64)| (convert_x(p.0) as u32, convert_y(p.1) as u32);
+
+        let c = &self.character;
+        let character = CharacterScanInfo {
+            name_position: convert_rect(&c.name_pos),
+            level_position: convert_rect(&c.level_pos),
+            talent_positions: [
+                convert_rect(&c.talent_pos[0]),
+                convert_rect(&c.talent_pos[1]),
+                convert_rect(&c.talent_pos[2]),
+            ],
+            attribute_tab: convert_point(&c.attribute_tab),
+            constellation_tab: convert_point(&c.constellation_tab),
+            talent_tab: convert_point(&c.talent_tab),
+            next_character: convert_point(&c.next_character),
+            constellations: [
+                convert_point(&c.constellations[0]),
+                convert_point(&c.constellations[1]),
+                convert_point(&c.constellations[2]),
+                convert_point(&c.constellations[3]),
+                convert_point(&c.constellations[4]),
+                convert_point(&c.constellations[5]),
+            ],
+        };
+
         ScanInfo {
             title_position: convert_rect(&self.title_pos),
             main_stat_name_position: convert_rect(&self.main_stat_name_pos),
@@ -76,6 +129,14 @@ impl WindowInfo {
             sub_stat4_position: convert_rect(&self.sub_stat4_pos),
             equip_position: convert_rect(&self.equip_pos),
             art_count_position: convert_rect(&self.art_count_pos),
+            weapon_level_position: convert_rect(&self.weapon_level_pos),
+            refinement_position: convert_rect(&self.refinement_pos),
+            material_icon_position: convert_rect(&self.material_icon_pos),
+            material_count_position: convert_rect(&self.material_count_pos),
+            material_tabs: [
+                convert_point(&self.material_tabs[0]),
+                convert_point(&self.material_tabs[1]),
+            ],
             art_width: convert_x(self.art_width) as u32,
             art_height: convert_y(self.art_height) as u32,
             art_gap_x: convert_x(self.art_gap_x) as u32,
@@ -95,6 +156,7 @@ impl WindowInfo {
             lock_x: convert_x(self.lock_x) as u32,
             lock_y: convert_y(self.lock_y) as u32,
             pool_position: convert_rect(&self.pool_pos),
+            character,
         }
     }
 }
@@ -117,6 +179,13 @@ pub const WINDOW_16_9: WindowInfo = WindowInfo {
     equip_pos: Rect(762.6, 1389.4, 787.8, 1154.9),
     art_count_pos: Rect(27.1, 1504.7, 52.9, 1314.9),
 
+    weapon_level_pos: Rect(355.0, 1215.0, 380.0, 1117.0),
+    refinement_pos: Rect(388.0, 1230.0, 412.0, 1117.0),
+
+    material_icon_pos: Rect(0.0, 102.0, 100.0, 0.0),
+    material_count_pos: Rect(102.0, 102.0, 124.0, 0.0),
+    material_tabs: [(705.0, 45.0), (855.0, 45.0)],
+
     art_width: 1055.0 - 953.0,
     art_height: 373.0 - 247.0,
     art_gap_x: 953.0 - 933.0,
@@ -138,6 +207,8 @@ pub const WINDOW_16_9: WindowInfo = WindowInfo {
     lock_y: 357.0,
 
     pool_pos: Rect(118.2, 1144.7 + 15.0, 510.3, 1144.7),
+
+    character: CHARACTER_16_9,
 };
 
 pub const WINDOW_8_5: WindowInfo = WindowInfo {
@@ -154,6 +225,11 @@ pub const WINDOW_8_5: WindowInfo = WindowInfo {
     sub_stat4_pos: Rect(440.5, 1224.1, 467.1, 1016.2),
     equip_pos: Rect(776.0, 1247.3, 800.6, 1041.3),
     art_count_pos: Rect(25.0, 1353.1, 46.8, 1182.8),
+    weapon_level_pos: Rect(319.5, 1094.2, 342.0, 1006.0),
+    refinement_pos: Rect(349.2, 1107.7, 370.8, 1006.0),
+    material_icon_pos: Rect(0.0, 93.0, 90.0, 0.0),
+    material_count_pos: Rect(92.0, 93.0, 111.0, 0.0),
+    material_tabs: [(634.5, 40.5), (769.5, 40.5)],
     art_width: 950.0 - 857.0,
     art_height: 204.0 - 91.0,
     art_gap_x: 857.0 - 840.0,
@@ -169,6 +245,7 @@ pub const WINDOW_8_5: WindowInfo = WindowInfo {
     lock_x: 1305.0,
     lock_y: 322.0,
     pool_pos: Rect(103.6, 1025.8 + 15.0, 460.7, 1028.5),
+    character: CHARACTER_8_5,
 };
 
 pub const WINDOW_4_3: WindowInfo = WindowInfo {
@@ -185,6 +262,11 @@ pub const WINDOW_4_3: WindowInfo = WindowInfo {
     sub_stat4_pos: Rect(395.3, 1100.5, 420.6, 904.3),
     equip_pos: Rect(849.8, 1090.8, 870.1, 924.4),
     art_count_pos: Rect(22.9, 1202.3, 41.4, 1058.6),
+    weapon_level_pos: Rect(284.0, 972.4, 304.0, 894.0),
+    refinement_pos: Rect(310.4, 984.4, 329.6, 894.0),
+    material_icon_pos: Rect(0.0, 82.0, 80.0, 0.0),
+    material_count_pos: Rect(82.0, 82.0, 99.0, 0.0),
+    material_tabs: [(564.0, 36.0), (684.0, 36.0)],
     art_width: 844.0 - 762.0,
     art_height: 182.0 - 81.0,
     art_gap_x: 762.0 - 747.0,
@@ -200,4 +282,72 @@ pub const WINDOW_4_3: WindowInfo = WindowInfo {
     lock_x: 1160.0,
     lock_y: 286.0,
     pool_pos: Rect(93.2, 912.7 + 15.0, 412.4, 912.7),
+    character: CHARACTER_4_3,
+};
+
+// Character screen layouts, one per window layout above
+pub const CHARACTER_16_9: CharacterWindowInfo = CharacterWindowInfo {
+    name_pos: Rect(84.0, 1420.0, 118.0, 1110.0),
+    level_pos: Rect(180.0, 1300.0, 205.0, 1110.0),
+    talent_pos: [
+        Rect(128.0, 1290.0, 150.0, 1210.0),
+        Rect(213.0, 1290.0, 235.0, 1210.0),
+        Rect(298.0, 1290.0, 320.0, 1210.0),
+    ],
+    attribute_tab: (165.0, 145.0),
+    constellation_tab: (165.0, 325.0),
+    talent_tab: (165.0, 385.0),
+    constellations: [
+        (1215.0, 175.0),
+        (1300.0, 290.0),
+        (1335.0, 420.0),
+        (1300.0, 550.0),
+        (1215.0, 665.0),
+        (1110.0, 745.0),
+    ],
+    next_character: (1560.0, 450.0),
+};
+
+pub const CHARACTER_8_5: CharacterWindowInfo = CharacterWindowInfo {
+    name_pos: Rect(75.6, 1278.0, 106.2, 999.0),
+    level_pos: Rect(162.0, 1170.0, 184.5, 999.0),
+    talent_pos: [
+        Rect(115.2, 1161.0, 135.0, 1089.0),
+        Rect(191.7, 1161.0, 211.5, 1089.0),
+        Rect(268.2, 1161.0, 288.0, 1089.0),
+    ],
+    attribute_tab: (148.5, 130.5),
+    constellation_tab: (148.5, 292.5),
+    talent_tab: (148.5, 346.5),
+    constellations: [
+        (1093.5, 157.5),
+        (1170.0, 261.0),
+        (1201.5, 378.0),
+        (1170.0, 495.0),
+        (1093.5, 598.5),
+        (999.0, 670.5),
+    ],
+    next_character: (1404.0, 405.0),
+};
+
+pub const CHARACTER_4_3: CharacterWindowInfo = CharacterWindowInfo {
+    name_pos: Rect(67.2, 1136.0, 94.4, 888.0),
+    level_pos: Rect(144.0, 1040.0, 164.0, 888.0),
+    talent_pos: [
+        Rect(102.4, 1032.0, 120.0, 968.0),
+        Rect(170.4, 1032.0, 188.0, 968.0),
+        Rect(238.4, 1032.0, 256.0, 968.0),
+    ],
+    attribute_tab: (132.0, 116.0),
+    constellation_tab: (132.0, 260.0),
+    talent_tab: (132.0, 308.0),
+    constellations: [
+        (972.0, 140.0),
+        (1040.0, 232.0),
+        (1068.0, 336.0),
+        (1040.0, 440.0),
+        (972.0, 532.0),
+        (888.0, 596.0),
+    ],
+    next_character: (1248.0, 360.0),
 };
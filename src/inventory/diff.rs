@@ -0,0 +1,358 @@
+use std::fs::File;
+use std::io::prelude::*;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+
+use crate::artifact::internal_artifact::{ArtifactStat, CharacterKey, InternalArtifact};
+use crate::artifact::rolls::max_level;
+use crate::expo::good::read_good_artifacts;
+use crate::expo::mona_uranai::read_mona_artifacts;
+
+// Artifacts of an export, and whether it records lock state and location
+pub struct Inventory {
+    pub artifacts: Vec<InternalArtifact>,
+    pub has_state: bool,
+}
+
+// Reads a good.json or mona.json, told apart by their content
+pub fn read_inventory(s: &str) -> Result<Inventory, String> {
+    let json: Value = match serde_json::from_str(s) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("cannot parse inventory: {}", e)),
+    };
+
+    if json["format"].as_str() == Some("GOOD") {
+        Ok(Inventory {
+            artifacts: read_good_artifacts(s)?,
+            has_state: true,
+        })
+    } else if json["flower"].is_array() {
+        Ok(Inventory {
+            artifacts: read_mona_artifacts(s)?,
+            has_state: false,
+        })
+    } else {
+        Err(String::from("neither a GOOD nor a mona file"))
+    }
+}
+
+// Displayed values differ by rounding only
+fn same_value(a: &ArtifactStat, b: &ArtifactStat) -> bool {
+    let tolerance = if a.key.is_percentage() { 0.05 } else { 0.5 };
+    (a.value - b.value).abs() <= tolerance
+}
+
+fn same_piece(a: &InternalArtifact, b: &InternalArtifact) -> bool {
+    a.set_key == b.set_key
+        && a.slot_key == b.slot_key
+        && a.rarity == b.rarity
+        && a.main_stat.key == b.main_stat.key
+}
+
+fn same_stats(a: &InternalArtifact, b: &InternalArtifact) -> bool {
+    let (subs_a, subs_b) = (a.substats(), b.substats());
+    same_piece(a, b)
+        && a.level == b.level
+        && subs_a.len() == subs_b.len()
+        && subs_a
+            .iter()
+            .zip(subs_b.iter())
+            .all(|(x, y)| x.key == y.key && same_value(x, y))
+}
+
+// Whether `a` can have become `b` by upgrading. Every 4 levels one substat is added or
+// increased, so no more substats than that may have changed and none may have decreased.
+// Some(levels gained) if so.
+fn upgrade_distance(a: &InternalArtifact, b: &InternalArtifact) -> Option<u32> {
+    if !same_piece(a, b) || b.level <= a.level {
+        return None;
+    }
+    let cap = max_level(a.rarity);
+    let upgrades = b.level.min(cap) / 4 - a.level.min(cap) / 4;
+
+    let (subs_a, subs_b) = (a.substats(), b.substats());
+    if subs_b.len() < subs_a.len() {
+        return None;
+    }
+    let mut changed = (subs_b.len() - subs_a.len()) as u32;
+    for x in subs_a.iter() {
+        let y = subs_b.iter().find(|y| y.key == x.key)?;
+        if !same_value(x, y) {
+            if y.value < x.value {
+                return None;
+            }
+            changed += 1;
+        }
+    }
+    if changed > upgrades {
+        return None;
+    }
+
+    Some(b.level - a.level)
+}
+
+#[derive(Debug, Clone)]
+pub struct InventoryChange {
+    // positions in the old and new inventory
+    pub old: usize,
+    pub new: usize,
+    pub upgraded: bool,
+    pub location: Option<(Option<CharacterKey>, Option<CharacterKey>)>,
+    pub lock: Option<(bool, bool)>,
+}
+
+pub struct InventoryDiff<'a> {
+    old: &'a [InternalArtifact],
+    new: &'a [InternalArtifact],
+    // for each old artifact its position in the new inventory
+    pairs: Vec<Option<usize>>,
+    // positions in the new inventory
+    pub added: Vec<usize>,
+    // positions in the old inventory
+    pub removed: Vec<usize>,
+    pub changed: Vec<InventoryChange>,
+    pub unchanged: usize,
+}
+
+impl<'a> InventoryDiff<'a> {
+    // Artifacts are paired in three passes: identical stats and state, identical stats, then
+    // upgrades, closest level first. Identical artifacts are interchangeable, so pairing
+    // them in order gives the same report. With `compare_state` off (mona files) lock state
+    // and location are not compared.
+    pub fn new(
+        old: &'a [InternalArtifact],
+        new: &'a [InternalArtifact],
+        compare_state: bool,
+    ) -> InventoryDiff<'a> {
+        let mut old_match: Vec<Option<usize>> = vec![None; old.len()];
+        let mut new_taken: Vec<bool> = vec![false; new.len()];
+
+        let same_state = |a: &InternalArtifact, b: &InternalArtifact| {
+            a.lock == b.lock && a.location == b.location
+        };
+        for strict in [true, false].iter() {
+            for (i, a) in old.iter().enumerate() {
+                if old_match[i].is_some() {
+                    continue;
+                }
+                let found = new.iter().enumerate().position(|(j, b)| {
+                    !new_taken[j] && same_stats(a, b) && (!*strict || same_state(a, b))
+                });
+                if let Some(j) = found {
+                    old_match[i] = Some(j);
+                    new_taken[j] = true;
+                }
+            }
+        }
+
+        let mut candidates: Vec<(u32, usize, usize)> = Vec::new();
+        for (i, a) in old.iter().enumerate() {
+            if old_match[i].is_some() {
+                continue;
+            }
+            for (j, b) in new.iter().enumerate() {
+                if new_taken[j] {
+                    continue;
+                }
+                if let Some(d) = upgrade_distance(a, b) {
+                    candidates.push((d, i, j));
+                }
+            }
+        }
+        candidates.sort();
+        for (_, i, j) in candidates {
+            if old_match[i].is_none() && !new_taken[j] {
+                old_match[i] = Some(j);
+                new_taken[j] = true;
+            }
+        }
+
+        let mut removed: Vec<usize> = Vec::new();
+        let mut changed: Vec<InventoryChange> = Vec::new();
+        let mut unchanged = 0;
+        for (i, m) in old_match.iter().enumerate() {
+            let j = match m {
+                Some(j) => *j,
+                None => {
+                    removed.push(i);
+                    continue;
+                }
+            };
+            let (a, b) = (&old[i], &new[j]);
+            let change = InventoryChange {
+                old: i,
+                new: j,
+                upgraded: a.level != b.level,
+                location: if compare_state && a.location != b.location {
+                    Some((a.location, b.location))
+                } else {
+                    None
+                },
+                lock: if compare_state && a.lock != b.lock {
+                    Some((a.lock, b.lock))
+                } else {
+                    None
+                },
+            };
+            if change.upgraded || change.location.is_some() || change.lock.is_some() {
+                changed.push(change);
+            } else {
+                unchanged += 1;
+            }
+        }
+        changed.sort_by_key(|c| c.new);
+
+        let added: Vec<usize> = (0..new.len()).filter(|j| !new_taken[*j]).collect();
+
+        InventoryDiff {
+            old,
+            new,
+            pairs: old_match,
+            added,
+            removed,
+            changed,
+            unchanged,
+        }
+    }
+
+    // Position in the new inventory of the old artifact at `old`, None if it was removed
+    pub fn matched_new(&self, old: usize) -> Option<usize> {
+        self.pairs.get(old).cloned().flatten()
+    }
+
+    pub fn matched_old(&self, new: usize) -> Option<usize> {
+        self.pairs.iter().position(|m| *m == Some(new))
+    }
+
+    pub fn upgraded(&self) -> usize {
+        self.changed.iter().filter(|c| c.upgraded).count()
+    }
+
+    pub fn re_equipped(&self) -> usize {
+        self.changed.iter().filter(|c| c.location.is_some()).count()
+    }
+
+    pub fn re_locked(&self) -> usize {
+        self.changed.iter().filter(|c| c.lock.is_some()).count()
+    }
+
+    pub fn print(&self) {
+        println!(
+            "新增 {}，移除 {}，强化 {}，更换装备 {}，改变锁定 {}，不变 {}",
+            self.added.len(),
+            self.removed.len(),
+            self.upgraded(),
+            self.re_equipped(),
+            self.re_locked(),
+            self.unchanged
+        );
+        for &j in self.added.iter() {
+            println!("+ {}", describe(&self.new[j]));
+        }
+        for &i in self.removed.iter() {
+            println!("- {}", describe(&self.old[i]));
+        }
+        for c in self.changed.iter() {
+            let mut parts: Vec<String> = Vec::new();
+            if c.upgraded {
+                parts.push(format!(
+                    "+{} -> +{}",
+                    self.old[c.old].level, self.new[c.new].level
+                ));
+            }
+            if let Some((from, to)) = c.location {
+                parts.push(format!("{} -> {}", location_name(from), location_name(to)));
+            }
+            if let Some((_, to)) = c.lock {
+                parts.push(String::from(if to { "加锁" } else { "解锁" }));
+            }
+            println!("* {}  {}", describe(&self.new[c.new]), parts.join("，"));
+        }
+    }
+
+    pub fn save(&self, path: String) {
+        let mut file = match File::create(&path) {
+            Err(why) => panic!("couldn't create {}: {}", path, why),
+            Ok(file) => file,
+        };
+        let s = serde_json::to_string(&self).unwrap();
+
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+}
+
+fn describe(art: &InternalArtifact) -> String {
+    let subs: Vec<String> = art
+        .substats()
+        .iter()
+        .map(|s| format!("{} {}", s.key.to_good(), s.value))
+        .collect();
+    format!(
+        "{} {}★+{} {} [{}]",
+        art.display_name(),
+        art.rarity,
+        art.level,
+        art.main_stat.key.to_good(),
+        subs.join(", ")
+    )
+}
+
+fn location_name(c: Option<CharacterKey>) -> String {
+    match c {
+        Some(v) => v.to_good(),
+        None => String::from("未装备"),
+    }
+}
+
+struct DiffChange<'a> {
+    change: &'a InventoryChange,
+}
+
+impl<'a> Serialize for DiffChange<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let c = self.change;
+        let mut root = serializer.serialize_map(Some(5))?;
+        root.serialize_entry("old", &c.old)?;
+        root.serialize_entry("new", &c.new)?;
+        root.serialize_entry("upgraded", &c.upgraded)?;
+        match c.location {
+            Some((from, to)) => root.serialize_entry(
+                "location",
+                &[
+                    from.map_or(String::new(), |v| v.to_good()),
+                    to.map_or(String::new(), |v| v.to_good()),
+                ],
+            )?,
+            None => root.serialize_entry("location", &Value::Null)?,
+        }
+        root.serialize_entry("lock", &c.lock.map(|(from, to)| [from, to]))?;
+        root.end()
+    }
+}
+
+// Positions refer to the artifacts of the two files, in file order
+impl<'a> Serialize for InventoryDiff<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let changed: Vec<DiffChange> = self
+            .changed
+            .iter()
+            .map(|change| DiffChange { change })
+            .collect();
+
+        let mut root = serializer.serialize_map(Some(4))?;
+        root.serialize_entry("added", &self.added)?;
+        root.serialize_entry("removed", &self.removed)?;
+        root.serialize_entry("changed", &changed)?;
+        root.serialize_entry("unchanged", &self.unchanged)?;
+        root.end()
+    }
+}
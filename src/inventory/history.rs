@@ -0,0 +1,335 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+
+use crate::artifact::internal_artifact::InternalArtifact;
+use crate::expo::genmo::GenmoFormat;
+use crate::expo::good::{read_good_artifacts, GoodFormat};
+use crate::expo::mona_uranai::MonaFormat;
+use crate::inventory::diff::InventoryDiff;
+
+pub const HISTORY_VERSION: u64 = 1;
+
+// One stored scan
+#[derive(Debug, Clone)]
+pub struct ScanEntry {
+    pub id: u32,
+    // seconds since the unix epoch
+    pub time: u64,
+    pub artifacts: usize,
+    // grid indices that could not be recognized, to map positions back to the bag
+    pub error_indices: Vec<u32>,
+}
+
+impl Serialize for ScanEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(4))?;
+        root.serialize_entry("id", &self.id)?;
+        root.serialize_entry("time", &self.time)?;
+        root.serialize_entry("artifacts", &self.artifacts)?;
+        root.serialize_entry("errorIndices", &self.error_indices)?;
+        root.end()
+    }
+}
+
+struct HistoryIndex<'a> {
+    scans: &'a [ScanEntry],
+}
+
+impl<'a> Serialize for HistoryIndex<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(2))?;
+        root.serialize_entry("version", &HISTORY_VERSION)?;
+        root.serialize_entry("scans", self.scans)?;
+        root.end()
+    }
+}
+
+// Scans kept in a directory: `index.json` lists them, `scans/<id>.json` holds the artifacts
+// of each in GOOD format
+pub struct HistoryStore {
+    dir: PathBuf,
+    scans: Vec<ScanEntry>,
+}
+
+impl HistoryStore {
+    // Opens the store in `dir`, creating it if needed
+    pub fn open(dir: &Path) -> Result<HistoryStore, String> {
+        if let Err(e) = fs::create_dir_all(dir.join("scans")) {
+            return Err(format!("cannot create {}: {}", dir.display(), e));
+        }
+
+        let index = dir.join("index.json");
+        if !index.exists() {
+            return Ok(HistoryStore {
+                dir: dir.to_path_buf(),
+                scans: Vec::new(),
+            });
+        }
+
+        let s = match fs::read_to_string(&index) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("cannot read {}: {}", index.display(), e)),
+        };
+        let json: Value = match serde_json::from_str(&s) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("cannot parse {}: {}", index.display(), e)),
+        };
+        let version = json["version"].as_u64().unwrap_or(0);
+        if version > HISTORY_VERSION {
+            return Err(format!(
+                "history version {} is newer than supported {}",
+                version, HISTORY_VERSION
+            ));
+        }
+
+        let mut scans: Vec<ScanEntry> = Vec::new();
+        for item in json["scans"].as_array().unwrap_or(&Vec::new()).iter() {
+            let id = match item["id"].as_u64() {
+                Some(v) => v as u32,
+                None => return Err(format!("{}: scan without id", index.display())),
+            };
+            scans.push(ScanEntry {
+                id,
+                time: item["time"].as_u64().unwrap_or(0),
+                artifacts: item["artifacts"].as_u64().unwrap_or(0) as usize,
+                error_indices: item["errorIndices"]
+                    .as_array()
+                    .unwrap_or(&Vec::new())
+                    .iter()
+                    .filter_map(|v| v.as_u64().map(|i| i as u32))
+                    .collect(),
+            });
+        }
+        scans.sort_by_key(|s| (s.time, s.id));
+
+        Ok(HistoryStore {
+            dir: dir.to_path_buf(),
+            scans,
+        })
+    }
+
+    // Oldest first
+    pub fn scans(&self) -> &[ScanEntry] {
+        &self.scans
+    }
+
+    pub fn scan(&self, id: u32) -> Option<&ScanEntry> {
+        self.scans.iter().find(|s| s.id == id)
+    }
+
+    // The inventory as of `time`: the last scan taken at or before it
+    pub fn at(&self, time: u64) -> Option<&ScanEntry> {
+        self.scans.iter().rfind(|s| s.time <= time)
+    }
+
+    fn scan_path(&self, id: u32) -> PathBuf {
+        self.dir.join("scans").join(format!("{}.json", id))
+    }
+
+    // Stores a scan taken now
+    pub fn add(
+        &mut self,
+        artifacts: &[InternalArtifact],
+        error_indices: &[u32],
+    ) -> Result<&ScanEntry, String> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let entry = ScanEntry {
+            id: self.scans.iter().map(|s| s.id).max().unwrap_or(0) + 1,
+            time,
+            artifacts: artifacts.len(),
+            error_indices: error_indices.to_vec(),
+        };
+
+        let path = self.scan_path(entry.id);
+        let s = serde_json::to_string(&GoodFormat::new(artifacts)).unwrap();
+        if let Err(e) = fs::write(&path, s) {
+            return Err(format!("cannot write {}: {}", path.display(), e));
+        }
+        self.scans.push(entry);
+        self.save_index()?;
+
+        Ok(self.scans.last().unwrap())
+    }
+
+    fn save_index(&self) -> Result<(), String> {
+        let path = self.dir.join("index.json");
+        let s = serde_json::to_string(&HistoryIndex { scans: &self.scans }).unwrap();
+
+        // written next to the index first, so an interrupted write keeps the old one
+        let temp = self.dir.join("index.json.tmp");
+        fs::write(&temp, s)
+            .and_then(|_| fs::rename(&temp, &path))
+            .map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+
+    pub fn load(&self, id: u32) -> Result<Vec<InternalArtifact>, String> {
+        if self.scan(id).is_none() {
+            return Err(format!("no scan {}", id));
+        }
+        let path = self.scan_path(id);
+        match fs::read_to_string(&path) {
+            Ok(s) => read_good_artifacts(&s).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) => Err(format!("cannot read {}: {}", path.display(), e)),
+        }
+    }
+
+    // Writes mona.json, genmo.json and good.json of a stored scan to `dir`, as the scan
+    // itself did
+    pub fn export(&self, id: u32, dir: &Path) -> Result<(), String> {
+        let artifacts = self.load(id)?;
+        if let Err(e) = fs::create_dir_all(dir) {
+            return Err(format!("cannot create {}: {}", dir.display(), e));
+        }
+        let path = |name: &str| match dir.join(name).to_str() {
+            Some(v) => Ok(String::from(v)),
+            None => Err(format!("{} is not a valid UTF-8 path", dir.display())),
+        };
+        MonaFormat::new(&artifacts).save(path("mona.json")?);
+        GenmoFormat::new(&artifacts).save(path("genmo.json")?);
+        GoodFormat::new(&artifacts).save(path("good.json")?);
+
+        Ok(())
+    }
+
+    // Where the artifact at `position` of scan `id` is in every scan, None where it was not
+    // there. Followed from scan to scan with the upgrade-tolerant matching of
+    // `InventoryDiff`, so it is still found after being upgraded.
+    pub fn track(&self, id: u32, position: usize) -> Result<Vec<(u32, Option<usize>)>, String> {
+        let start = match self.scans.iter().position(|s| s.id == id) {
+            Some(v) => v,
+            None => return Err(format!("no scan {}", id)),
+        };
+        let mut loaded: Vec<Option<Vec<InternalArtifact>>> = vec![None; self.scans.len()];
+        loaded[start] = Some(self.load(id)?);
+        if position >= loaded[start].as_ref().unwrap().len() {
+            return Err(format!("scan {} has no artifact {}", id, position));
+        }
+
+        let mut positions: Vec<Option<usize>> = vec![None; self.scans.len()];
+        positions[start] = Some(position);
+
+        // forwards, then backwards from the starting scan
+        let forward: Vec<(usize, usize)> =
+            (start + 1..self.scans.len()).map(|i| (i - 1, i)).collect();
+        let backward: Vec<(usize, usize)> = (0..start).rev().map(|i| (i + 1, i)).collect();
+        for (from, to) in forward.into_iter().chain(backward) {
+            let p = match positions[from] {
+                Some(v) => v,
+                None => continue,
+            };
+            if loaded[to].is_none() {
+                loaded[to] = Some(self.load(self.scans[to].id)?);
+            }
+            let (a, b) = (loaded[from].as_ref().unwrap(), loaded[to].as_ref().unwrap());
+            positions[to] = if from < to {
+                let diff = InventoryDiff::new(a, b, true);
+                diff.matched_new(p)
+            } else {
+                let diff = InventoryDiff::new(b, a, true);
+                diff.matched_old(p)
+            };
+        }
+
+        Ok(self.scans.iter().map(|s| s.id).zip(positions).collect())
+    }
+}
+
+// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (
+        if m <= 2 {
+            yoe + era * 400 + 1
+        } else {
+            yoe + era * 400
+        },
+        m,
+        d,
+    )
+}
+
+// e.g. "2021-11-05 13:20:00", in UTC
+pub fn format_time(time: u64) -> String {
+    let (y, m, d) = civil_from_days((time / 86400) as i64);
+    let s = time % 86400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        y,
+        m,
+        d,
+        s / 3600,
+        s / 60 % 60,
+        s % 60
+    )
+}
+
+// "YYYY-MM-DD", "YYYY-MM-DD HH:MM" or "YYYY-MM-DD HH:MM:SS" in UTC, a date alone meaning the
+// end of that day
+pub fn parse_time(s: &str) -> Result<u64, String> {
+    let error = || format!("cannot parse time {}, expected YYYY-MM-DD [HH:MM[:SS]]", s);
+
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    if parts.is_empty() || parts.len() > 2 {
+        return Err(error());
+    }
+    let date: Vec<i64> = parts[0]
+        .split('-')
+        .map(|v| v.parse::<i64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| error())?;
+    if date.len() != 3 || date[1] < 1 || date[1] > 12 || date[2] < 1 || date[2] > 31 {
+        return Err(error());
+    }
+    let clock: Vec<i64> = match parts.get(1) {
+        Some(t) => t
+            .split(':')
+            .map(|v| v.parse::<i64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| error())?,
+        None => vec![23, 59, 59],
+    };
+    if clock.len() < 2 || clock.len() > 3 || clock[0] > 23 || clock[1] > 59 {
+        return Err(error());
+    }
+
+    let days = days_from_civil(date[0], date[1], date[2]);
+    // e.g. 2021-02-31 comes back as 2021-03-03
+    if civil_from_days(days) != (date[0], date[1], date[2]) {
+        return Err(error());
+    }
+    let seconds = clock[0] * 3600 + clock[1] * 60 + clock.get(2).cloned().unwrap_or(0);
+    if days < 0 {
+        return Err(error());
+    }
+
+    Ok((days * 86400 + seconds) as u64)
+}
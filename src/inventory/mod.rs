@@ -0,0 +1,2 @@
+pub mod diff;
+pub mod history;
\ No newline at end of file
@@ -1,7 +1,13 @@
 pub mod common;
+#[cfg(windows)]
 pub mod capture;
 pub mod inference;
 pub mod info;
 pub mod scanner;
 pub mod artifact;
-pub mod expo;
\ No newline at end of file
+pub mod expo;
+pub mod bench;
+pub mod inventory;
+pub mod lock;
+pub mod weapon;
+pub mod character;
\ No newline at end of file
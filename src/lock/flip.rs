@@ -0,0 +1,330 @@
+use log::{info, warn};
+
+use crate::artifact::internal_artifact::{ArtifactSetKey, ArtifactSlotKey, InternalArtifact};
+use crate::artifact::lang::Language;
+use crate::lock::lock_file::{content_hash, fingerprint, LockEntry};
+use crate::lock::planner::LockAction;
+use crate::scanner::report::grid_indices;
+
+// An item in the grid whose lock is to be set
+#[derive(Debug, Clone)]
+pub struct LockTarget {
+    // position in the in-game grid
+    pub index: u32,
+    // the state wanted, None to toggle
+    pub action: Option<LockAction>,
+    // zh-cn title expected there, checked before clicking when known
+    pub title: Option<String>,
+    // lock state in the scan the lock file was planned on
+    pub current: Option<bool>,
+    // of the artifact planned on, see `YasScanner::check_lock_targets`
+    pub fingerprint: Option<String>,
+}
+
+impl LockTarget {
+    // None for entries found by identity only
+    pub fn from_entry(entry: &LockEntry) -> Option<LockTarget> {
+        Some(LockTarget {
+            index: entry.index?,
+            action: entry.action,
+            title: None,
+            current: None,
+            fingerprint: entry.fingerprint.clone(),
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        match self.title {
+            Some(ref title) => format!("#{} {}", self.index, title),
+            None => format!("#{}", self.index),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlipOutcome {
+    Flipped { attempts: u32 },
+    // already in the wanted state, nothing was clicked
+    AlreadySet,
+    // the lock icon could not be read, nothing was clicked
+    UnknownState,
+    // another artifact was selected, with the title read
+    TitleMismatch(String),
+    // the lock state stayed the same after every attempt
+    NotChanged,
+    // paging failed or the scan was stopped before getting there
+    NotReached,
+    // no artifact in the bag matches, see `YasScanner::lock_by_identity`
+    NotFound,
+    // --dry-run, reached and checked but the lock was not clicked
+    WouldFlip,
+}
+
+impl FlipOutcome {
+    pub fn is_resolved(&self) -> bool {
+        matches!(
+            self,
+            FlipOutcome::Flipped { .. } | FlipOutcome::AlreadySet | FlipOutcome::WouldFlip
+        )
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            FlipOutcome::Flipped { attempts } => format!("已切换（第{}次）", attempts),
+            FlipOutcome::AlreadySet => String::from("已是目标状态"),
+            FlipOutcome::UnknownState => String::from("无法识别锁定状态"),
+            FlipOutcome::TitleMismatch(title) => format!("名称不符：{}", title),
+            FlipOutcome::NotChanged => String::from("多次点击后锁定状态未改变"),
+            FlipOutcome::NotReached => String::from("未到达"),
+            FlipOutcome::NotFound => String::from("背包中未找到"),
+            FlipOutcome::WouldFlip => String::from("需要切换（未点击）"),
+        }
+    }
+}
+
+pub struct FlipReport {
+    // with what was to be set, e.g. "#12 角斗士的留恋"
+    pub outcomes: Vec<(String, FlipOutcome)>,
+}
+
+impl Default for FlipReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlipReport {
+    pub fn new() -> FlipReport {
+        FlipReport {
+            outcomes: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, target: String, outcome: FlipOutcome) {
+        self.outcomes.push((target, outcome));
+    }
+
+    pub fn unresolved(&self) -> Vec<&(String, FlipOutcome)> {
+        self.outcomes
+            .iter()
+            .filter(|(_, outcome)| !outcome.is_resolved())
+            .collect()
+    }
+
+    pub fn print_summary(&self) {
+        let unresolved = self.unresolved();
+        info!(
+            "加解锁完成：成功 {}，失败 {}",
+            self.outcomes.len() - unresolved.len(),
+            unresolved.len()
+        );
+        let retried = self
+            .outcomes
+            .iter()
+            .filter(|(_, outcome)| match outcome {
+                FlipOutcome::Flipped { attempts } => *attempts > 1,
+                _ => false,
+            })
+            .count();
+        if retried > 0 {
+            info!("其中 {} 件重试后成功", retried);
+        }
+        let already = self
+            .outcomes
+            .iter()
+            .filter(|(_, outcome)| *outcome == FlipOutcome::AlreadySet)
+            .count();
+        if already > 0 {
+            info!("其中 {} 件已是目标状态，未点击", already);
+        }
+        for (target, outcome) in unresolved {
+            warn!("{}: {}", target, outcome.describe());
+        }
+    }
+}
+
+// Whether the title read from the panel of a `lang` client is the piece named `title` in
+// zh-cn, allowing for small recognition errors
+pub fn title_matches(raw: &str, title: &str, lang: Language) -> bool {
+    let raw = raw.trim();
+    if raw == title {
+        return true;
+    }
+    let name = match lang.resolve_piece(raw) {
+        Some(m) if !m.ambiguous => m.name,
+        _ => return false,
+    };
+    let piece = |lang: Language, name: &str| {
+        (
+            ArtifactSetKey::from_lang(lang, name),
+            ArtifactSlotKey::from_lang(lang, name),
+        )
+    };
+    match piece(lang, name) {
+        (Some(set), Some(slot)) => piece(Language::ZhCn, title) == (Some(set), Some(slot)),
+        _ => false,
+    }
+}
+
+// Targets for the entries of a lock file with the titles from the scan it was planned on,
+// given its export and the error indices of its report. Entries with a fingerprint only take
+// the title of an artifact that still has it.
+pub fn targets_with_titles(
+    entries: &[LockEntry],
+    artifacts: &[InternalArtifact],
+    error_indices: &[u32],
+) -> Vec<LockTarget> {
+    let grid = grid_indices(artifacts.len(), error_indices);
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let target = LockTarget::from_entry(entry)?;
+            let art = grid
+                .iter()
+                .position(|&i| i == target.index)
+                .map(|p| &artifacts[p])
+                .filter(|art| match entry.fingerprint {
+                    Some(ref v) => *v == fingerprint(art),
+                    None => true,
+                });
+            Some(LockTarget {
+                title: art.map(|art| art.display_name()),
+                current: art.map(|art| art.lock),
+                ..target
+            })
+        })
+        .collect()
+}
+
+// Up to `n` of the targets with a fingerprint or a title from the last scan, spread over the
+// whole grid, to read back before anything is clicked
+pub fn verify_sample(targets: &[LockTarget], n: usize) -> Vec<&LockTarget> {
+    let known: Vec<&LockTarget> = targets
+        .iter()
+        .filter(|t| t.fingerprint.is_some() || t.title.is_some())
+        .collect();
+    if known.len() <= n {
+        return known;
+    }
+
+    (0..n)
+        .map(|i| known[i * (known.len() - 1) / (n - 1).max(1)])
+        .collect()
+}
+
+// `content_hash` of the artifacts a scan has at the indices of `entries`, given its export
+// and the error indices of its report, to compare with the one stamped in the lock file
+pub fn scan_content_hash(
+    entries: &[LockEntry],
+    artifacts: &[InternalArtifact],
+    error_indices: &[u32],
+) -> String {
+    let grid = grid_indices(artifacts.len(), error_indices);
+    let fingerprints: Vec<Option<String>> = entries
+        .iter()
+        .filter_map(|entry| entry.index)
+        .map(|index| {
+            grid.iter()
+                .position(|&i| i == index)
+                .map(|p| fingerprint(&artifacts[p]))
+        })
+        .collect();
+
+    content_hash(&fingerprints)
+}
+
+// Where an item is reached when the grid is paged the way `YasScanner::flip_lock` does, a
+// page of `rows` rows at a time with the last page scrolled only as far as rows are left
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridPosition {
+    pub page: u32,
+    // on screen
+    pub row: u32,
+    pub col: u32,
+    // rows scrolled from the top to get there
+    pub scrolled_rows: u32,
+}
+
+pub fn grid_position(index: u32, count: u32, rows: u32, cols: u32) -> GridPosition {
+    let total_row = count.div_ceil(cols);
+    let row = index / cols;
+    let page = row / rows;
+
+    let mut scrolled_rows = 0;
+    let mut start_row = 0;
+    for p in 1..=page {
+        let scroll_row = total_row.saturating_sub(p * rows).min(rows);
+        scrolled_rows += scroll_row;
+        start_row = rows - scroll_row;
+    }
+
+    GridPosition {
+        page,
+        row: row - page * rows + start_row,
+        col: index % cols,
+        scrolled_rows,
+    }
+}
+
+fn lock_text(lock: Option<bool>) -> &'static str {
+    match lock {
+        Some(true) => "已锁定",
+        Some(false) => "未锁定",
+        None => "未知",
+    }
+}
+
+// What flipping `targets` will do, one line each, before anything is clicked. `count` is the
+// number of artifacts in the bag, only the last page depends on it.
+pub fn preview_text(targets: &[LockTarget], count: u32, rows: u32, cols: u32) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    lines.push(format!(
+        "共 {} 个目标，按背包 {} 件圣遗物、每页 {} 行 {} 列计算",
+        targets.len(),
+        count,
+        rows,
+        cols
+    ));
+    for target in targets.iter() {
+        let pos = grid_position(target.index, count, rows, cols);
+        let wanted = match target.action {
+            Some(action) => Some(action == LockAction::Lock),
+            None => target.current.map(|v| !v),
+        };
+        let change = match (target.current, wanted) {
+            (Some(a), Some(b)) if a == b => String::from("无需点击"),
+            (_, None) => String::from("切换"),
+            (current, wanted) => format!("{} -> {}", lock_text(current), lock_text(wanted)),
+        };
+        lines.push(format!(
+            "{}  第{}页 第{}行第{}列  滚动{}行  {}",
+            target.describe(),
+            pos.page + 1,
+            pos.row + 1,
+            pos.col + 1,
+            pos.scrolled_rows,
+            change
+        ));
+    }
+
+    lines.join("\n")
+}
+
+// Like `preview_text` for `YasScanner::lock_by_identity`, where positions are only known once
+// the bag is read
+pub fn identity_preview_text(entries: &[LockEntry]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    lines.push(format!(
+        "共 {} 个目标，按指纹或内容在整个背包中查找",
+        entries.len()
+    ));
+    for entry in entries.iter() {
+        let change = match entry.action {
+            Some(action) => action.to_str(),
+            None => "切换",
+        };
+        lines.push(format!("{}  {}", entry.describe(), change));
+    }
+
+    lines.join("\n")
+}
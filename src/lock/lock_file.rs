@@ -0,0 +1,426 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::prelude::*;
+use std::str::FromStr;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+
+use crate::artifact::internal_artifact::{
+    ArtifactSetKey, ArtifactSlotKey, ArtifactStat, ArtifactStatKey, InternalArtifact,
+};
+use crate::lock::planner::LockAction;
+
+pub const LOCK_FILE_VERSION: u64 = 1;
+
+// Artifact described by its content in GOOD keys, unset fields match anything
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactPattern {
+    pub set_key: Option<ArtifactSetKey>,
+    pub slot_key: Option<ArtifactSlotKey>,
+    pub rarity: Option<u32>,
+    pub level: Option<u32>,
+    pub main_stat_key: Option<ArtifactStatKey>,
+    // each must be among the substats, others may be there too
+    pub substats: Vec<ArtifactStat>,
+}
+
+// One artifact of a lock file, found by its position in the grid or by what it is
+#[derive(Debug, Clone)]
+pub struct LockEntry {
+    // position in the in-game grid
+    pub index: Option<u32>,
+    // the state wanted, None for the bare indices of old lock files, which are toggled
+    pub action: Option<LockAction>,
+    // `fingerprint` of the artifact planned on
+    pub fingerprint: Option<String>,
+    pub artifact: Option<ArtifactPattern>,
+}
+
+// The scan a lock file was planned on, to tell whether the bag changed since
+#[derive(Debug, Clone)]
+pub struct LockSource {
+    // artifacts in the bag as shown above the grid, when the scan report has it
+    pub count: Option<u32>,
+    // `content_hash` of the artifacts at the targets
+    pub hash: String,
+}
+
+pub struct LockFile {
+    // ordered by grid index, entries without one last
+    pub entries: Vec<LockEntry>,
+    pub source: Option<LockSource>,
+}
+
+fn stat_text(stat: &ArtifactStat) -> String {
+    format!(
+        "{}={}",
+        stat.key.to_good(),
+        (stat.value * 10.0).round() as i64
+    )
+}
+
+// Identifies an artifact across scans as long as it is not upgraded: FNV-1a of its set, slot,
+// rarity, level, main stat and substats. The main stat value is left out, it follows from the
+// rest and is only estimated when read from GOOD, as are the lock state and who wears it,
+// both change without the artifact changing.
+pub fn fingerprint(art: &InternalArtifact) -> String {
+    let mut parts = vec![
+        String::from(art.set_key.0),
+        format!("{:?}", art.slot_key),
+        art.rarity.to_string(),
+        art.level.to_string(),
+        art.main_stat.key.to_good(),
+    ];
+    parts.extend(art.substats().into_iter().map(stat_text));
+
+    fnv1a(&parts.join("|"))
+}
+
+fn fnv1a(s: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in s.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+// One hash for the fingerprints of several artifacts in order, None for a position without
+// an artifact
+pub fn content_hash(fingerprints: &[Option<String>]) -> String {
+    let parts: Vec<&str> = fingerprints
+        .iter()
+        .map(|f| f.as_deref().unwrap_or("-"))
+        .collect();
+    fnv1a(&parts.join(","))
+}
+
+impl ArtifactPattern {
+    pub fn matches(&self, art: &InternalArtifact) -> bool {
+        let same = |a: &ArtifactStat, b: &ArtifactStat| {
+            a.key == b.key && (a.value * 10.0).round() == (b.value * 10.0).round()
+        };
+
+        self.set_key.is_none_or(|k| k == art.set_key)
+            && self.slot_key.as_ref().is_none_or(|k| *k == art.slot_key)
+            && self.rarity.is_none_or(|r| r == art.rarity)
+            && self.level.is_none_or(|l| l == art.level)
+            && self
+                .main_stat_key
+                .as_ref()
+                .is_none_or(|k| *k == art.main_stat.key)
+            && self
+                .substats
+                .iter()
+                .all(|s| art.substats().into_iter().any(|t| same(s, t)))
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(k) = self.set_key {
+            parts.push(String::from(k.0));
+        }
+        if let Some(ref k) = self.slot_key {
+            parts.push(format!("{:?}", k));
+        }
+        if let Some(r) = self.rarity {
+            parts.push(format!("{}★", r));
+        }
+        if let Some(l) = self.level {
+            parts.push(format!("+{}", l));
+        }
+        if let Some(ref k) = self.main_stat_key {
+            parts.push(k.to_good());
+        }
+        for s in self.substats.iter() {
+            parts.push(format!("{}:{}", s.key.to_good(), s.value));
+        }
+        parts.join(" ")
+    }
+}
+
+impl LockEntry {
+    // Whether this is the artifact `art` of the given fingerprint, by fingerprint and content
+    // as far as given. An entry with neither matches nothing.
+    pub fn matches(&self, art: &InternalArtifact, art_fingerprint: &str) -> bool {
+        if self.fingerprint.is_none() && self.artifact.is_none() {
+            return false;
+        }
+        self.fingerprint
+            .as_ref()
+            .is_none_or(|f| f == art_fingerprint)
+            && self.artifact.as_ref().is_none_or(|p| p.matches(art))
+    }
+
+    // For logs, e.g. "#12 GladiatorsFinale Flower" or "fingerprint 3f9c0b6a1d2e4f58"
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(index) = self.index {
+            parts.push(format!("#{}", index));
+        }
+        match (&self.artifact, &self.fingerprint) {
+            (Some(p), _) => parts.push(p.describe()),
+            (None, Some(f)) => parts.push(format!("fingerprint {}", f)),
+            (None, None) => (),
+        }
+        parts.join(" ")
+    }
+}
+
+struct GoodSubstat<'a> {
+    stat: &'a ArtifactStat,
+}
+
+impl<'a> Serialize for GoodSubstat<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(2))?;
+        root.serialize_entry("key", &self.stat.key.to_good())?;
+        root.serialize_entry("value", &self.stat.value)?;
+        root.end()
+    }
+}
+
+impl Serialize for ArtifactPattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(None)?;
+        if let Some(k) = self.set_key {
+            root.serialize_entry("setKey", &k.to_good())?;
+        }
+        if let Some(ref k) = self.slot_key {
+            root.serialize_entry("slotKey", &k.to_good())?;
+        }
+        if let Some(r) = self.rarity {
+            root.serialize_entry("rarity", &r)?;
+        }
+        if let Some(l) = self.level {
+            root.serialize_entry("level", &l)?;
+        }
+        if let Some(ref k) = self.main_stat_key {
+            root.serialize_entry("mainStatKey", &k.to_good())?;
+        }
+        if !self.substats.is_empty() {
+            let substats: Vec<GoodSubstat> = self
+                .substats
+                .iter()
+                .map(|stat| GoodSubstat { stat })
+                .collect();
+            root.serialize_entry("substats", &substats)?;
+        }
+        root.end()
+    }
+}
+
+impl Serialize for LockEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(None)?;
+        if let Some(index) = self.index {
+            root.serialize_entry("index", &index)?;
+        }
+        if let Some(action) = self.action {
+            root.serialize_entry("action", action.to_key())?;
+        }
+        if let Some(ref fingerprint) = self.fingerprint {
+            root.serialize_entry("fingerprint", fingerprint)?;
+        }
+        if let Some(ref artifact) = self.artifact {
+            root.serialize_entry("artifact", artifact)?;
+        }
+        root.end()
+    }
+}
+
+impl Serialize for LockSource {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(None)?;
+        if let Some(count) = self.count {
+            root.serialize_entry("count", &count)?;
+        }
+        root.serialize_entry("hash", &self.hash)?;
+        root.end()
+    }
+}
+
+impl Serialize for LockFile {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(None)?;
+        root.serialize_entry("version", &LOCK_FILE_VERSION)?;
+        if let Some(ref source) = self.source {
+            root.serialize_entry("source", source)?;
+        }
+        root.serialize_entry("targets", &self.entries)?;
+        root.end()
+    }
+}
+
+// The fields of a GOOD artifact, all optional
+fn parse_pattern(value: &Value) -> Result<ArtifactPattern, String> {
+    let stat_key = |s: &str| match ArtifactStatKey::from_good(s) {
+        Some(v) => Ok(v),
+        None => Err(format!("unknown stat {}", s)),
+    };
+
+    let mut substats: Vec<ArtifactStat> = Vec::new();
+    for s in value["substats"].as_array().unwrap_or(&Vec::new()).iter() {
+        let key = stat_key(s["key"].as_str().unwrap_or(""))?;
+        match s["value"].as_f64() {
+            Some(v) => substats.push(ArtifactStat { key, value: v }),
+            None => return Err(format!("substat {} without value", s)),
+        }
+    }
+
+    Ok(ArtifactPattern {
+        set_key: match value["setKey"].as_str() {
+            Some(s) => match ArtifactSetKey::from_good(s) {
+                Some(v) => Some(v),
+                None => return Err(format!("unknown set {}", s)),
+            },
+            None => None,
+        },
+        slot_key: match value["slotKey"].as_str() {
+            Some(s) => match ArtifactSlotKey::from_good(s) {
+                Some(v) => Some(v),
+                None => return Err(format!("unknown slot {}", s)),
+            },
+            None => None,
+        },
+        rarity: value["rarity"].as_u64().map(|v| v as u32),
+        level: value["level"].as_u64().map(|v| v as u32),
+        main_stat_key: match value["mainStatKey"].as_str() {
+            Some(s) => Some(stat_key(s)?),
+            None => None,
+        },
+        substats,
+    })
+}
+
+fn parse_entry(value: &Value) -> Result<LockEntry, String> {
+    let index = value["index"].as_u64().map(|v| v as u32);
+    let action = match value["action"].as_str() {
+        Some(s) => match LockAction::from_key(s) {
+            Some(v) => Some(v),
+            None => return Err(format!("unknown action {}", s)),
+        },
+        // toggled, as the bare indices
+        None => None,
+    };
+
+    let fingerprint = value["fingerprint"].as_str().map(String::from);
+    let artifact = match value.get("artifact") {
+        Some(v) => Some(parse_pattern(v)?),
+        None => None,
+    };
+    if artifact.as_ref().is_some_and(|p| p.describe().is_empty()) {
+        // would match every artifact
+        return Err(String::from("empty artifact"));
+    }
+    if index.is_none() && fingerprint.is_none() && artifact.is_none() {
+        return Err(String::from("needs index, fingerprint or artifact"));
+    }
+
+    Ok(LockEntry {
+        index,
+        action,
+        fingerprint,
+        artifact,
+    })
+}
+
+impl FromStr for LockFile {
+    type Err = String;
+
+    // {"version": 1, "source": {"count": 1234, "hash": "..."},
+    // "targets": [{"index": 12, "action": "lock", "fingerprint": "..."}]}, or
+    // the bare array of indices to toggle written by older versions, e.g. [3, 12]. Instead of
+    // the index a target may have the fingerprint or an "artifact" with the fields of a GOOD
+    // artifact, see `YasScanner::lock_by_identity`.
+    fn from_str(s: &str) -> Result<LockFile, String> {
+        let json: Value = match serde_json::from_str(s) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("cannot parse lock file: {}", e)),
+        };
+
+        let mut entries: Vec<LockEntry> = Vec::new();
+        let mut source: Option<LockSource> = None;
+        if let Some(items) = json.as_array() {
+            for item in items.iter() {
+                match item.as_u64() {
+                    Some(v) => entries.push(LockEntry {
+                        index: Some(v as u32),
+                        action: None,
+                        fingerprint: None,
+                        artifact: None,
+                    }),
+                    None => return Err(format!("not an index: {}", item)),
+                }
+            }
+        } else {
+            let version = match json["version"].as_u64() {
+                Some(v) => v,
+                None => return Err(String::from("missing version")),
+            };
+            if version > LOCK_FILE_VERSION {
+                return Err(format!(
+                    "lock file version {} is newer than supported {}",
+                    version, LOCK_FILE_VERSION
+                ));
+            }
+            if let Some(hash) = json["source"]["hash"].as_str() {
+                source = Some(LockSource {
+                    count: json["source"]["count"].as_u64().map(|v| v as u32),
+                    hash: String::from(hash),
+                });
+            }
+            for (i, item) in json["targets"]
+                .as_array()
+                .unwrap_or(&Vec::new())
+                .iter()
+                .enumerate()
+            {
+                match parse_entry(item) {
+                    Ok(v) => entries.push(v),
+                    Err(e) => return Err(format!("targets[{}]: {}", i, e)),
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for index in entries.iter().filter_map(|e| e.index) {
+            if !seen.insert(index) {
+                return Err(format!("index {} listed twice", index));
+            }
+        }
+        entries.sort_by_key(|e| e.index.unwrap_or(u32::MAX));
+
+        Ok(LockFile { entries, source })
+    }
+}
+
+impl LockFile {
+    pub fn save(&self, path: String) {
+        let mut file = match File::create(&path) {
+            Err(why) => panic!("couldn't create {}: {}", path, why),
+            Ok(file) => file,
+        };
+        let s = serde_json::to_string(&self).unwrap();
+
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+}
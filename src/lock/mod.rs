@@ -0,0 +1,3 @@
+pub mod planner;
+pub mod flip;
+pub mod lock_file;
\ No newline at end of file
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+
+use serde_json::Value;
+
+use crate::artifact::filter::Filter;
+use crate::artifact::internal_artifact::{
+    ArtifactSetKey, ArtifactSlotKey, ArtifactStatKey, InternalArtifact,
+};
+use crate::artifact::score::{crit_value, roll_value, ScoreMetric, ScoreProfile};
+use crate::lock::lock_file::{content_hash, fingerprint, LockEntry, LockFile, LockSource};
+
+pub const LOCK_RULES_VERSION: u64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LockAction {
+    Lock,
+    Unlock,
+}
+
+impl LockAction {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            LockAction::Lock => "加锁",
+            LockAction::Unlock => "解锁",
+        }
+    }
+
+    // as written in rule and lock files
+    pub fn to_key(&self) -> &'static str {
+        match self {
+            LockAction::Lock => "lock",
+            LockAction::Unlock => "unlock",
+        }
+    }
+
+    pub fn from_key(s: &str) -> Option<LockAction> {
+        match s {
+            "lock" => Some(LockAction::Lock),
+            "unlock" => Some(LockAction::Unlock),
+            _ => None,
+        }
+    }
+}
+
+// Which artifacts a rule applies to, unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct Condition {
+    pub min_rarity: Option<u32>,
+    pub max_rarity: Option<u32>,
+    pub min_level: Option<u32>,
+    pub max_level: Option<u32>,
+    pub sets: Vec<ArtifactSetKey>,
+    pub slots: Vec<ArtifactSlotKey>,
+    pub main_stats: Vec<ArtifactStatKey>,
+    pub equipped: Option<bool>,
+    pub min_crit_value: Option<f64>,
+    pub max_crit_value: Option<f64>,
+    pub min_roll_value: Option<f64>,
+    pub max_roll_value: Option<f64>,
+    // filter expression, see `Filter`
+    pub filter: Option<Filter>,
+}
+
+impl Condition {
+    pub fn matches(&self, art: &InternalArtifact) -> bool {
+        let in_range = |v: f64, min: Option<f64>, max: Option<f64>| {
+            min.is_none_or(|m| v >= m) && max.is_none_or(|m| v <= m)
+        };
+
+        self.min_rarity.is_none_or(|v| art.rarity >= v)
+            && self.max_rarity.is_none_or(|v| art.rarity <= v)
+            && self.min_level.is_none_or(|v| art.level >= v)
+            && self.max_level.is_none_or(|v| art.level <= v)
+            && (self.sets.is_empty() || self.sets.contains(&art.set_key))
+            && (self.slots.is_empty() || self.slots.contains(&art.slot_key))
+            && (self.main_stats.is_empty() || self.main_stats.contains(&art.main_stat.key))
+            && self.equipped.is_none_or(|v| art.location.is_some() == v)
+            && in_range(crit_value(art), self.min_crit_value, self.max_crit_value)
+            && in_range(roll_value(art), self.min_roll_value, self.max_roll_value)
+            && self.filter.as_ref().is_none_or(|f| f.matches(art))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupKey {
+    Set,
+    Slot,
+    MainStat,
+}
+
+#[derive(Debug, Clone)]
+pub enum RuleKind {
+    Set(LockAction),
+    // lock the best `n` of every group
+    KeepTop {
+        n: usize,
+        group_by: Vec<GroupKey>,
+        by: ScoreMetric,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct LockRule {
+    // "name" from the rules file, or the rule itself
+    pub description: String,
+    pub kind: RuleKind,
+    pub condition: Condition,
+}
+
+fn parse_list<T>(
+    value: &Value,
+    what: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<Vec<T>, String> {
+    let mut list: Vec<T> = Vec::new();
+    for item in value.as_array().unwrap_or(&Vec::new()).iter() {
+        let s = item.as_str().unwrap_or("");
+        match parse(s) {
+            Some(v) => list.push(v),
+            None => return Err(format!("unknown {} {}", what, item)),
+        }
+    }
+
+    Ok(list)
+}
+
+// `when` is either a filter expression or an object of the fields of `Condition`
+fn parse_condition(value: &Value) -> Result<Condition, String> {
+    if let Some(s) = value.as_str() {
+        return Ok(Condition {
+            filter: Some(Filter::parse(s)?),
+            ..Condition::default()
+        });
+    }
+
+    let u32_field = |name: &str| value[name].as_u64().map(|v| v as u32);
+    let rarity = u32_field("rarity");
+
+    Ok(Condition {
+        min_rarity: u32_field("min_rarity").or(rarity),
+        max_rarity: u32_field("max_rarity").or(rarity),
+        min_level: u32_field("min_level"),
+        max_level: u32_field("max_level"),
+        sets: parse_list(&value["sets"], "set", ArtifactSetKey::from_good)?,
+        slots: parse_list(&value["slots"], "slot", ArtifactSlotKey::from_good)?,
+        main_stats: parse_list(&value["main_stats"], "stat", ArtifactStatKey::from_good)?,
+        equipped: value["equipped"].as_bool(),
+        min_crit_value: value["min_crit_value"].as_f64(),
+        max_crit_value: value["max_crit_value"].as_f64(),
+        min_roll_value: value["min_roll_value"].as_f64(),
+        max_roll_value: value["max_roll_value"].as_f64(),
+        filter: match value["filter"].as_str() {
+            Some(s) => Some(Filter::parse(s)?),
+            None => None,
+        },
+    })
+}
+
+fn parse_rule(value: &Value, profiles: &[ScoreProfile]) -> Result<LockRule, String> {
+    let kind = match value["action"].as_str().unwrap_or("") {
+        "lock" => RuleKind::Set(LockAction::Lock),
+        "unlock" => RuleKind::Set(LockAction::Unlock),
+        "keep_top" => {
+            let n = match value["n"].as_u64() {
+                Some(v) => v as usize,
+                None => return Err(String::from("keep_top without n")),
+            };
+            let group_by = parse_list(&value["group_by"], "group", |s| match s {
+                "set" => Some(GroupKey::Set),
+                "slot" => Some(GroupKey::Slot),
+                "main_stat" => Some(GroupKey::MainStat),
+                _ => None,
+            })?;
+            let name = value["by"].as_str().unwrap_or("crit_value");
+            let by = match ScoreMetric::from_name(name, profiles) {
+                Some(v) => v,
+                None => return Err(format!("unknown score profile {}", name)),
+            };
+            RuleKind::KeepTop { n, group_by, by }
+        }
+        other => return Err(format!("unknown action {}", other)),
+    };
+
+    Ok(LockRule {
+        description: match value["name"].as_str() {
+            Some(s) => String::from(s),
+            None => value.to_string(),
+        },
+        kind,
+        condition: parse_condition(&value["when"])?,
+    })
+}
+
+// {"version": 1, "rules": [{"action": "lock", "when": {"rarity": 5, "min_crit_value": 25}},
+// {"action": "keep_top", "n": 2, "group_by": ["set", "slot", "main_stat"], "by": "crit_value"}]}
+// `profiles` are the score profiles a keep_top rule may rank by
+pub fn lock_rules_from_str(s: &str, profiles: &[ScoreProfile]) -> Result<Vec<LockRule>, String> {
+    let json: Value = match serde_json::from_str(s) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("cannot parse lock rules: {}", e)),
+    };
+
+    let version = match json["version"].as_u64() {
+        Some(v) => v,
+        None => return Err(String::from("missing version")),
+    };
+    if version > LOCK_RULES_VERSION {
+        return Err(format!(
+            "lock rule version {} is newer than supported {}",
+            version, LOCK_RULES_VERSION
+        ));
+    }
+
+    let mut rules: Vec<LockRule> = Vec::new();
+    for (i, item) in json["rules"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .enumerate()
+    {
+        match parse_rule(item, profiles) {
+            Ok(v) => rules.push(v),
+            Err(e) => return Err(format!("rules[{}]: {}", i, e)),
+        }
+    }
+
+    Ok(rules)
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedChange {
+    // position in the scan results
+    pub position: usize,
+    // position in the in-game grid, what lock.json lists
+    pub index: u32,
+    pub action: LockAction,
+    pub rule: usize,
+}
+
+pub struct LockPlan {
+    // ordered by grid index
+    pub changes: Vec<PlannedChange>,
+    // artifacts some rule decided on that are already in the wanted state
+    pub satisfied: usize,
+    // artifacts no rule applies to, left as they are
+    pub untouched: usize,
+}
+
+fn group_of(art: &InternalArtifact, group_by: &[GroupKey]) -> Vec<String> {
+    group_by
+        .iter()
+        .map(|g| match g {
+            GroupKey::Set => String::from(art.set_key.0),
+            GroupKey::Slot => format!("{:?}", art.slot_key),
+            GroupKey::MainStat => format!("{:?}", art.main_stat.key),
+        })
+        .collect()
+}
+
+// The first rule deciding on an artifact wins. Only artifacts whose lock state differs from
+// the decision end up in the plan.
+pub fn plan_locks(
+    artifacts: &[InternalArtifact],
+    grid_indices: &[u32],
+    rules: &[LockRule],
+) -> LockPlan {
+    let mut decisions: Vec<Option<(LockAction, usize)>> = vec![None; artifacts.len()];
+
+    for (r, rule) in rules.iter().enumerate() {
+        match &rule.kind {
+            RuleKind::Set(action) => {
+                for (i, art) in artifacts.iter().enumerate() {
+                    if decisions[i].is_none() && rule.condition.matches(art) {
+                        decisions[i] = Some((*action, r));
+                    }
+                }
+            }
+            RuleKind::KeepTop { n, group_by, by } => {
+                // artifacts decided by earlier rules still take their place in the ranking
+                let mut groups: HashMap<Vec<String>, Vec<(usize, f64)>> = HashMap::new();
+                for (i, art) in artifacts.iter().enumerate() {
+                    if rule.condition.matches(art) {
+                        groups
+                            .entry(group_of(art, group_by))
+                            .or_default()
+                            .push((i, by.score(art)));
+                    }
+                }
+                for (_, mut members) in groups {
+                    members.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+                    for &(i, _) in members.iter().take(*n) {
+                        if decisions[i].is_none() {
+                            decisions[i] = Some((LockAction::Lock, r));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut changes: Vec<PlannedChange> = Vec::new();
+    let mut satisfied = 0;
+    let mut untouched = 0;
+    for (i, decision) in decisions.iter().enumerate() {
+        let (action, rule) = match decision {
+            Some(v) => *v,
+            None => {
+                untouched += 1;
+                continue;
+            }
+        };
+        if artifacts[i].lock == (action == LockAction::Lock) {
+            satisfied += 1;
+            continue;
+        }
+        changes.push(PlannedChange {
+            position: i,
+            index: grid_indices[i],
+            action,
+            rule,
+        });
+    }
+    changes.sort_by_key(|c| c.index);
+
+    LockPlan {
+        changes,
+        satisfied,
+        untouched,
+    }
+}
+
+impl LockPlan {
+    pub fn count(&self, action: LockAction) -> usize {
+        self.changes.iter().filter(|c| c.action == action).count()
+    }
+
+    pub fn indices(&self) -> Vec<u32> {
+        self.changes.iter().map(|c| c.index).collect()
+    }
+
+    // What `YasScanner::flip_lock` is given, with the fingerprints of the artifacts planned on
+    // and `count`, the items in the bag when they were scanned, to tell a stale file later
+    pub fn to_lock_file(&self, artifacts: &[InternalArtifact], count: Option<u32>) -> LockFile {
+        let entries: Vec<LockEntry> = self
+            .changes
+            .iter()
+            .map(|c| LockEntry {
+                index: Some(c.index),
+                action: Some(c.action),
+                fingerprint: Some(fingerprint(&artifacts[c.position])),
+                artifact: None,
+            })
+            .collect();
+        let fingerprints: Vec<Option<String>> =
+            entries.iter().map(|e| e.fingerprint.clone()).collect();
+
+        LockFile {
+            source: Some(LockSource {
+                count,
+                hash: content_hash(&fingerprints),
+            }),
+            entries,
+        }
+    }
+
+    pub fn to_text(&self, artifacts: &[InternalArtifact], rules: &[LockRule]) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        lines.push(format!(
+            "加锁 {} 件，解锁 {} 件；已符合规则 {} 件，无规则匹配 {} 件",
+            self.count(LockAction::Lock),
+            self.count(LockAction::Unlock),
+            self.satisfied,
+            self.untouched
+        ));
+        for c in self.changes.iter() {
+            let art = &artifacts[c.position];
+            lines.push(format!(
+                "#{} {} {} {}★+{} {} 双暴{:.1} 词条{:.0}  规则{}: {}",
+                c.index,
+                c.action.to_str(),
+                art.display_name(),
+                art.rarity,
+                art.level,
+                art.main_stat.key.to_good(),
+                crit_value(art),
+                roll_value(art),
+                c.rule + 1,
+                rules[c.rule].description
+            ));
+        }
+
+        lines.join("\n")
+    }
+
+    pub fn save_lock_file(&self, path: String, artifacts: &[InternalArtifact], count: Option<u32>) {
+        self.to_lock_file(artifacts, count).save(path);
+    }
+
+    pub fn save_text(&self, path: String, artifacts: &[InternalArtifact], rules: &[LockRule]) {
+        let mut file = match File::create(&path) {
+            Err(why) => panic!("couldn't create {}: {}", path, why),
+            Ok(file) => file,
+        };
+
+        if let Err(why) = file.write_all(self.to_text(artifacts, rules).as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+}
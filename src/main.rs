@@ -1,18 +1,30 @@
 use std::error::Error;
-use std::fs::File;
+use std::fs;
 use std::io::stdin;
 use std::io::stdout;
-use std::io::BufReader;
 use std::io::Write;
 use std::path::Path;
+use std::str::FromStr;
 use std::time::SystemTime;
 
-use yas::capture::capture_absolute_image;
+use yas::artifact::filter::Filter;
+use yas::artifact::fuzzy::{init_fuzzy_resolver, FuzzyResolver};
+use yas::artifact::game_data::init_game_data;
+use yas::artifact::internal_artifact::InternalArtifact;
+use yas::artifact::score::load_score_profiles;
 use yas::common::utils;
 use yas::expo::genmo::GenmoFormat;
-use yas::expo::good::GoodFormat;
+use yas::expo::good::{read_good_artifacts, GoodFormat};
 use yas::expo::mona_uranai::MonaFormat;
+use yas::expo::ranking::RankingReport;
+use yas::inference::icon::IconMatcher;
 use yas::info::info;
+use yas::inventory::history::HistoryStore;
+use yas::lock::flip::{
+    identity_preview_text, preview_text, scan_content_hash, targets_with_titles, LockTarget,
+};
+use yas::lock::lock_file::{LockEntry, LockFile};
+use yas::scanner::report::read_error_indices;
 use yas::scanner::yas_scanner::{YasScanner, YasScannerConfig};
 
 use winapi::um::shellscalingapi::{SetProcessDpiAwareness, PROCESS_PER_MONITOR_DPI_AWARE};
@@ -20,8 +32,7 @@ use winapi::um::winuser::{SetForegroundWindow, SetProcessDPIAware, ShowWindow, S
 
 use clap::{App, Arg};
 use env_logger::Builder;
-use log::{info, LevelFilter};
-use os_info;
+use log::{error, info, warn, LevelFilter};
 
 // fn open_local(path: String) -> RawImage {
 //     let img = image::open(path).unwrap();
@@ -62,13 +73,50 @@ fn get_version() -> String {
     String::from("unknown_version")
 }
 
-fn read_lock_file<P: AsRef<Path>>(path: P) -> Result<Vec<u32>, Box<dyn Error>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
+fn read_lock_file<P: AsRef<Path>>(path: P) -> Result<LockFile, Box<dyn Error>> {
+    let s = fs::read_to_string(path)?;
 
-    let l: Vec<u32> = serde_json::from_reader(reader)?;
+    Ok(LockFile::from_str(&s)?)
+}
+
+// Lock targets with the titles and lock states the artifacts had in the scan lock.json was
+// planned from, the good.json and report.json next to it, when that scan is still there. Also
+// gives the number of artifacts that scan went through and the `content_hash` it has for the
+// targets.
+fn lock_targets(
+    entries: &[LockEntry],
+    dir: &Path,
+) -> (Vec<LockTarget>, Option<u32>, Option<String>) {
+    let read_scan = || -> Result<(Vec<InternalArtifact>, Vec<u32>), String> {
+        let good = fs::read_to_string(dir.join("good.json")).map_err(|e| e.to_string())?;
+        let report = fs::read_to_string(dir.join("report.json")).map_err(|e| e.to_string())?;
+        Ok((read_good_artifacts(&good)?, read_error_indices(&report)?))
+    };
+
+    match read_scan() {
+        Ok((artifacts, error_indices)) => (
+            targets_with_titles(entries, &artifacts, &error_indices),
+            Some((artifacts.len() + error_indices.len()) as u32),
+            Some(scan_content_hash(entries, &artifacts, &error_indices)),
+        ),
+        Err(e) => {
+            warn!("无法读取上次扫描结果，不核对圣遗物名称: {}", e);
+            (
+                entries.iter().filter_map(LockTarget::from_entry).collect(),
+                None,
+                None,
+            )
+        }
+    }
+}
+
+fn confirm(prompt: &str) -> bool {
+    print!("{}", prompt);
+    stdout().flush().unwrap();
+    let mut s: String = String::new();
+    stdin().read_line(&mut s).expect("Readline error");
 
-    Ok(l)
+    s.trim() == "y"
 }
 
 fn main() {
@@ -84,6 +132,14 @@ fn main() {
         .version(version.as_str())
         .author("wormtql <584130248@qq.com>")
         .about("Genshin Impact Artifact Exporter")
+        .arg(
+            Arg::with_name("mode")
+                .long("mode")
+                .takes_value(true)
+                .possible_values(&["artifact", "weapon", "character", "material"])
+                .default_value("artifact")
+                .help("扫描对象：artifact为圣遗物背包，weapon为武器背包（结果输出到good_weapons.json），character为角色界面（结果输出到good_characters.json），material为养成道具和材料背包（结果输出到good_materials.json）"),
+        )
         .arg(
             Arg::with_name("max-row")
                 .long("max-row")
@@ -97,6 +153,35 @@ fn main() {
                 .takes_value(false)
                 .help("输出模型预测结果、二值化图像和灰度图像，debug专用"),
         )
+        .arg(
+            Arg::with_name("lock-by-identity")
+                .long("lock-by-identity")
+                .required(false)
+                .takes_value(false)
+                .help("按指纹或圣遗物内容而不是位置查找lock文件中的目标，会识别整个背包，背包变化后仍然有效"),
+        )
+        .arg(
+            Arg::with_name("lock-preview")
+                .long("lock-preview")
+                .required(false)
+                .takes_value(false)
+                .help("加解锁前列出每个目标所在的页、行、列和需要滚动的行数，保存到lock_preview.txt，确认后再开始"),
+        )
+        .arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .required(false)
+                .takes_value(false)
+                .help("加解锁时只翻页、选中并核对目标，不点击锁定图标"),
+        )
+        .arg(
+            Arg::with_name("lang")
+                .long("lang")
+                .takes_value(true)
+                .possible_values(&["zh-cn", "en", "ja"])
+                .default_value("zh-cn")
+                .help("游戏客户端语言，按该语言的名称表识别圣遗物、武器和角色名称；自带的识别模型只支持简体中文"),
+        )
         .arg(
             Arg::with_name("capture-only")
                 .long("capture-only")
@@ -108,7 +193,7 @@ fn main() {
             Arg::with_name("min-star")
                 .long("min-star")
                 .takes_value(true)
-                .help("最小星级")
+                .help("最小星级，圣遗物默认为4，武器默认为1")
                 .min_values(1)
                 .max_values(5),
         )
@@ -146,6 +231,70 @@ fn main() {
                 .long("verbose")
                 .help("显示详细信息"),
         )
+        .arg(
+            Arg::with_name("digit-templates")
+                .long("digit-templates")
+                .takes_value(true)
+                .help("数字模板文件，用于识别等级、数值和数量（由yas_bench --build-digit-templates生成）"),
+        )
+        .arg(
+            Arg::with_name("title-templates")
+                .long("title-templates")
+                .takes_value(true)
+                .help("圣遗物名称模板目录，名称识别失败时用于匹配（由yas_bench --build-title-templates生成）"),
+        )
+        .arg(
+            Arg::with_name("material-icons")
+                .long("material-icons")
+                .takes_value(true)
+                .help("材料图标目录，每个材料一张<GOOD名称>.png，material模式下必须指定"),
+        )
+        .arg(
+            Arg::with_name("game-data")
+                .long("game-data")
+                .takes_value(true)
+                .help("游戏数据文件（圣遗物套装、角色等），默认读取当前目录下的game_data.json，不存在时使用内置数据"),
+        )
+        .arg(
+            Arg::with_name("fuzzy-max-distance")
+                .long("fuzzy-max-distance")
+                .takes_value(true)
+                .help("名称模糊匹配允许的最大编辑距离，形近字替换按0.4计，默认为2"),
+        )
+        .arg(
+            Arg::with_name("fuzzy-min-margin")
+                .long("fuzzy-min-margin")
+                .takes_value(true)
+                .help("模糊匹配时第二接近的名称至少要远出的距离，不足时视为无法确定，默认为1"),
+        )
+        .arg(
+            Arg::with_name("roll-analysis")
+                .long("roll-analysis")
+                .help("在good.json中为每个圣遗物附加副词条强化次数和档位分析（yasRolls字段）"),
+        )
+        .arg(
+            Arg::with_name("score")
+                .long("score")
+                .help("为圣遗物评分：在good.json中加入yasScore字段，并按各评分方案排序输出ranking.json"),
+        )
+        .arg(
+            Arg::with_name("score-profiles")
+                .long("score-profiles")
+                .takes_value(true)
+                .help("评分方案文件（各类角色的词条权重），默认读取当前目录下的score_profiles.json，不存在时使用内置方案"),
+        )
+        .arg(
+            Arg::with_name("filter")
+                .long("filter")
+                .takes_value(true)
+                .help("只导出符合条件的圣遗物，如 \"set == EmblemOfSeveredFate && sub(critRate) >= 6.6\""),
+        )
+        .arg(
+            Arg::with_name("history")
+                .long("history")
+                .takes_value(true)
+                .help("将每次扫描结果保存到该目录，可用yas_history查看历史或重新导出"),
+        )
         .arg(
             Arg::with_name("offset-x")
                 .long("offset-x")
@@ -160,28 +309,87 @@ fn main() {
         )
         // .arg(Arg::with_name("output-format").long("output-format").short("f").takes_value(true).help("输出格式。mona：莫纳占卜铺（默认）；mingyulab：原魔计算器。").possible_values(&["mona", "mingyulab"]).default_value("mona"))
         .get_matches();
+    init_game_data(matches.value_of("game-data"));
+    init_fuzzy_resolver(FuzzyResolver::new(
+        matches
+            .value_of("fuzzy-max-distance")
+            .unwrap_or("2")
+            .parse::<f64>()
+            .unwrap(),
+        matches
+            .value_of("fuzzy-min-margin")
+            .unwrap_or("1")
+            .parse::<f64>()
+            .unwrap(),
+    ));
+
+    let filter = match matches.value_of("filter").map(Filter::parse) {
+        Some(Ok(f)) => Some(f),
+        Some(Err(e)) => {
+            error!("{}", e);
+            utils::error_and_quit("过滤条件有误");
+        }
+        None => None,
+    };
+
     let config = YasScannerConfig::from_match(&matches);
+    let mode = matches.value_of("mode").unwrap();
 
     set_dpi_awareness();
 
+    let icons = if mode == "material" {
+        // no icons are shipped yet
+        let dir = match matches.value_of("material-icons") {
+            Some(v) => v,
+            None => utils::error_and_quit("material模式需要用 --material-icons 指定材料图标目录"),
+        };
+        match IconMatcher::from_dir(dir) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                error!("{}", e);
+                utils::error_and_quit("无法加载材料图标");
+            }
+        }
+    } else {
+        None
+    };
+
     let mut lock_mode = false;
-    let mut indices: Vec<u32> = Vec::new();
+    let mut lock_file = LockFile {
+        entries: Vec::new(),
+        source: None,
+    };
 
     let output_dir = Path::new(matches.value_of("output-dir").unwrap());
     let lock_filename = output_dir.join("lock.json");
-    if lock_filename.exists() {
+    if mode == "artifact" && lock_filename.exists() {
         print!("检测到lock文件，输入y开始加解锁，直接回车开始扫描：");
         stdout().flush().unwrap();
         let mut s: String = String::new();
         stdin().read_line(&mut s).expect("Readline error");
         if s.trim() == "y" {
-            indices = match read_lock_file(lock_filename) {
+            lock_file = match read_lock_file(lock_filename) {
                 Ok(v) => v,
-                _ => {
+                Err(e) => {
+                    error!("{}", e);
                     utils::error_and_quit("无法读取lock文件");
                 }
             };
             lock_mode = true;
+
+            // checked before the bag is walked, such entries would all end up not found
+            let index_only = lock_file
+                .entries
+                .iter()
+                .filter(|e| e.fingerprint.is_none() && e.artifact.is_none())
+                .count();
+            if matches.is_present("lock-by-identity") && index_only > 0 {
+                error!(
+                    "lock文件中有 {} 个目标只有位置，没有指纹或圣遗物内容，无法按内容查找",
+                    index_only
+                );
+                utils::error_and_quit("请去掉 --lock-by-identity，或用 yas_plan 重新生成lock文件");
+            }
         }
     }
 
@@ -239,13 +447,128 @@ fn main() {
     let mut scanner = YasScanner::new(info.clone(), config);
 
     if lock_mode {
-        scanner.flip_lock(indices);
+        // entries without an index can only be found by identity, entries with nothing but an
+        // index are never found that way
+        let by_identity = matches.is_present("lock-by-identity")
+            || lock_file.entries.iter().any(|e| e.index.is_none());
+        let (targets, scan_count, scan_hash) = if by_identity {
+            (Vec::new(), None, None)
+        } else {
+            lock_targets(&lock_file.entries, output_dir)
+        };
+        let source_count = lock_file.source.as_ref().and_then(|s| s.count);
+
+        if matches.is_present("lock-preview") {
+            let text = if by_identity {
+                identity_preview_text(&lock_file.entries)
+            } else {
+                // the real count is read from the bag later, only the last page depends on it
+                let count = source_count
+                    .or(scan_count)
+                    .unwrap_or_else(|| targets.iter().map(|t| t.index + 1).max().unwrap_or(0));
+                preview_text(&targets, count, info.art_row, info.art_col)
+            };
+            println!("{}", text);
+            let preview_filename = output_dir.join("lock_preview.txt");
+            fs::write(&preview_filename, &text).expect("Err");
+            info!("已保存到 {}", preview_filename.display());
+
+            if !confirm("确认无误后输入y开始加解锁：") {
+                return;
+            }
+            unsafe {
+                SetForegroundWindow(hwnd);
+            }
+            utils::sleep(1000);
+        }
+
+        // a lock file only holds for the bag it was planned on, anything added, removed or
+        // upgraded since moves the targets
+        if !by_identity && !targets.is_empty() {
+            let mut problems: Vec<String> = Vec::new();
+            match lock_file.source {
+                Some(ref source) => {
+                    if scan_hash.is_some_and(|h| h != source.hash) {
+                        problems.push(String::from("上次扫描的结果与生成lock文件时不同"));
+                    }
+                }
+                None => warn!("lock文件没有来源扫描的信息，只核对部分目标"),
+            }
+            problems.extend(scanner.check_lock_targets(&targets, source_count));
+
+            if !problems.is_empty() {
+                for p in problems.iter() {
+                    warn!("{}", p);
+                }
+                warn!("无法确认lock文件仍与背包一致，建议重新扫描并生成lock文件，或使用 --lock-by-identity");
+                if !confirm("输入y仍然按lock文件加解锁，直接回车退出：") {
+                    return;
+                }
+                unsafe {
+                    SetForegroundWindow(hwnd);
+                }
+                utils::sleep(1000);
+            }
+        }
+
+        let report = if by_identity {
+            scanner.lock_by_identity(&lock_file.entries)
+        } else {
+            scanner.flip_lock(targets)
+        };
+        report.print_summary();
+    } else if mode == "character" {
+        let (results, report) = scanner.scan_characters();
+
+        let output_filename = output_dir.join("good_characters.json");
+        let good = GoodFormat::from_characters(&results);
+        good.save(String::from(output_filename.to_str().unwrap()));
+        let output_filename = output_dir.join("report_characters.json");
+        report.save(String::from(output_filename.to_str().unwrap()));
+    } else if let Some(icons) = icons {
+        let (results, report) = scanner.scan_materials(&icons);
+
+        let output_filename = output_dir.join("good_materials.json");
+        let good = GoodFormat::from_materials(&results);
+        good.save(String::from(output_filename.to_str().unwrap()));
+        let output_filename = output_dir.join("report_materials.json");
+        report.save(String::from(output_filename.to_str().unwrap()));
+    } else if mode == "weapon" {
+        let now = SystemTime::now();
+        let (results, report) = scanner.scan_weapons();
+        let t = now.elapsed().unwrap().as_secs_f64();
+        info!("time: {}s", t);
+
+        // kept apart from good.json and report.json of the artifact scan
+        let output_filename = output_dir.join("good_weapons.json");
+        let good = GoodFormat::from_weapons(&results);
+        good.save(String::from(output_filename.to_str().unwrap()));
+        let output_filename = output_dir.join("report_weapons.json");
+        report.save(String::from(output_filename.to_str().unwrap()));
     } else {
         let now = SystemTime::now();
-        let results = scanner.scan();
+        let (mut results, mut report) = scanner.scan();
         let t = now.elapsed().unwrap().as_secs_f64();
         info!("time: {}s", t);
 
+        // stored before filtering, the history keeps the whole bag
+        if let Some(dir) = matches.value_of("history") {
+            let error_indices: Vec<u32> = report.parse_errors.iter().map(|e| e.index).collect();
+            match HistoryStore::open(Path::new(dir))
+                .and_then(|mut store| store.add(&results, &error_indices).map(|s| s.id))
+            {
+                Ok(id) => info!("saved to history as scan {}", id),
+                Err(e) => error!("cannot save history: {}", e),
+            }
+        }
+
+        if let Some(ref f) = filter {
+            let total = results.len();
+            results.retain(|art| f.matches(art));
+            info!("filter: {} of {} artifacts", results.len(), total);
+            report.filtered = true;
+        }
+
         // Mona
         let output_filename = output_dir.join("mona.json");
         let mona = MonaFormat::new(&results);
@@ -256,8 +579,26 @@ fn main() {
         genmo.save(String::from(output_filename.to_str().unwrap()));
         // GOOD
         let output_filename = output_dir.join("good.json");
-        let good = GoodFormat::new(&results);
+        let score_profiles = if matches.is_present("score") {
+            Some(load_score_profiles(matches.value_of("score-profiles")))
+        } else {
+            None
+        };
+        let good = GoodFormat::new_with_extras(
+            &results,
+            matches.is_present("roll-analysis"),
+            score_profiles.as_deref(),
+        );
         good.save(String::from(output_filename.to_str().unwrap()));
+        // Ranking
+        if let Some(ref profiles) = score_profiles {
+            let output_filename = output_dir.join("ranking.json");
+            let ranking = RankingReport::new(&results, profiles);
+            ranking.save(String::from(output_filename.to_str().unwrap()));
+        }
+        // Scan report
+        let output_filename = output_dir.join("report.json");
+        report.save(String::from(output_filename.to_str().unwrap()));
     }
 
     // let info = info;
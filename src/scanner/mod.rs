@@ -1 +1,3 @@
-pub mod yas_scanner;
\ No newline at end of file
+#[cfg(windows)]
+pub mod yas_scanner;
+pub mod report;
\ No newline at end of file
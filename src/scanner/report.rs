@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+
+use log::info;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+
+use crate::artifact::fuzzy::FuzzyMatch;
+use crate::artifact::main_stat::MainStatCheck;
+use crate::artifact::parse_error::ParseError;
+use crate::artifact::rolls::{RollCheck, SubstatCorrection};
+use crate::artifact::rules::Diagnostic;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CorrectionKind {
+    SnappedToRoll,
+    DecimalPointRestored,
+    ImpossibleValue,
+    LevelInferred,
+    MainStatCorrected,
+    ReRecognized,
+}
+
+impl CorrectionKind {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            CorrectionKind::SnappedToRoll => "snapped_to_roll",
+            CorrectionKind::DecimalPointRestored => "decimal_point_restored",
+            CorrectionKind::ImpossibleValue => "impossible_value",
+            CorrectionKind::LevelInferred => "level_inferred",
+            CorrectionKind::MainStatCorrected => "main_stat_corrected",
+            CorrectionKind::ReRecognized => "re_recognized",
+        }
+    }
+}
+
+// A recognized value that was changed or flagged after recognition. `index` is the position
+// of the artifact in the scan order.
+#[derive(Debug, Clone)]
+pub struct Correction {
+    pub index: u32,
+    pub field: String,
+    pub kind: CorrectionKind,
+    pub original: String,
+    pub corrected: Option<String>,
+}
+
+impl Correction {
+    pub fn from_substat(index: u32, c: &SubstatCorrection) -> Correction {
+        let (kind, corrected) = match c.check {
+            RollCheck::Snapped(v) => (CorrectionKind::SnappedToRoll, Some(v)),
+            RollCheck::DecimalRestored(v) => (CorrectionKind::DecimalPointRestored, Some(v)),
+            _ => (CorrectionKind::ImpossibleValue, None),
+        };
+
+        Correction {
+            index,
+            field: String::from(c.field),
+            kind,
+            original: c.original.to_string(),
+            corrected: corrected.map(|v| v.to_string()),
+        }
+    }
+
+    pub fn from_main_stat(index: u32, check: &MainStatCheck) -> Option<Correction> {
+        let c = match check {
+            MainStatCheck::Valid => return None,
+            MainStatCheck::LevelInferred { original, level } => Correction {
+                index,
+                field: String::from("level"),
+                kind: CorrectionKind::LevelInferred,
+                original: original.map_or(String::new(), |v| v.to_string()),
+                corrected: Some(level.to_string()),
+            },
+            MainStatCheck::ValueCorrected {
+                original,
+                corrected,
+            } => Correction {
+                index,
+                field: String::from("main_stat_value"),
+                kind: CorrectionKind::MainStatCorrected,
+                original: original.map_or(String::new(), |v| v.to_string()),
+                corrected: Some(corrected.to_string()),
+            },
+        };
+
+        Some(c)
+    }
+}
+
+impl Serialize for Correction {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(5))?;
+        root.serialize_entry("index", &self.index)?;
+        root.serialize_entry("field", &self.field)?;
+        root.serialize_entry("kind", self.kind.to_str())?;
+        root.serialize_entry("original", &self.original)?;
+        root.serialize_entry("corrected", &self.corrected)?;
+        root.end()
+    }
+}
+
+// A recognized name that was not spelled like any known one. Ambiguous matches are left as
+// recognized, the runner-up tells what else it could have been.
+#[derive(Debug, Clone)]
+pub struct NameMatch {
+    pub index: u32,
+    pub field: String,
+    pub raw: String,
+    pub name: String,
+    pub distance: f64,
+    pub runner_up: Option<String>,
+    pub margin: Option<f64>,
+    pub ambiguous: bool,
+}
+
+impl NameMatch {
+    pub fn new(index: u32, field: &str, raw: &str, m: &FuzzyMatch) -> NameMatch {
+        NameMatch {
+            index,
+            field: String::from(field),
+            raw: String::from(raw),
+            name: String::from(m.name),
+            distance: m.distance,
+            runner_up: m.runner_up.map(|(name, _)| String::from(name)),
+            margin: m.margin(),
+            ambiguous: m.ambiguous,
+        }
+    }
+}
+
+impl Serialize for NameMatch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(8))?;
+        root.serialize_entry("index", &self.index)?;
+        root.serialize_entry("field", &self.field)?;
+        root.serialize_entry("raw", &self.raw)?;
+        root.serialize_entry("name", &self.name)?;
+        root.serialize_entry("distance", &self.distance)?;
+        root.serialize_entry("runner_up", &self.runner_up)?;
+        root.serialize_entry("margin", &self.margin)?;
+        root.serialize_entry("ambiguous", &self.ambiguous)?;
+        root.end()
+    }
+}
+
+// A capture that could not be turned into an artifact, it is missing from the exports
+#[derive(Debug, Clone)]
+pub struct ParseFailure {
+    pub index: u32,
+    pub error: ParseError,
+}
+
+impl Serialize for ParseFailure {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(4))?;
+        root.serialize_entry("index", &self.index)?;
+        root.serialize_entry("field", self.error.field)?;
+        root.serialize_entry("reason", self.error.reason.to_str())?;
+        root.serialize_entry("text", &self.error.text)?;
+        root.end()
+    }
+}
+
+// An artifact that breaks the structural rules even after re-recognition. It is still
+// exported, the diagnostics tell what to look at in game.
+#[derive(Debug, Clone)]
+pub struct Review {
+    pub index: u32,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Serialize for Review {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(Some(2))?;
+        root.serialize_entry("index", &self.index)?;
+        root.serialize_entry("diagnostics", &self.diagnostics)?;
+        root.end()
+    }
+}
+
+// Everything noteworthy that happened while turning captures into artifacts
+pub struct ScanReport {
+    // items in the bag as shown above the grid, when it was read
+    pub item_count: Option<u32>,
+    // the exports only hold the artifacts matching `--filter`, so they cannot be mapped back
+    // onto the grid
+    pub filtered: bool,
+    pub scanned: u32,
+    pub recognized: u32,
+    pub errors: u32,
+    pub duplicates: u32,
+    pub parse_errors: Vec<ParseFailure>,
+    pub corrections: Vec<Correction>,
+    pub name_matches: Vec<NameMatch>,
+    pub reviews: Vec<Review>,
+}
+
+impl Default for ScanReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScanReport {
+    pub fn new() -> ScanReport {
+        ScanReport {
+            item_count: None,
+            filtered: false,
+            scanned: 0,
+            recognized: 0,
+            errors: 0,
+            duplicates: 0,
+            parse_errors: Vec::new(),
+            corrections: Vec::new(),
+            name_matches: Vec::new(),
+            reviews: Vec::new(),
+        }
+    }
+
+    pub fn add_substat_corrections(&mut self, index: u32, corrections: &[SubstatCorrection]) {
+        for c in corrections.iter() {
+            self.corrections.push(Correction::from_substat(index, c));
+        }
+    }
+
+    pub fn add_main_stat_check(&mut self, index: u32, check: &MainStatCheck) {
+        if let Some(c) = Correction::from_main_stat(index, check) {
+            self.corrections.push(c);
+        }
+    }
+
+    pub fn add_re_recognition(&mut self, index: u32, field: &str, original: String, text: String) {
+        self.corrections.push(Correction {
+            index,
+            field: String::from(field),
+            kind: CorrectionKind::ReRecognized,
+            original,
+            corrected: Some(text),
+        });
+    }
+
+    pub fn add_parse_error(&mut self, index: u32, error: ParseError) {
+        self.errors += 1;
+        self.parse_errors.push(ParseFailure { index, error });
+    }
+
+    // Parse errors counted by field and reason, most frequent first
+    pub fn parse_error_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for f in self.parse_errors.iter() {
+            let key = format!("{} {}", f.error.field, f.error.reason.to_str());
+            *counts.entry(key).or_insert(0) += 1;
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        counts
+    }
+
+    pub fn add_name_match(&mut self, index: u32, field: &str, raw: &str, m: &FuzzyMatch) {
+        self.name_matches.push(NameMatch::new(index, field, raw, m));
+    }
+
+    pub fn add_review(&mut self, index: u32, diagnostics: Vec<Diagnostic>) {
+        self.reviews.push(Review { index, diagnostics });
+    }
+
+    pub fn count_corrections(&self, kind: CorrectionKind) -> usize {
+        self.corrections.iter().filter(|c| c.kind == kind).count()
+    }
+
+    pub fn print_summary(&self) {
+        info!("scanned: {}", self.scanned);
+        info!("recognized: {}", self.recognized);
+        info!("error count: {}", self.errors);
+        for (key, count) in self.parse_error_counts() {
+            info!("    {}: {}", key, count);
+        }
+        info!("dup count: {}", self.duplicates);
+        info!(
+            "corrected values: {}, impossible values: {}",
+            self.corrections.len() - self.count_corrections(CorrectionKind::ImpossibleValue),
+            self.count_corrections(CorrectionKind::ImpossibleValue)
+        );
+        let ambiguous = self.name_matches.iter().filter(|m| m.ambiguous).count();
+        info!(
+            "fuzzy matched names: {}, ambiguous names: {}",
+            self.name_matches.len() - ambiguous,
+            ambiguous
+        );
+        info!("needs review: {}", self.reviews.len());
+    }
+
+    pub fn save(&self, path: String) {
+        let mut file = match File::create(&path) {
+            Err(why) => panic!("couldn't create {}: {}", path, why),
+            Ok(file) => file,
+        };
+        let s = serde_json::to_string(&self).unwrap();
+
+        if let Err(why) = file.write_all(s.as_bytes()) {
+            panic!("couldn't write to {}: {}", path, why)
+        }
+    }
+}
+
+impl Serialize for ScanReport {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut root = serializer.serialize_map(None)?;
+        if let Some(count) = self.item_count {
+            root.serialize_entry("item_count", &count)?;
+        }
+        if self.filtered {
+            root.serialize_entry("filtered", &true)?;
+        }
+        root.serialize_entry("scanned", &self.scanned)?;
+        root.serialize_entry("recognized", &self.recognized)?;
+        root.serialize_entry("errors", &self.errors)?;
+        root.serialize_entry("duplicates", &self.duplicates)?;
+        root.serialize_entry("parse_errors", &self.parse_errors)?;
+
+        root.serialize_entry("corrections", &self.corrections)?;
+        root.serialize_entry("name_matches", &self.name_matches)?;
+
+        root.serialize_entry("reviews", &self.reviews)?;
+        root.end()
+    }
+}
+
+// Scan indices of the captures missing from the exports, read back from a saved report. The
+// report of a filtered scan is rejected, its exports do not go through the whole grid.
+pub fn read_error_indices(s: &str) -> Result<Vec<u32>, String> {
+    let json: Value = match serde_json::from_str(s) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("cannot parse scan report: {}", e)),
+    };
+    if json["filtered"].as_bool() == Some(true) {
+        return Err(String::from(
+            "the scan was exported with --filter, its artifacts are not the whole bag",
+        ));
+    }
+
+    let mut indices: Vec<u32> = Vec::new();
+    for item in json["parse_errors"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+    {
+        match item["index"].as_u64() {
+            Some(v) => indices.push(v as u32),
+            None => return Err(String::from("parse error without index")),
+        }
+    }
+    indices.sort();
+
+    Ok(indices)
+}
+
+// Items in the bag when a saved report was written, None for reports without it
+pub fn read_item_count(s: &str) -> Option<u32> {
+    let json: Value = serde_json::from_str(s).ok()?;
+    json["item_count"].as_u64().map(|v| v as u32)
+}
+
+// Grid index of every exported artifact. Captures that could not be parsed are missing from
+// the exports, so the n-th artifact sits at the n-th index not listed in `error_indices`.
+pub fn grid_indices(artifact_count: usize, error_indices: &[u32]) -> Vec<u32> {
+    (0..)
+        .filter(|i| !error_indices.contains(i))
+        .take(artifact_count)
+        .collect()
+}
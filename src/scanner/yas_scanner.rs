@@ -1,33 +1,69 @@
 use std::collections::HashSet;
 use std::convert::From;
 use std::fs;
-use std::sync::mpsc;
+use std::sync::mpsc::{self, Sender};
 use std::thread;
 use std::time::SystemTime;
 
 use clap::ArgMatches;
 use enigo::*;
+use image::RgbImage;
 use log::{error, info, warn};
 
+use crate::artifact::fuzzy::FuzzyMatch;
+use crate::artifact::game_data::game_data;
 use crate::artifact::internal_artifact::{
-    ArtifactSetKey, ArtifactSlotKey, ArtifactStat, CharacterKey, InternalArtifact,
+    ArtifactSetKey, ArtifactSlotKey, ArtifactStat, ArtifactStatKey, CharacterKey, InternalArtifact,
 };
+use crate::artifact::lang::Language;
+use crate::artifact::main_stat::{normalize_main_stat_key, resolve_main_stat, MainStatCheck};
+use crate::artifact::parse_error::{ParseError, ParseErrorReason};
+use crate::artifact::rolls::validate_substats;
+use crate::artifact::rules::{check_artifact, initial_substats, suspect_fields, Diagnostic};
+
 use crate::capture;
+use crate::character::internal_character::{
+    base_talent_levels, parse_talent_level, InternalCharacter,
+};
 use crate::common::color::Color;
-use crate::common::{utils, PixelRect, PixelRectBound, RawCaptureImage};
+use crate::common::{utils, PixelRect, PixelRectBound, RawCaptureImage, RawImage};
+use crate::inference::digit::{numeric_tail, DigitRecognizer};
+use crate::inference::icon::{is_blank, IconMatcher};
 use crate::inference::inference::CRNNModel;
-use crate::inference::pre_process::pre_process;
+use crate::inference::pre_process::{pre_process, to_gray};
+use crate::inference::title::TitleMatcher;
 use crate::info::info::ScanInfo;
+use crate::lock::flip::{
+    grid_position, title_matches, verify_sample, FlipOutcome, FlipReport, LockTarget,
+};
+use crate::lock::lock_file::{fingerprint, LockEntry};
+use crate::lock::planner::LockAction;
+use crate::scanner::report::ScanReport;
+use crate::weapon::internal_weapon::{
+    parse_level_and_ascension, parse_refinement, InternalWeapon, WeaponKey,
+};
+
+// attempts to flip one lock, see `flip_selected`
+const FLIP_ATTEMPTS: u32 = 3;
+// targets read back by `check_lock_targets`
+const VERIFY_SAMPLE: usize = 5;
 
 pub struct YasScannerConfig {
     max_row: u32,
     capture_only: bool,
-    min_star: u32,
+    // None when not given, each bag has its own default
+    min_star: Option<u32>,
     max_wait_switch_artifact: u32,
     scroll_stop: u32,
     number: u32,
     verbose: bool,
     dump_mode: bool,
+    digit_templates: Option<String>,
+    title_templates: Option<String>,
+    // language of the game client, names are looked up in its tables
+    lang: Language,
+    // go to every lock target and check it without clicking the lock
+    dry_run: bool,
     // offset_x: i32,
     // offset_y: i32,
 }
@@ -44,9 +80,7 @@ impl YasScannerConfig {
             dump_mode: matches.is_present("dump"),
             min_star: matches
                 .value_of("min-star")
-                .unwrap_or("4")
-                .parse::<u32>()
-                .unwrap(),
+                .map(|s| s.parse::<u32>().unwrap()),
             max_wait_switch_artifact: matches
                 .value_of("max-wait-switch-artifact")
                 .unwrap_or("800")
@@ -63,6 +97,17 @@ impl YasScannerConfig {
                 .parse::<u32>()
                 .unwrap(),
             verbose: matches.is_present("verbose"),
+            digit_templates: matches.value_of("digit-templates").map(String::from),
+            title_templates: matches.value_of("title-templates").map(String::from),
+            lang: match matches
+                .value_of("lang")
+                .unwrap_or("zh-cn")
+                .parse::<Language>()
+            {
+                Ok(v) => v,
+                Err(e) => utils::error_and_quit(&e),
+            },
+            dry_run: matches.is_present("dry-run"),
             // offset_x: matches.value_of("offset-x").unwrap_or("0").parse::<i32>().unwrap(),
             // offset_y: matches.value_of("offset-y").unwrap_or("0").parse::<i32>().unwrap(),
         }
@@ -71,6 +116,7 @@ impl YasScannerConfig {
 
 pub struct YasScanner {
     model: CRNNModel,
+    digit: Option<DigitRecognizer>,
     enigo: Enigo,
 
     info: ScanInfo,
@@ -91,14 +137,47 @@ pub struct YasScanner {
     scanned_count: u32,
 }
 
+#[derive(Clone, Copy)]
+enum FieldKind {
+    Text,
+    // artifact name, checked against the title templates
+    Title,
+    // the whole crop is a number, e.g. level and main stat value
+    Number,
+    // "name+number", e.g. sub stats
+    NumberSuffix,
+}
+
+fn load_digit_recognizer(path: &Option<String>) -> Option<DigitRecognizer> {
+    let path = path.as_ref()?;
+    match DigitRecognizer::from_file(path) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            warn!("无法加载数字模板，使用通用模型识别数字: {}", e);
+            None
+        }
+    }
+}
+
+fn load_title_matcher(path: &Option<String>) -> Option<TitleMatcher> {
+    let path = path.as_ref()?;
+    match TitleMatcher::from_dir(path) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            warn!("无法加载圣遗物名称模板: {}", e);
+            None
+        }
+    }
+}
+
 enum ScrollResult {
-    TLE, // time limit exceeded
+    Tle, // time limit exceeded
     Interrupt,
     Success,
     Skip,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct YasScanResult {
     name: String,
     main_stat_name: String,
@@ -114,34 +193,104 @@ pub struct YasScanResult {
 }
 
 impl YasScanResult {
-    pub fn to_internal_artifact(&self) -> Option<InternalArtifact> {
-        let set_key = ArtifactSetKey::from_zh_cn(&self.name)?;
-        let slot_key = ArtifactSlotKey::from_zh_cn(&self.name)?;
+    // The main stat check tells whether the level or the main stat value had to be fixed. Only
+    // the title, main stat and level are required, a substat or equip line that cannot be
+    // parsed is left empty and returned as a diagnostic.
+    pub fn to_internal_artifact(
+        &self,
+        lang: Language,
+    ) -> Result<(InternalArtifact, MainStatCheck, Vec<Diagnostic>), ParseError> {
+        let set_key = ArtifactSetKey::from_lang(lang, self.name.trim()).ok_or(ParseError::new(
+            "title",
+            ParseErrorReason::UnknownName,
+            &self.name,
+        ))?;
+        let slot_key = ArtifactSlotKey::from_lang(lang, self.name.trim()).ok_or(
+            ParseError::new("slot", ParseErrorReason::UnknownName, &self.name),
+        )?;
         let rarity = self.rarity;
-        if !self.level.contains("+") {
-            return None;
-        }
+        // only "+20" is trusted, otherwise the level is inferred from the main stat value
         let level = self
             .level
-            .chars()
-            .skip(1)
-            .collect::<String>()
-            .parse::<u32>()
-            .ok()?;
-        let main_stat = ArtifactStat::from_zh_cn_raw(
-            (self.main_stat_name.clone() + "+" + self.main_stat_value.as_str()).as_str(),
-        )?;
-        let sub1 = ArtifactStat::from_zh_cn_raw(&self.sub_stat_1);
-        let sub2 = ArtifactStat::from_zh_cn_raw(&self.sub_stat_2);
-        let sub3 = ArtifactStat::from_zh_cn_raw(&self.sub_stat_3);
-        let sub4 = ArtifactStat::from_zh_cn_raw(&self.sub_stat_4);
-
-        let location = if self.location.contains("已装备") {
-            let len = self.location.chars().count();
-            let character_name: String = self.location.chars().take(len - 3).collect::<String>();
-            CharacterKey::from_zh_cn(&character_name)
-        } else {
-            None
+            .trim()
+            .strip_prefix('+')
+            .and_then(|s| s.parse::<u32>().ok());
+        let is_percentage = self.main_stat_value.contains('%');
+        let main_stat_key =
+            ArtifactStatKey::from_lang(lang, self.main_stat_name.trim(), is_percentage).ok_or(
+                ParseError::new(
+                    "main_stat_name",
+                    ParseErrorReason::UnknownName,
+                    &self.main_stat_name,
+                ),
+            )?;
+        let main_stat_key = normalize_main_stat_key(&slot_key, main_stat_key);
+        let main_stat_value = lang.parse_number(&self.main_stat_value);
+        let resolution = match resolve_main_stat(&main_stat_key, rarity, level, main_stat_value) {
+            Some(v) => v,
+            None if main_stat_value.is_none() => {
+                return Err(ParseError::new(
+                    "main_stat_value",
+                    ParseErrorReason::MalformedNumber,
+                    &self.main_stat_value,
+                ))
+            }
+            // the value fits several levels and the level itself could not be read
+            None => {
+                return Err(ParseError::new(
+                    "level",
+                    ParseErrorReason::MalformedNumber,
+                    &self.level,
+                ))
+            }
+        };
+        let level = resolution.level;
+        let main_stat = ArtifactStat {
+            key: main_stat_key,
+            value: resolution.value,
+        };
+        // lines below the last substat (e.g. the set bonus) have no "+", that only counts as
+        // an error where the rarity guarantees a substat
+        let required = initial_substats(rarity).0 as usize;
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        let mut subs: Vec<Option<ArtifactStat>> = Vec::new();
+        for (i, (field, text)) in [
+            ("sub_stat_1", &self.sub_stat_1),
+            ("sub_stat_2", &self.sub_stat_2),
+            ("sub_stat_3", &self.sub_stat_3),
+            ("sub_stat_4", &self.sub_stat_4),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let sub = match ArtifactStat::from_lang_raw(lang, text) {
+                Ok(v) => Some(v),
+                Err(ParseErrorReason::MissingSeparator) if i >= required => None,
+                Err(reason) => {
+                    let error = ParseError::new(field, reason, text);
+                    diagnostics.push(Diagnostic::UnreadableField { error });
+                    None
+                }
+            };
+            subs.push(sub);
+        }
+        let sub4 = subs.pop().unwrap();
+        let sub3 = subs.pop().unwrap();
+        let sub2 = subs.pop().unwrap();
+        let sub1 = subs.pop().unwrap();
+
+        // not equipped when the equip line does not name anyone
+        let location = match lang.equipped_name(&self.location) {
+            Some(name) => {
+                let key = CharacterKey::from_lang(lang, name);
+                if key.is_none() {
+                    let error =
+                        ParseError::new("equip", ParseErrorReason::UnknownName, &self.location);
+                    diagnostics.push(Diagnostic::UnreadableField { error });
+                }
+                key
+            }
+            None => None,
         };
 
         let art = InternalArtifact {
@@ -157,11 +306,239 @@ impl YasScanResult {
             sub_stat_3: sub3,
             sub_stat_4: sub4,
         };
-        Some(art)
+        Ok((art, resolution.check, diagnostics))
+    }
+
+    // The artifact as `scan` exports it, with the names resolved and the substats snapped to
+    // values the game can show. Lock files are fingerprinted from that export.
+    fn exported_artifact(&mut self, lang: Language) -> Result<InternalArtifact, ParseError> {
+        self.resolve_names(lang);
+        let (mut art, _, _) = self.to_internal_artifact(lang)?;
+        validate_substats(&mut art);
+        Ok(art)
+    }
+
+    // Recognized text by field name, as used in diagnostics and the scan report
+    fn field_mut(&mut self, field: &str) -> Option<&mut String> {
+        match field {
+            "title" => Some(&mut self.name),
+            "main_stat_name" => Some(&mut self.main_stat_name),
+            "main_stat_value" => Some(&mut self.main_stat_value),
+            "sub_stat_1" => Some(&mut self.sub_stat_1),
+            "sub_stat_2" => Some(&mut self.sub_stat_2),
+            "sub_stat_3" => Some(&mut self.sub_stat_3),
+            "sub_stat_4" => Some(&mut self.sub_stat_4),
+            "level" => Some(&mut self.level),
+            "equip" => Some(&mut self.location),
+            _ => None,
+        }
+    }
+
+    // Names not spelled like any known one are replaced by the closest match. Returns every
+    // inexact match with its field and the recognized name, ambiguous ones are not applied.
+    fn resolve_names(
+        &mut self,
+        lang: Language,
+    ) -> Vec<(&'static str, String, FuzzyMatch<'static>)> {
+        let mut matches = Vec::new();
+
+        let fields = [
+            ("title", &mut self.name),
+            ("main_stat_name", &mut self.main_stat_name),
+            ("sub_stat_1", &mut self.sub_stat_1),
+            ("sub_stat_2", &mut self.sub_stat_2),
+            ("sub_stat_3", &mut self.sub_stat_3),
+            ("sub_stat_4", &mut self.sub_stat_4),
+            ("equip", &mut self.location),
+        ];
+        for (field, text) in fields {
+            let name = match field {
+                "title" | "main_stat_name" => Some(text.trim()),
+                "equip" => lang.equipped_name(text),
+                _ => text.rfind('+').map(|pos| text[..pos].trim()),
+            };
+            let name = match name {
+                Some(v) if !v.is_empty() => v,
+                _ => continue,
+            };
+            let m = match field {
+                "title" => lang.resolve_piece(name),
+                "equip" => lang.resolve_character(name),
+                _ => lang.resolve_stat(name),
+            };
+            let m = match m {
+                Some(v) if v.distance > 0.0 => v,
+                _ => continue,
+            };
+
+            let raw = String::from(name);
+            if !m.ambiguous {
+                *text = splice(text, name, m.name);
+            }
+            matches.push((field, raw, m));
+        }
+
+        matches
     }
 }
 
-fn calc_pool(row: &Vec<u8>) -> f64 {
+#[derive(Debug, Clone)]
+pub struct WeaponScanResult {
+    name: String,
+    level: String,
+    refinement: String,
+    location: String,
+    rarity: u32,
+    lock: bool,
+}
+
+impl WeaponScanResult {
+    pub fn to_internal_weapon(&self, lang: Language) -> Result<InternalWeapon, ParseError> {
+        let key = WeaponKey::from_lang(lang, self.name.trim()).ok_or(ParseError::new(
+            "title",
+            ParseErrorReason::UnknownName,
+            &self.name,
+        ))?;
+        let (level, ascension) = parse_level_and_ascension(&self.level).ok_or(ParseError::new(
+            "level",
+            ParseErrorReason::MalformedNumber,
+            &self.level,
+        ))?;
+        // 1 and 2 star weapons cannot be refined and have no refinement line
+        let rarity = game_data().weapon(&key).map_or(self.rarity, |w| w.rarity);
+        let refinement = if rarity <= 2 {
+            1
+        } else {
+            parse_refinement(&self.refinement).ok_or(ParseError::new(
+                "refinement",
+                ParseErrorReason::MalformedNumber,
+                &self.refinement,
+            ))?
+        };
+        let location = match lang.equipped_name(&self.location) {
+            Some(name) => Some(CharacterKey::from_lang(lang, name).ok_or(ParseError::new(
+                "equip",
+                ParseErrorReason::UnknownName,
+                &self.location,
+            ))?),
+            None => None,
+        };
+
+        Ok(InternalWeapon {
+            key,
+            level,
+            ascension,
+            refinement,
+            lock: self.lock,
+            location,
+        })
+    }
+
+    // Same as for artifacts, with weapon names for the title
+    fn resolve_names(
+        &mut self,
+        lang: Language,
+    ) -> Vec<(&'static str, String, FuzzyMatch<'static>)> {
+        let mut matches = Vec::new();
+
+        let fields = [("title", &mut self.name), ("equip", &mut self.location)];
+        for (field, text) in fields {
+            let name = match field {
+                "title" => Some(text.trim()),
+                _ => lang.equipped_name(text),
+            };
+            let name = match name {
+                Some(v) if !v.is_empty() => v,
+                _ => continue,
+            };
+            let m = match field {
+                "title" => lang.resolve_weapon(name),
+                _ => lang.resolve_character(name),
+            };
+            let m = match m {
+                Some(v) if v.distance > 0.0 => v,
+                _ => continue,
+            };
+
+            let raw = String::from(name);
+            if !m.ambiguous {
+                *text = splice(text, name, m.name);
+            }
+            matches.push((field, raw, m));
+        }
+
+        matches
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CharacterScanResult {
+    name: String,
+    level: String,
+    constellation: u32,
+    // auto attack, skill and burst as shown, constellation bonus included
+    talents: [String; 3],
+}
+
+impl CharacterScanResult {
+    pub fn to_internal_character(&self, lang: Language) -> Result<InternalCharacter, ParseError> {
+        let key = CharacterKey::from_lang(lang, self.name.trim()).ok_or(ParseError::new(
+            "name",
+            ParseErrorReason::UnknownName,
+            &self.name,
+        ))?;
+        let (level, ascension) = parse_level_and_ascension(&self.level).ok_or(ParseError::new(
+            "level",
+            ParseErrorReason::MalformedNumber,
+            &self.level,
+        ))?;
+        let mut shown = [0_u32; 3];
+        for (i, field) in ["talent_auto", "talent_skill", "talent_burst"]
+            .iter()
+            .enumerate()
+        {
+            shown[i] = parse_talent_level(&self.talents[i]).ok_or(ParseError::new(
+                field,
+                ParseErrorReason::MalformedNumber,
+                &self.talents[i],
+            ))?;
+        }
+        // kept as shown if the bonus cannot be taken off, the warning asks for a review
+        let talents = match base_talent_levels(&key, shown, self.constellation) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("{}", e);
+                shown
+            }
+        };
+
+        Ok(InternalCharacter {
+            key,
+            level,
+            ascension,
+            constellation: self.constellation,
+            talent_auto: talents[0],
+            talent_skill: talents[1],
+            talent_burst: talents[2],
+        })
+    }
+}
+
+// Replaces `part`, a slice of `text`, with `replacement`
+fn splice(text: &str, part: &str, replacement: &str) -> String {
+    let start = part.as_ptr() as usize - text.as_ptr() as usize;
+    format!(
+        "{}{}{}",
+        &text[..start],
+        replacement,
+        &text[start + part.len()..]
+    )
+}
+
+// Crop offsets tried when a field is re-recognized
+const RECHECK_OFFSETS: [(i32, i32); 4] = [(0, -2), (0, 2), (-2, 0), (2, 0)];
+
+fn calc_pool(row: &[u8]) -> f64 {
     let len = row.len() / 4;
     let mut pool: f64 = 0.0;
 
@@ -176,12 +553,14 @@ impl YasScanner {
     pub fn new(info: ScanInfo, config: YasScannerConfig) -> YasScanner {
         let row = info.art_row;
         let col = info.art_col;
+        let digit = load_digit_recognizer(&config.digit_templates);
 
         YasScanner {
             model: CRNNModel::new(
                 String::from("model_training.onnx"),
                 String::from("index_2_word.json"),
             ),
+            digit,
             enigo: Enigo::new(),
             info,
             config,
@@ -209,7 +588,7 @@ impl YasScanner {
         let top = info.top
             + (info.top_margin + (info.art_height + info.art_gap_y) * row + info.art_height / 4)
                 as i32;
-        self.enigo.mouse_move_to(left as i32, top as i32);
+        self.enigo.mouse_move_to(left, top);
     }
 
     fn sample_initial_color(&mut self) {
@@ -219,12 +598,18 @@ impl YasScanner {
     fn get_color(&self) -> Color {
         let flag_x = self.info.flag_x as i32 + self.info.left;
         let flag_y = self.info.flag_y as i32 + self.info.top;
-        let color = capture::get_color(flag_x as u32, flag_y as u32);
 
-        color
+        capture::get_color(flag_x as u32, flag_y as u32)
     }
 
     fn get_art_count(&mut self) -> Result<u32, String> {
+        self.get_item_count("圣遗物")
+    }
+
+    // Count shown above the grid, e.g. 1234 of "圣遗物 1234/1500", unless given by --number.
+    // Only the trailing "1234/1500" is read, the label before it is in the client language.
+    // `bag` names the bag in the error.
+    fn get_item_count(&mut self, bag: &str) -> Result<u32, String> {
         let count = self.config.number;
         if let 0 = count {
             let info = &self.info;
@@ -232,20 +617,25 @@ impl YasScanner {
             // raw_after_pp.to_gray_image().save("count.png");
             let s = self.model.inference_string(&raw_after_pp);
             info!("raw count string: {}", s);
-            if s.starts_with("圣遗物") {
-                let chars = s.chars().collect::<Vec<char>>();
-                let count_str = (&chars[4..chars.len() - 5]).iter().collect::<String>();
-                let count = match count_str.parse::<u32>() {
-                    Ok(v) => v,
-                    Err(_) => {
-                        return Err(String::from("无法识别圣遗物数量"));
-                    }
-                };
-                return Ok(count);
+            // the templates only reread the "1234/1500" the model found, a digit the model
+            // missed would otherwise give a smaller count
+            let mut tail = String::from(numeric_tail(&s));
+            if let Some(digit) = &self.digit {
+                if let Some(v) = digit.recognize_tail(&raw_after_pp, tail.chars().count()) {
+                    info!("raw count digits: {}", v);
+                    tail = v;
+                }
+            }
+            let count_str: String = match tail.split_once('/') {
+                Some((v, _)) => v.chars().filter(|c| c.is_ascii_digit()).collect(),
+                None => return Err(format!("无法识别{}数量", bag)),
+            };
+            match count_str.parse::<u32>() {
+                Ok(v) => Ok(v),
+                Err(_) => Err(format!("无法识别{}数量", bag)),
             }
-            Err(String::from("无法识别圣遗物数量"))
         } else {
-            return Ok(count);
+            Ok(count)
         }
     }
 
@@ -275,12 +665,25 @@ impl YasScanner {
             }
         }
 
-        ScrollResult::TLE
+        ScrollResult::Tle
+    }
+
+    // Back to the first row after `rows` rows were scrolled down. Scrolling past the top does
+    // nothing so this errs on the long side.
+    fn scroll_to_top(&mut self, rows: u32) {
+        if rows == 0 {
+            return;
+        }
+        let scroll = (self.avg_scroll_one_row.max(1.0) * 5.0 * (rows + 1) as f64).ceil() as u32;
+        for _ in 0..scroll {
+            self.enigo.mouse_scroll_y(1);
+        }
+        utils::sleep(400);
     }
 
     fn scroll_rows(&mut self, count: u32) -> ScrollResult {
         if self.scrolled_rows >= 5 {
-            let scroll = ((self.avg_scroll_one_row * count as f64 - 3.0).round() as u32).max(0);
+            let scroll = (self.avg_scroll_one_row * count as f64 - 3.0).round() as u32;
             for _ in 0..scroll {
                 self.enigo.mouse_scroll_y(-1);
             }
@@ -291,7 +694,7 @@ impl YasScanner {
 
         for _ in 0..count {
             match self.scroll_one_row() {
-                ScrollResult::TLE => return ScrollResult::TLE,
+                ScrollResult::Tle => return ScrollResult::Tle,
                 ScrollResult::Interrupt => return ScrollResult::Interrupt,
                 _ => (),
             }
@@ -316,13 +719,14 @@ impl YasScanner {
         false
     }
 
+    #[allow(dead_code)]
     fn wait_until_switched(&mut self) -> bool {
         let now = SystemTime::now();
         while now.elapsed().unwrap().as_millis() < self.config.max_wait_switch_artifact as u128 {
             // let pool_start = SystemTime::now();
             let rect = PixelRect {
-                left: self.info.left as i32 + self.info.pool_position.left,
-                top: self.info.top as i32 + self.info.pool_position.top,
+                left: self.info.left + self.info.pool_position.left,
+                top: self.info.top + self.info.pool_position.top,
                 width: self.info.pool_position.right - self.info.pool_position.left,
                 height: self.info.pool_position.bottom - self.info.pool_position.top,
             };
@@ -354,8 +758,8 @@ impl YasScanner {
         let w = self.info.panel_position.right - self.info.panel_position.left;
         let h = self.info.panel_position.bottom - self.info.panel_position.top;
         let rect: PixelRect = PixelRect {
-            left: self.info.left as i32 + self.info.panel_position.left,
-            top: self.info.top as i32 + self.info.panel_position.top,
+            left: self.info.left + self.info.panel_position.left,
+            top: self.info.top + self.info.panel_position.top,
             width: w,
             height: h,
         };
@@ -399,6 +803,14 @@ impl YasScanner {
     }
 
     fn get_lock(&self, lock_last: bool) -> bool {
+        match self.read_lock() {
+            Some(v) => v,
+            None => !lock_last, // switch animation
+        }
+    }
+
+    // Lock state of the selected item, None while the icon is not in either state
+    fn read_lock(&self) -> Option<bool> {
         let color = capture::get_color(
             (self.info.lock_x as i32 + self.info.left) as u32,
             (self.info.lock_y as i32 + self.info.top) as u32,
@@ -409,14 +821,69 @@ impl YasScanner {
         let color_f = Color::from(241, 237, 232);
 
         if color_t.dis_2(&color) <= 3 {
-            return true;
+            Some(true)
         } else if color_f.dis_2(&color) <= 3 {
-            return false;
+            Some(false)
         } else {
-            return !lock_last; // switch animation
+            None
         }
     }
 
+    fn click_at(&mut self, point: (u32, u32)) {
+        let left = self.info.left + point.0 as i32;
+        let top = self.info.top + point.1 as i32;
+        self.enigo.mouse_move_to(left, top);
+        self.enigo.mouse_click(MouseButton::Left);
+    }
+
+    fn read_text(&self, pos: &PixelRectBound) -> String {
+        let img = pos.capture_relative(&self.info).unwrap();
+        self.model.inference_string(&img)
+    }
+
+    // Constellations lit on the constellation tab, counted from the first
+    fn get_constellation(&self) -> u32 {
+        let lit = |&(x, y): &(u32, u32)| {
+            let color = capture::get_color(
+                (x as i32 + self.info.left) as u32,
+                (y as i32 + self.info.top) as u32,
+            );
+            color.0 as u32 + color.1 as u32 + color.2 as u32 > 450
+        };
+        self.info
+            .character
+            .constellations
+            .iter()
+            .take_while(|p| lit(p))
+            .count() as u32
+    }
+
+    // Icon and preprocessed count of the grid cell at `row` and `col` on screen
+    fn capture_cell(&self, row: u32, col: u32) -> Result<(RgbImage, RawImage), String> {
+        let info = &self.info;
+        let left = info.left + (info.left_margin + (info.art_width + info.art_gap_x) * col) as i32;
+        let top = info.top + (info.top_margin + (info.art_height + info.art_gap_y) * row) as i32;
+        let to_rect = |pos: &PixelRectBound| PixelRect {
+            left: left + pos.left,
+            top: top + pos.top,
+            width: pos.right - pos.left,
+            height: pos.bottom - pos.top,
+        };
+
+        let icon = capture::capture_absolute_image(&to_rect(&info.material_icon_position))?;
+        let rect = to_rect(&info.material_count_position);
+        let raw_u8 = capture::capture_absolute(&rect)?;
+        let count = pre_process(to_gray(raw_u8, rect.width as u32, rect.height as u32));
+        Ok((icon, count))
+    }
+
+    fn read_number(&self, img: &RawImage) -> String {
+        if let Some(s) = self.digit.as_ref().and_then(|digit| digit.recognize(img)) {
+            return s;
+        }
+        self.model.inference_string(img)
+    }
+
     fn start_capture_only(&mut self) {
         fs::create_dir("captures").expect("Create dir error");
         let info = &self.info.clone();
@@ -488,24 +955,136 @@ impl YasScanner {
             .expect("Err");
     }
 
-    pub fn scan(&mut self) -> Vec<InternalArtifact> {
+    // Clicks through the items of the grid from the top and hands each to `visit` with its
+    // row and column on screen, `visit` returns false to stop. Without a `count` paging goes
+    // on until the grid stops scrolling and the last page is then visited whole, as it may
+    // have moved by less than a page. Also stops at the row limit or when the right mouse
+    // button is held.
+    fn walk_grid<F>(&mut self, count: Option<u32>, mut visit: F)
+    where
+        F: FnMut(&mut YasScanner, u32, u32) -> bool,
+    {
+        let is_count_known = count.is_some();
+        let count = match count {
+            Some(v) => v,
+            None => self.config.max_row.saturating_mul(self.col),
+        };
+        let total_row = count.div_ceil(self.col);
+        let last_row_col = if count % self.col == 0 {
+            self.col
+        } else {
+            count % self.col
+        };
+
+        let mut scanned_row = 0_u32;
+        let mut scanned_count = 0_u32;
+        let mut start_row = 0_u32;
+        let mut is_last_page = false;
+
+        self.move_to(0, 0);
+        self.enigo.mouse_click(MouseButton::Left);
+        utils::sleep(1000);
+        self.sample_initial_color();
+
+        'outer: while scanned_count < count {
+            for row in start_row..self.row {
+                let c = if scanned_row == total_row - 1 {
+                    last_row_col
+                } else {
+                    self.col
+                };
+                for col in 0..c {
+                    // 大于最大数量则退出
+                    if scanned_count > count {
+                        break 'outer;
+                    }
+
+                    // 右键终止
+                    if utils::is_rmb_down() {
+                        break 'outer;
+                    }
+
+                    self.move_to(row, col);
+                    self.enigo.mouse_click(MouseButton::Left);
+
+                    // self.wait_until_switched();
+                    utils::sleep(80);
+
+                    if !visit(self, row, col) {
+                        break 'outer;
+                    }
+
+                    scanned_count += 1;
+                } // end 'col
+
+                scanned_row += 1;
+
+                if scanned_row >= self.config.max_row {
+                    info!("max row reached, quiting...");
+                    break 'outer;
+                }
+            } // end 'row
+
+            if is_last_page {
+                break 'outer;
+            }
+
+            let remain = count - scanned_count;
+            let remain_row = remain.div_ceil(self.col);
+            let scroll_row = remain_row.min(self.row);
+            start_row = self.row - scroll_row;
+            match self.scroll_rows(scroll_row) {
+                ScrollResult::Tle if !is_count_known => {
+                    // the end of the grid
+                    is_last_page = true;
+                    start_row = 0;
+                }
+                ScrollResult::Tle => {
+                    error!("翻页出现问题");
+                    break 'outer;
+                }
+                ScrollResult::Interrupt => break 'outer,
+                _ => (),
+            }
+
+            utils::sleep(100);
+        }
+    }
+
+    // Sends the panel of every item with its rarity and lock state, stopping at the first item
+    // below `min_star` since the bags are sorted by rarity
+    fn send_panels(
+        &mut self,
+        count: u32,
+        min_star: u32,
+        tx: &Sender<Option<(RawCaptureImage, u32, bool)>>,
+    ) {
+        let mut lock = false;
+        self.walk_grid(Some(count), |scanner, _, _| {
+            let capture = scanner.capture_panel().unwrap();
+            let star = scanner.get_star();
+            if star < min_star {
+                return false;
+            }
+            lock = scanner.get_lock(lock);
+            // info!("locked: {}", lock);
+            tx.send(Some((capture, star, lock))).unwrap();
+            true
+        });
+    }
+
+    pub fn scan(&mut self) -> (Vec<InternalArtifact>, ScanReport) {
         if self.config.capture_only {
             self.start_capture_only();
-            return Vec::new();
+            return (Vec::new(), ScanReport::new());
         }
 
-        let count = match self.get_art_count() {
+        let art_count = self.get_art_count();
+        let count = match art_count {
             Ok(v) => v,
             Err(_) => 1000,
         };
 
-        let total_row = (count + self.col - 1) / self.col;
-        let last_row_col = if count % self.col == 0 {
-            self.col
-        } else {
-            count % self.col
-        };
-
         // println!("检测到圣遗物数量：{}，若无误请按回车，否则输入正确的圣遗物数量：", count);
         // let mut s: String = String::new();
         // stdin().read_line(&mut s);
@@ -522,19 +1101,22 @@ impl YasScanner {
         // v bvvmnvbm
         let is_verbose = self.config.verbose;
         let is_dump_mode = self.config.dump_mode;
+        let digit_templates = self.config.digit_templates.clone();
+        let title_templates = self.config.title_templates.clone();
+        let lang = self.config.lang;
         let handle = thread::spawn(move || {
             let mut results: Vec<InternalArtifact> = Vec::new();
             let model = CRNNModel::new(
                 String::from("model_training.onnx"),
                 String::from("index_2_word.json"),
             );
-            let mut error_count = 0;
-            let mut dup_count = 0;
+            let digit = load_digit_recognizer(&digit_templates);
+            let title_matcher = load_title_matcher(&title_templates);
+            let mut report = ScanReport::new();
             let mut hash = HashSet::new();
             let mut consecutive_dup_count = 0;
             let info = info_2;
 
-            let mut cnt = 0;
             if is_dump_mode {
                 fs::create_dir("dumps").expect("Err");
             }
@@ -546,58 +1128,168 @@ impl YasScanner {
                 height: rect.bottom - rect.top,
             };
 
-            for i in rx {
+            for (cnt, i) in rx.into_iter().enumerate() {
                 let (capture, rarity, lock) = match i {
                     Some(v) => v,
                     None => break,
                 };
                 // let now = SystemTime::now();
 
-                let model_inference = |pos: &PixelRectBound, name: &str, cnt: i32| {
-                    let raw_img = capture.crop_to_raw_img(&convert_rect(pos));
-                    if is_dump_mode {
-                        raw_img
-                            .grayscale_to_gray_image()
-                            .save(format!("dumps/{}_{}.png", name, cnt))
-                            .expect("Err");
+                let digit_inference = |img: &RawImage, kind: FieldKind, text: Option<&str>| {
+                    let digit = digit.as_ref()?;
+                    match kind {
+                        FieldKind::Text | FieldKind::Title => None,
+                        FieldKind::Number => digit.recognize(img),
+                        FieldKind::NumberSuffix => {
+                            // keep the name recognized by the general model
+                            let text = text?;
+                            let pos = text.rfind('+')?;
+                            let value = digit.recognize_suffix(img)?;
+                            Some(format!("{}+{}", &text[..pos], value))
+                        }
                     }
+                };
 
-                    let processed_img = pre_process(raw_img);
-                    if is_dump_mode {
-                        processed_img
-                            .to_gray_image()
-                            .save(format!("dumps/p_{}_{}.png", name, cnt))
-                            .expect("Err");
+                // Falls back to the templates when the model output is not a known name,
+                // otherwise only cross-checks it
+                let title_inference = |img: &RawImage, text: &str| {
+                    let matcher = title_matcher.as_ref()?;
+                    let (name, distance) = matcher.best_match(img)?;
+                    if ArtifactSetKey::from_lang(lang, text).is_some() {
+                        if is_verbose && name != text && distance <= matcher.max_distance {
+                            warn!("title cross-check: {} / {} ({:.3})", text, name, distance);
+                        }
+                        return None;
                     }
-                    let inference_result = model.inference_string(&processed_img);
-                    if is_dump_mode {
-                        fs::write(format!("dumps/{}_{}.txt", name, cnt), &inference_result)
-                            .expect("Err");
+                    if distance > matcher.max_distance {
+                        return None;
                     }
-
-                    inference_result
+                    info!("title by template: {} -> {} ({:.3})", text, name, distance);
+                    Some(name)
                 };
 
-                let str_title = model_inference(&info.title_position, "title", cnt);
-                let str_main_stat_name =
-                    model_inference(&info.main_stat_name_position, "main_stat_name", cnt);
-                let str_main_stat_value =
-                    model_inference(&info.main_stat_value_position, "main_stat_value", cnt);
+                let recognize = |processed_img: &RawImage, kind: FieldKind| {
+                    let inference_result = match kind {
+                        FieldKind::Number => digit_inference(processed_img, kind, None),
+                        _ => None,
+                    };
+                    match (inference_result, kind) {
+                        (Some(v), _) => v,
+                        (None, FieldKind::NumberSuffix) => {
+                            let s = model.inference_string(processed_img);
+                            digit_inference(processed_img, kind, Some(&s)).unwrap_or(s)
+                        }
+                        (None, FieldKind::Title) => {
+                            let s = model.inference_string(processed_img);
+                            title_inference(processed_img, &s).unwrap_or(s)
+                        }
+                        (None, _) => model.inference_string(processed_img),
+                    }
+                };
 
-                let str_sub_stat_1 = model_inference(&info.sub_stat1_position, "sub_stat_1", cnt);
-                let str_sub_stat_2 = model_inference(&info.sub_stat2_position, "sub_stat_2", cnt);
-                let str_sub_stat_3 = model_inference(&info.sub_stat3_position, "sub_stat_3", cnt);
-                let str_sub_stat_4 = model_inference(&info.sub_stat4_position, "sub_stat_4", cnt);
+                let model_inference =
+                    |pos: &PixelRectBound, name: &str, cnt: usize, kind: FieldKind| {
+                        let raw_img = capture.crop_to_raw_img(&convert_rect(pos));
+                        if is_dump_mode {
+                            raw_img
+                                .grayscale_to_gray_image()
+                                .save(format!("dumps/{}_{}.png", name, cnt))
+                                .expect("Err");
+                        }
+
+                        let processed_img = pre_process(raw_img);
+                        if is_dump_mode {
+                            processed_img
+                                .to_gray_image()
+                                .save(format!("dumps/p_{}_{}.png", name, cnt))
+                                .expect("Err");
+                        }
+                        let inference_result = recognize(&processed_img, kind);
+                        if is_dump_mode {
+                            fs::write(format!("dumps/{}_{}.txt", name, cnt), &inference_result)
+                                .expect("Err");
+                        }
+
+                        inference_result
+                    };
+
+                // Same field cropped a few pixels off, for fields the rules reject
+                let re_inference = |pos: &PixelRectBound, dx: i32, dy: i32, kind: FieldKind| {
+                    let shifted = PixelRectBound {
+                        left: pos.left + dx,
+                        top: pos.top + dy,
+                        right: pos.right + dx,
+                        bottom: pos.bottom + dy,
+                    };
+                    let processed_img =
+                        pre_process(capture.crop_to_raw_img(&convert_rect(&shifted)));
+                    recognize(&processed_img, kind)
+                };
 
-                let str_level = model_inference(&info.level_position, "level", cnt);
-                let str_equip = model_inference(&info.equip_position, "equip", cnt);
+                let field_position = |field: &str| match field {
+                    "title" => Some((&info.title_position, FieldKind::Title)),
+                    "main_stat_name" => Some((&info.main_stat_name_position, FieldKind::Text)),
+                    "sub_stat_1" => Some((&info.sub_stat1_position, FieldKind::NumberSuffix)),
+                    "sub_stat_2" => Some((&info.sub_stat2_position, FieldKind::NumberSuffix)),
+                    "sub_stat_3" => Some((&info.sub_stat3_position, FieldKind::NumberSuffix)),
+                    "sub_stat_4" => Some((&info.sub_stat4_position, FieldKind::NumberSuffix)),
+                    "level" => Some((&info.level_position, FieldKind::Number)),
+                    _ => None,
+                };
 
-                cnt += 1;
+                let str_title =
+                    model_inference(&info.title_position, "title", cnt, FieldKind::Title);
+
+                let str_main_stat_name = model_inference(
+                    &info.main_stat_name_position,
+                    "main_stat_name",
+                    cnt,
+                    FieldKind::Text,
+                );
+                let str_main_stat_value = model_inference(
+                    &info.main_stat_value_position,
+                    "main_stat_value",
+                    cnt,
+                    FieldKind::Number,
+                );
+
+                let str_sub_stat_1 = model_inference(
+                    &info.sub_stat1_position,
+                    "sub_stat_1",
+                    cnt,
+                    FieldKind::NumberSuffix,
+                );
+                let str_sub_stat_2 = model_inference(
+                    &info.sub_stat2_position,
+                    "sub_stat_2",
+                    cnt,
+                    FieldKind::NumberSuffix,
+                );
+                let str_sub_stat_3 = model_inference(
+                    &info.sub_stat3_position,
+                    "sub_stat_3",
+                    cnt,
+                    FieldKind::NumberSuffix,
+                );
+                let str_sub_stat_4 = model_inference(
+                    &info.sub_stat4_position,
+                    "sub_stat_4",
+                    cnt,
+                    FieldKind::NumberSuffix,
+                );
+
+                let str_level =
+                    model_inference(&info.level_position, "level", cnt, FieldKind::Number);
+                let str_equip =
+                    model_inference(&info.equip_position, "equip", cnt, FieldKind::Text);
+
+                let index = cnt as u32;
+                report.scanned += 1;
 
                 // let predict_time = now.elapsed().unwrap().as_millis();
                 // println!("predict time: {}ms", predict_time);
 
-                let result = YasScanResult {
+                let mut result = YasScanResult {
                     name: str_title,
                     main_stat_name: str_main_stat_name,
                     main_stat_value: str_main_stat_value,
@@ -614,164 +1306,775 @@ impl YasScanner {
                     info!("{:?}", result);
                 }
                 // println!("{:?}", result);
-                let art = result.to_internal_artifact();
-                if let Some(a) = art {
-                    if hash.contains(&a) {
-                        dup_count += 1;
-                        consecutive_dup_count += 1;
-                        warn!("dup artifact detected: {:?}", result);
-                    } else {
-                        consecutive_dup_count = 0;
-                        hash.insert(a.clone());
-                        // results.push(a);
+                for (field, raw, m) in result.resolve_names(lang).iter() {
+                    if m.ambiguous {
+                        warn!(
+                            "ambiguous {} of #{}: {} -> {} or {:?}",
+                            field, index, raw, m.name, m.runner_up
+                        );
+                    } else if is_verbose {
+                        info!("{} of #{}: {} -> {}", field, index, raw, m.name);
                     }
-                    results.push(a);
-                } else {
-                    error!("wrong detection: {:?}", result);
-                    error_count += 1;
-                    // println!("error parsing results");
+                    report.add_name_match(index, field, raw, m);
                 }
+                let mut art = result.to_internal_artifact(lang);
+
+                // Fields contradicting the rules are read again from slightly shifted crops,
+                // the first variant that satisfies every rule is kept
+                let (mut diagnostics, fields) = match &art {
+                    Ok((a, _, unreadable)) => {
+                        let mut d = unreadable.clone();
+                        d.extend(check_artifact(a));
+                        let f = suspect_fields(a, &d);
+                        (d, f)
+                    }
+                    Err(_) => (Vec::new(), Vec::new()),
+                };
+                for &(dx, dy) in RECHECK_OFFSETS.iter() {
+                    if fields.is_empty() {
+                        break;
+                    }
+                    let mut candidate = result.clone();
+                    for field in fields.iter() {
+                        if let Some((pos, kind)) = field_position(field) {
+                            *candidate.field_mut(field).unwrap() = re_inference(pos, dx, dy, kind);
+                        }
+                    }
+                    let candidate_matches = candidate.resolve_names(lang);
+                    let candidate_art = match candidate.to_internal_artifact(lang) {
+                        Ok(v) => v,
+                        Err(_) => continue,
+                    };
+                    if !candidate_art.2.is_empty() || !check_artifact(&candidate_art.0).is_empty() {
+                        continue;
+                    }
+
+                    for field in fields.iter() {
+                        let before = result.field_mut(field).unwrap().clone();
+                        let after = candidate.field_mut(field).unwrap().clone();
+                        if before != after {
+                            info!(
+                                "re-recognized {} of #{}: {} -> {}",
+                                field, index, before, after
+                            );
+                            report.add_re_recognition(index, field, before, after);
+                        }
+                    }
+                    for (field, raw, m) in candidate_matches.iter() {
+                        report.add_name_match(index, field, raw, m);
+                    }
+                    result = candidate;
+
+                    art = Ok(candidate_art);
+                    diagnostics.clear();
+                    break;
+                }
+                if !diagnostics.is_empty() {
+                    let messages: Vec<String> = diagnostics.iter().map(|d| d.message()).collect();
+                    warn!("#{} needs review: {}", index, messages.join(", "));
+                    report.add_review(index, diagnostics);
+                }
+
+                match art {
+                    Ok((mut a, main_stat_check, _)) => {
+                        if main_stat_check != MainStatCheck::Valid {
+                            warn!("main stat of #{}: {:?}", index, main_stat_check);
+                            report.add_main_stat_check(index, &main_stat_check);
+                        }
+                        let corrections = validate_substats(&mut a);
+
+                        for c in corrections.iter() {
+                            warn!(
+                                "substat {} of #{}: {:?} {:?}",
+                                c.field, index, c.key, c.check
+                            );
+                        }
+                        report.add_substat_corrections(index, &corrections);
+                        report.recognized += 1;
+
+                        if hash.contains(&a) {
+                            report.duplicates += 1;
+                            consecutive_dup_count += 1;
+                            warn!("dup artifact detected: {:?}", result);
+                        } else {
+                            consecutive_dup_count = 0;
+                            hash.insert(a.clone());
+                            // results.push(a);
+                        }
+                        results.push(a);
+                    }
+                    Err(e) => {
+                        error!("wrong detection: {}, {:?}", e.message(), result);
+                        report.add_parse_error(index, e);
+                        // println!("error parsing results");
+                    }
+                }
+
                 if consecutive_dup_count >= info.art_row {
                     error!("检测到连续多个重复圣遗物，可能为翻页错误，或者为非背包顶部开始扫描");
                     break;
                 }
             }
 
-            info!("error count: {}", error_count);
-            info!("dup count: {}", dup_count);
+            report.print_summary();
 
-            results
+            (results, report)
         });
 
-        let mut scanned_row = 0_u32;
-        let mut scanned_count = 0_u32;
-        let mut start_row = 0_u32;
-        let mut lock = false;
+        self.send_panels(count, self.config.min_star.unwrap_or(4), &tx);
 
-        self.move_to(0, 0);
-        self.enigo.mouse_click(MouseButton::Left);
-        utils::sleep(1000);
-        self.sample_initial_color();
+        tx.send(None).unwrap();
 
-        'outer: while scanned_count < count {
-            for row in start_row..self.row {
-                let c = if scanned_row == total_row - 1 {
-                    last_row_col
-                } else {
-                    self.col
-                };
-                for col in 0..c {
-                    // 大于最大数量则退出
-                    if scanned_count > count {
-                        break 'outer;
-                    }
+        info!("扫描结束，等待识别线程结束，请勿关闭程序");
+        let (results, mut report) = handle.join().unwrap();
+        info!("count: {}", results.len());
+        // only a count read from the bag, the fallback above is made up
+        if self.config.number == 0 {
+            report.item_count = art_count.ok();
+        }
+        (results, report)
+    }
 
-                    // 右键终止
-                    if utils::is_rmb_down() {
-                        break 'outer;
-                    }
+    // Goes through the character screen, opened on any character, with the next button
+    // until that character comes round again
+    pub fn scan_characters(&mut self) -> (Vec<InternalCharacter>, ScanReport) {
+        let mut results: Vec<InternalCharacter> = Vec::new();
+        let mut report = ScanReport::new();
+        let layout = self.info.character.clone();
+        let lang = self.config.lang;
 
-                    self.move_to(row, col);
-                    self.enigo.mouse_click(MouseButton::Left);
+        let mut first: Option<String> = None;
+        for index in 0..game_data().characters.len() as u32 {
+            // 右键终止
+            if utils::is_rmb_down() {
+                break;
+            }
 
-                    // self.wait_until_switched();
-                    utils::sleep(80);
+            self.click_at(layout.attribute_tab);
+            utils::sleep(500);
+            let name = self.read_text(&layout.name_position);
+            match first {
+                Some(ref v) if *v == name => break,
+                None => first = Some(name.clone()),
+                _ => (),
+            }
+            let level = self.read_text(&layout.level_position);
+
+            self.click_at(layout.constellation_tab);
+            // the constellations fade in
+            utils::sleep(1000);
+            let constellation = self.get_constellation();
+
+            self.click_at(layout.talent_tab);
+            utils::sleep(500);
+            let talents = [
+                self.read_text(&layout.talent_positions[0]),
+                self.read_text(&layout.talent_positions[1]),
+                self.read_text(&layout.talent_positions[2]),
+            ];
+
+            let mut result = CharacterScanResult {
+                name,
+                level,
+                constellation,
+                talents,
+            };
+            report.scanned += 1;
+            if self.config.verbose {
+                info!("{:?}", result);
+            }
 
-                    let capture = self.capture_panel().unwrap();
-                    let star = self.get_star();
-                    if star < self.config.min_star {
-                        break 'outer;
+            let raw = String::from(result.name.trim());
+            if let Some(m) = lang.resolve_character(&raw) {
+                if m.distance > 0.0 {
+                    if m.ambiguous {
+                        warn!(
+                            "ambiguous name of #{}: {} -> {} or {:?}",
+                            index, raw, m.name, m.runner_up
+                        );
+                    } else {
+                        result.name = String::from(m.name);
                     }
-                    lock = self.get_lock(lock);
-                    // info!("locked: {}", lock);
-                    tx.send(Some((capture, star, lock))).unwrap();
+                    report.add_name_match(index, "name", &raw, &m);
+                }
+            }
 
-                    scanned_count += 1;
-                } // end 'col
+            match result.to_internal_character(lang) {
+                Ok(c) => {
+                    report.recognized += 1;
+                    results.push(c);
+                }
+                Err(e) => {
+                    error!("wrong detection: {}, {:?}", e.message(), result);
+                    report.add_parse_error(index, e);
+                }
+            }
 
-                scanned_row += 1;
+            self.click_at(layout.next_character);
+            utils::sleep(800);
+        }
 
-                if scanned_row >= self.config.max_row {
-                    info!("max row reached, quiting...");
-                    break 'outer;
+        report.print_summary();
+        info!("count: {}", results.len());
+        (results, report)
+    }
+
+    // Weapons are laid out in the same grid as artifacts, only the panel differs
+    pub fn scan_weapons(&mut self) -> (Vec<InternalWeapon>, ScanReport) {
+        if self.config.capture_only {
+            self.start_capture_only();
+            return (Vec::new(), ScanReport::new());
+        }
+
+        let count = self.get_item_count("武器").unwrap_or(2000);
+
+        let (tx, rx) = mpsc::channel::<Option<(RawCaptureImage, u32, bool)>>();
+        let info = self.info.clone();
+        let is_verbose = self.config.verbose;
+        let is_dump_mode = self.config.dump_mode;
+        let lang = self.config.lang;
+        let handle = thread::spawn(move || {
+            let mut results: Vec<InternalWeapon> = Vec::new();
+            let model = CRNNModel::new(
+                String::from("model_training.onnx"),
+                String::from("index_2_word.json"),
+            );
+            let mut report = ScanReport::new();
+            if is_dump_mode {
+                fs::create_dir("dumps").expect("Err");
+            }
+
+            let convert_rect = |rect: &PixelRectBound| PixelRect {
+                left: rect.left - info.panel_position.left,
+                top: rect.top - info.panel_position.top,
+                width: rect.right - rect.left,
+                height: rect.bottom - rect.top,
+            };
+
+            for (cnt, i) in rx.into_iter().enumerate() {
+                let (capture, rarity, lock) = match i {
+                    Some(v) => v,
+                    None => break,
+                };
+
+                let model_inference = |pos: &PixelRectBound, name: &str| {
+                    let processed_img = pre_process(capture.crop_to_raw_img(&convert_rect(pos)));
+                    if is_dump_mode {
+                        processed_img
+                            .to_gray_image()
+                            .save(format!("dumps/p_weapon_{}_{}.png", name, cnt))
+                            .expect("Err");
+                    }
+                    model.inference_string(&processed_img)
+                };
+
+                let mut result = WeaponScanResult {
+                    name: model_inference(&info.title_position, "title"),
+                    level: model_inference(&info.weapon_level_position, "level"),
+                    refinement: model_inference(&info.refinement_position, "refinement"),
+                    location: model_inference(&info.equip_position, "equip"),
+                    rarity,
+                    lock,
+                };
+                let index = cnt as u32;
+                report.scanned += 1;
+                if is_verbose {
+                    info!("{:?}", result);
                 }
-            } // end 'row
 
-            let remain = count - scanned_count;
-            let remain_row = (remain + self.col - 1) / self.col;
-            let scroll_row = remain_row.min(self.row);
-            start_row = self.row - scroll_row;
-            match self.scroll_rows(scroll_row) {
-                ScrollResult::TLE => {
-                    error!("翻页出现问题");
-                    break 'outer;
+                for (field, raw, m) in result.resolve_names(lang).iter() {
+                    if m.ambiguous {
+                        warn!(
+                            "ambiguous {} of #{}: {} -> {} or {:?}",
+                            field, index, raw, m.name, m.runner_up
+                        );
+                    }
+                    report.add_name_match(index, field, raw, m);
+                }
+
+                // identical weapons are common, so duplicates say nothing about paging here
+                match result.to_internal_weapon(lang) {
+                    Ok(w) => {
+                        report.recognized += 1;
+                        results.push(w);
+                    }
+                    Err(e) => {
+                        error!("wrong detection: {}, {:?}", e.message(), result);
+                        report.add_parse_error(index, e);
+                    }
                 }
-                ScrollResult::Interrupt => break 'outer,
-                _ => (),
             }
 
-            utils::sleep(100);
-        }
+            report.print_summary();
+
+            (results, report)
+        });
+
+        self.send_panels(count, self.config.min_star.unwrap_or(1), &tx);
 
         tx.send(None).unwrap();
 
         info!("扫描结束，等待识别线程结束，请勿关闭程序");
-        let results: Vec<InternalArtifact> = handle.join().unwrap();
+        let (results, report) = handle.join().unwrap();
         info!("count: {}", results.len());
-        results
+        (results, report)
     }
-    pub fn flip_lock(&mut self, indices: Vec<u32>) {
-        let mut indices = indices;
-        indices.sort();
 
-        let count = match self.get_art_count() {
-            Ok(v) => v,
-            Err(_) => 1000,
+    // Goes through both material tabs, the bag opened on either. Materials stack so each key
+    // shows up once, and no total is shown above the grid, a tab ends at the first empty
+    // cell or once a whole page brings nothing new.
+    pub fn scan_materials(&mut self, icons: &IconMatcher) -> (Vec<(String, u32)>, ScanReport) {
+        let mut results: Vec<(String, u32)> = Vec::new();
+        let mut report = ScanReport::new();
+        let is_dump_mode = self.config.dump_mode;
+        if is_dump_mode {
+            fs::create_dir("dumps").expect("Err");
+        }
+
+        let count = match self.config.number {
+            0 => None,
+            v => Some(v),
         };
-        if indices[indices.len() - 1] > count {
+        let page_size = self.row * self.col;
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut cnt = 0;
+
+        let tabs = self.info.material_tabs;
+        for &tab in tabs.iter() {
+            // 右键终止
+            if utils::is_rmb_down() {
+                break;
+            }
+
+            self.click_at(tab);
+            utils::sleep(500);
+
+            let mut repeated = 0;
+            self.walk_grid(count, |scanner, row, col| {
+                let (icon, count_img) = match scanner.capture_cell(row, col) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("{}", e);
+                        return false;
+                    }
+                };
+                let text = scanner.read_number(&count_img);
+                if is_dump_mode {
+                    icon.save(format!("dumps/material_{}.png", cnt))
+                        .expect("Err");
+                    count_img
+                        .to_gray_image()
+                        .save(format!("dumps/p_material_count_{}.png", cnt))
+                        .expect("Err");
+                }
+                let index = cnt;
+                cnt += 1;
+
+                // the items are packed from the top, the rest of the tab is empty as well
+                if is_blank(&icon) {
+                    return false;
+                }
+
+                let (key, distance) = match icons.best_match(&icon) {
+                    Some(v) => v,
+                    None => return false,
+                };
+                if distance > icons.max_distance {
+                    report.scanned += 1;
+                    warn!(
+                        "unknown material #{}: {} ({:.3}), count {}",
+                        index, key, distance, text
+                    );
+                    return true;
+                }
+                if !seen.insert(key.clone()) {
+                    repeated += 1;
+                    return repeated < page_size;
+                }
+                repeated = 0;
+
+                report.scanned += 1;
+                let digits: String = text.chars().filter(|c| c.is_ascii_digit()).collect();
+                let amount = match digits.parse::<u32>() {
+                    Ok(v) => v,
+                    Err(_) => {
+                        error!("count of #{} {}: {}", index, key, text);
+                        let error =
+                            ParseError::new("count", ParseErrorReason::MalformedNumber, &text);
+                        report.add_parse_error(index, error);
+                        return true;
+                    }
+                };
+                report.recognized += 1;
+                if scanner.config.verbose {
+                    info!("{}: {} ({:.3})", key, amount, distance);
+                }
+                results.push((key, amount));
+                true
+            });
+        }
+
+        report.print_summary();
+        info!("count: {}", results.len());
+        (results, report)
+    }
+
+    // Selects the item at `row` and `col` on screen and clicks its lock until the state read
+    // back is the one wanted, for a toggle the opposite of the first state read. Nothing is
+    // clicked when it is already there, so running a lock file twice does no harm. The waits
+    // double with each attempt in case the game lags.
+    fn flip_selected(&mut self, row: u32, col: u32, target: &LockTarget) -> FlipOutcome {
+        let mut goal: Option<bool> = None;
+        let mut mismatch: Option<String> = None;
+        let mut clicks = 0;
+        for attempt in 0..FLIP_ATTEMPTS {
+            let wait = 100 << attempt;
+            self.move_to(row, col);
+            self.enigo.mouse_click(MouseButton::Left);
+            utils::sleep(wait);
+
+            let state = match self.read_lock() {
+                Some(v) => v,
+                None => continue,
+            };
+            if let Some(title) = &target.title {
+                let text = self.read_text(&self.info.title_position);
+                if !title_matches(&text, title, self.config.lang) {
+                    // the panel may not have switched yet
+                    mismatch = Some(text);
+                    continue;
+                }
+                mismatch = None;
+            }
+            let wanted = match target.action {
+                Some(action) => action == LockAction::Lock,
+                None => !state,
+            };
+            let wanted = *goal.get_or_insert(wanted);
+            if state == wanted {
+                // a click of an earlier attempt may have landed late
+                return match clicks {
+                    0 => FlipOutcome::AlreadySet,
+                    _ => FlipOutcome::Flipped { attempts: clicks },
+                };
+            }
+            if self.config.dry_run {
+                return FlipOutcome::WouldFlip;
+            }
+
+            let left: i32 = self.info.left + self.info.lock_x as i32;
+            let top: i32 = self.info.top + self.info.lock_y as i32;
+            self.enigo.mouse_move_to(left, top);
+            self.enigo.mouse_click(MouseButton::Left);
+            clicks += 1;
+            utils::sleep(wait);
+
+            if self.read_lock() == Some(wanted) {
+                return FlipOutcome::Flipped { attempts: clicks };
+            }
+        }
+
+        match (mismatch, goal) {
+            (Some(text), _) => FlipOutcome::TitleMismatch(text),
+            (None, None) => FlipOutcome::UnknownState,
+            (None, Some(_)) => FlipOutcome::NotChanged,
+        }
+    }
+
+    pub fn flip_lock(&mut self, targets: Vec<LockTarget>) -> FlipReport {
+        let mut targets = targets;
+        targets.sort_by_key(|t| t.index);
+        let mut report = FlipReport::new();
+
+        if targets.is_empty() {
+            info!("lock文件中没有目标");
+            return report;
+        }
+
+        let count = self.get_art_count().unwrap_or(1000);
+        if targets[targets.len() - 1].index >= count {
             error!("指标超出范围");
-            return;
+            return report;
         }
         self.sample_initial_color();
 
-        let total_row = (count + self.col - 1) / self.col;
-        let mut scanned_row = 0_u32;
-        let mut start_row = 0_u32;
+        let mut scrolled_rows = 0_u32;
+        let mut is_stopped = false;
 
-        for index in indices {
-            let row: u32 = index / self.col;
-            let col: u32 = index % self.col;
-            while row >= scanned_row + self.row {
-                scanned_row += self.row;
-                let remain_row = total_row - scanned_row;
-                let scroll_row = remain_row.min(self.row);
-                start_row = self.row - scroll_row;
-                match self.scroll_rows(scroll_row) {
-                    ScrollResult::TLE => {
+        for target in targets.iter() {
+            if is_stopped {
+                report.add(target.describe(), FlipOutcome::NotReached);
+                continue;
+            }
+
+            let pos = grid_position(target.index, count, self.row, self.col);
+            if pos.scrolled_rows > scrolled_rows {
+                match self.scroll_rows(pos.scrolled_rows - scrolled_rows) {
+                    ScrollResult::Tle => {
                         error!("翻页出现问题");
-                        break;
+                        is_stopped = true;
                     }
-                    ScrollResult::Interrupt => break,
-                    _ => (),
+                    ScrollResult::Interrupt => is_stopped = true,
+                    _ => scrolled_rows = pos.scrolled_rows,
                 }
             }
             // 右键终止
             if utils::is_rmb_down() {
-                break;
+                is_stopped = true;
+            }
+            if is_stopped {
+                report.add(target.describe(), FlipOutcome::NotReached);
+                continue;
             }
             // info!("{} {} {}", index, row, col);
 
-            self.move_to(row - scanned_row + start_row, col);
-            self.enigo.mouse_click(MouseButton::Left);
-            // self.wait_until_switched();
-            utils::sleep(100);
+            let outcome = self.flip_selected(pos.row, pos.col, target);
+            if !outcome.is_resolved() {
+                warn!("#{}: {}", target.index, outcome.describe());
+            }
+            report.add(target.describe(), outcome);
+            self.move_to(pos.row, pos.col);
+        }
 
-            let left: i32 = self.info.left + self.info.lock_x as i32;
-            let top: i32 = self.info.top + self.info.lock_y as i32;
-            self.enigo.mouse_move_to(left, top);
+        report
+    }
+
+    // Reads the bag count and a few of the targets back before anything is clicked, and returns
+    // what no longer matches the scan the lock file was planned on. `source_count` is the bag
+    // count stamped in the lock file. Ends back at the top of the grid.
+    pub fn check_lock_targets(
+        &mut self,
+        targets: &[LockTarget],
+        source_count: Option<u32>,
+    ) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+        let count = match self.get_art_count() {
+            Ok(v) => v,
+            Err(_) => {
+                problems.push(String::from("无法识别圣遗物数量"));
+                return problems;
+            }
+        };
+        if let Some(source_count) = source_count {
+            if source_count != count {
+                problems.push(format!(
+                    "圣遗物数量为 {}，生成lock文件时为 {}",
+                    count, source_count
+                ));
+            }
+        }
+        if let Some(target) = targets.iter().find(|t| t.index >= count) {
+            problems.push(format!(
+                "{} 超出背包范围（共 {} 件）",
+                target.describe(),
+                count
+            ));
+            return problems;
+        }
+
+        let sample = verify_sample(targets, VERIFY_SAMPLE);
+        if sample.is_empty() {
+            problems.push(String::from(
+                "没有可以核对的目标：lock文件中没有指纹，也没有上次扫描的结果",
+            ));
+            return problems;
+        }
+        info!("核对 {} 个目标", sample.len());
+        self.sample_initial_color();
+
+        let mut scrolled_rows = 0_u32;
+        let mut lock = false;
+        for target in sample {
+            let pos = grid_position(target.index, count, self.row, self.col);
+            if pos.scrolled_rows > scrolled_rows {
+                match self.scroll_rows(pos.scrolled_rows - scrolled_rows) {
+                    ScrollResult::Tle | ScrollResult::Interrupt => {
+                        problems.push(format!("{} 未到达", target.describe()));
+                        break;
+                    }
+                    _ => scrolled_rows = pos.scrolled_rows,
+                }
+            }
+
+            self.move_to(pos.row, pos.col);
             self.enigo.mouse_click(MouseButton::Left);
-            utils::sleep(100);
-            self.move_to(row - scanned_row + start_row, col);
+            utils::sleep(300);
+            let expected = match target.fingerprint {
+                Some(ref v) => v,
+                None => {
+                    // only the title from the last scan is known
+                    let title = target.title.as_deref().unwrap_or_default();
+                    let text = self.read_text(&self.info.title_position);
+                    if !title_matches(&text, title, self.config.lang) {
+                        problems.push(format!(
+                            "{} 与上次扫描不符，现为 {}",
+                            target.describe(),
+                            text.trim()
+                        ));
+                    }
+                    continue;
+                }
+            };
+            let capture = self.capture_panel().unwrap();
+            let rarity = self.get_star();
+            lock = self.get_lock(lock);
+            match self.read_artifact(&capture, rarity, lock) {
+                Ok(art) => {
+                    if *expected != fingerprint(&art) {
+                        problems.push(format!(
+                            "{} 与lock文件不符，现为 {} {}★+{}",
+                            target.describe(),
+                            art.display_name(),
+                            art.rarity,
+                            art.level
+                        ));
+                    }
+                }
+                Err(e) => problems.push(format!("{} 无法识别: {}", target.describe(), e.message())),
+            }
+        }
+
+        self.scroll_to_top(scrolled_rows);
+        problems
+    }
+
+    // Reads the artifact in a panel capture on this thread, without the re-recognition of
+    // suspect fields `scan` does but with the same corrections
+    fn read_artifact(
+        &self,
+        capture: &RawCaptureImage,
+        rarity: u32,
+        lock: bool,
+    ) -> Result<InternalArtifact, ParseError> {
+        let info = &self.info;
+        let read = |pos: &PixelRectBound, kind: FieldKind| {
+            let rect = PixelRect {
+                left: pos.left - info.panel_position.left,
+                top: pos.top - info.panel_position.top,
+                width: pos.right - pos.left,
+                height: pos.bottom - pos.top,
+            };
+            let img = pre_process(capture.crop_to_raw_img(&rect));
+            match kind {
+                FieldKind::Number => self.read_number(&img),
+                _ => self.model.inference_string(&img),
+            }
+        };
+
+        let mut result = YasScanResult {
+            name: read(&info.title_position, FieldKind::Title),
+            main_stat_name: read(&info.main_stat_name_position, FieldKind::Text),
+            main_stat_value: read(&info.main_stat_value_position, FieldKind::Number),
+            sub_stat_1: read(&info.sub_stat1_position, FieldKind::NumberSuffix),
+            sub_stat_2: read(&info.sub_stat2_position, FieldKind::NumberSuffix),
+            sub_stat_3: read(&info.sub_stat3_position, FieldKind::NumberSuffix),
+            sub_stat_4: read(&info.sub_stat4_position, FieldKind::NumberSuffix),
+            level: read(&info.level_position, FieldKind::Number),
+            location: read(&info.equip_position, FieldKind::Text),
+            rarity,
+            lock,
+        };
+        result.exported_artifact(self.config.lang)
+    }
+
+    // Walks the bag reading every artifact and sets the lock of those matching an entry by
+    // fingerprint or content, so a lock file stays valid after the bag changed. Each entry is
+    // used for the first artifact it matches, entries never matched are reported as not found.
+    pub fn lock_by_identity(&mut self, entries: &[LockEntry]) -> FlipReport {
+        let mut report = FlipReport::new();
+        let count = self.get_art_count().unwrap_or(1000);
+
+        let mut found = vec![false; entries.len()];
+        let mut index = 0_u32;
+        let mut lock = false;
+        self.walk_grid(Some(count), |scanner, row, col| {
+            let capture = scanner.capture_panel().unwrap();
+            let rarity = scanner.get_star();
+            lock = scanner.get_lock(lock);
+            let art = scanner.read_artifact(&capture, rarity, lock);
+            let current = index;
+            index += 1;
+
+            let art = match art {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("#{}: {}", current, e.message());
+                    return true;
+                }
+            };
+            let art_fingerprint = fingerprint(&art);
+            let matched = entries
+                .iter()
+                .enumerate()
+                .position(|(i, entry)| !found[i] && entry.matches(&art, &art_fingerprint));
+            if let Some(i) = matched {
+                found[i] = true;
+                let target = LockTarget {
+                    index: current,
+                    action: entries[i].action,
+                    title: None,
+                    current: Some(art.lock),
+                    fingerprint: Some(art_fingerprint.clone()),
+                };
+                info!("{} -> #{}", entries[i].describe(), current);
+                let outcome = scanner.flip_selected(row, col, &target);
+                report.add(
+                    format!("{} {}", target.describe(), entries[i].describe()),
+                    outcome,
+                );
+            }
+
+            found.iter().any(|f| !f)
+        });
+
+        for (entry, _) in entries.iter().zip(found.iter()).filter(|(_, f)| !**f) {
+            report.add(entry.describe(), FlipOutcome::NotFound);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expo::good::{read_good_artifacts, GoodFormat};
+
+    fn scan_result(sub_stat_1: &str) -> YasScanResult {
+        YasScanResult {
+            name: String::from("角斗士的留恋"),
+            main_stat_name: String::from("生命值"),
+            main_stat_value: String::from("4,780"),
+            sub_stat_1: String::from(sub_stat_1),
+            sub_stat_2: String::from("攻击力+5.8%"),
+            sub_stat_3: String::from("元素精通+23"),
+            sub_stat_4: String::from("暴击伤害+7.8%"),
+            level: String::from("+20"),
+            location: String::new(),
+            rarity: 5,
+            lock: false,
+        }
+    }
+
+    // What a lock file planned on the good.json of `scan` holds for the artifact
+    fn exported_fingerprint(result: &YasScanResult) -> String {
+        let (mut art, _, _) = result.to_internal_artifact(Language::ZhCn).unwrap();
+        validate_substats(&mut art);
+        let arts = vec![art];
+        let json = serde_json::to_string(&GoodFormat::new(&arts)).unwrap();
+        fingerprint(&read_good_artifacts(&json).unwrap()[0])
+    }
+
+    #[test]
+    fn corrected_artifact_fingerprints_as_exported() {
+        // snapped to 3.9 and restored to 3.9
+        for sub in ["暴击率+3.8%", "暴击率+39%"].iter() {
+            let mut result = scan_result(sub);
+            let (raw, _, _) = result.to_internal_artifact(Language::ZhCn).unwrap();
+            let read = result.exported_artifact(Language::ZhCn).unwrap();
+
+            assert_eq!(read.sub_stat_1.as_ref().unwrap().value, 3.9);
+            assert_ne!(fingerprint(&raw), exported_fingerprint(&result));
+            assert_eq!(fingerprint(&read), exported_fingerprint(&result));
         }
     }
 }
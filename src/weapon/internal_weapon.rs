@@ -0,0 +1,88 @@
+use crate::artifact::game_data::game_data;
+use crate::artifact::internal_artifact::CharacterKey;
+use crate::artifact::lang::Language;
+
+// Level cap after each ascension, the first one before any
+const LEVEL_CAPS: [u32; 7] = [20, 40, 50, 60, 70, 80, 90];
+
+// Keys are defined by the game data file (see `game_data`), e.g. "MistsplitterReforged"
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct WeaponKey(pub &'static str);
+
+#[derive(Debug, Hash, Clone, PartialEq, Eq)]
+pub struct InternalWeapon {
+    pub key: WeaponKey,
+    pub level: u32,
+    // 0 to 6
+    pub ascension: u32,
+    // 1 to 5
+    pub refinement: u32,
+    pub lock: bool,
+    pub location: Option<CharacterKey>,
+}
+
+impl InternalWeapon {
+    // Chinese name, e.g. "雾切之回光", for logs and reports
+    pub fn display_name(&self) -> String {
+        let name = game_data()
+            .weapon(&self.key)
+            .and_then(|w| w.names(Language::ZhCn).first().cloned());
+        match name {
+            Some(v) => v,
+            None => String::from(self.key.0),
+        }
+    }
+}
+
+impl WeaponKey {
+    pub fn from_lang(lang: Language, s: &str) -> Option<WeaponKey> {
+        game_data().find_weapon(lang, s)
+    }
+
+    // Closest weapon name, None if there is none or the match is ambiguous
+    pub fn from_zh_cn(s: &str) -> Option<WeaponKey> {
+        let m = Language::ZhCn.resolve_weapon(s)?;
+        if m.ambiguous {
+            return None;
+        }
+        WeaponKey::from_lang(Language::ZhCn, m.name)
+    }
+}
+
+// Level and ascension out of a level line, e.g. "Lv.80/90" -> (80, 6). The cap tells the
+// ascension, which needs the previous cap reached first. Characters have the same caps.
+pub fn parse_level_and_ascension(s: &str) -> Option<(u32, u32)> {
+    let pos = s.find('/')?;
+    let digits = |part: &str| -> Option<u32> {
+        let d: String = part
+            .trim()
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        d.chars().rev().collect::<String>().parse::<u32>().ok()
+    };
+    let level = digits(&s[..pos])?;
+    let cap = digits(s[pos + 1..].trim_end_matches(|c: char| !c.is_ascii_digit()))?;
+
+    let ascension = LEVEL_CAPS.iter().position(|&c| c == cap)?;
+    let floor = if ascension == 0 {
+        1
+    } else {
+        LEVEL_CAPS[ascension - 1]
+    };
+    if level < floor || level > cap {
+        return None;
+    }
+
+    Some((level, ascension as u32))
+}
+
+// e.g. "精炼1阶", "Refinement Rank 1", "精錬ランク1"
+pub fn parse_refinement(s: &str) -> Option<u32> {
+    let digits: Vec<u32> = s.chars().filter_map(|c| c.to_digit(10)).collect();
+    match digits.as_slice() {
+        &[r] if (1..=5).contains(&r) => Some(r),
+        _ => None,
+    }
+}
@@ -0,0 +1 @@
+pub mod internal_weapon;
\ No newline at end of file